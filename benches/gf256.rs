@@ -0,0 +1,65 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+use networkcoding::gf256::{self, Backend};
+
+const SYMBOL_SIZES: &[usize] = &[64, 1200, 9000];
+
+/// Compares every backend supported by the CPU, the portable one being the baseline
+fn region_mul_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gf256_region_mul_add");
+    for &symbol_size in SYMBOL_SIZES {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut src = vec![0; symbol_size];
+        let mut dst = vec![0; symbol_size];
+        rng.fill_bytes(&mut src);
+        rng.fill_bytes(&mut dst);
+        group.throughput(Throughput::Bytes(symbol_size as u64));
+        for backend in Backend::supported() {
+            group.bench_function(BenchmarkId::new(backend.name(), format!("{}B", symbol_size)), |b| {
+                b.iter(|| gf256::region_mul_add_with(backend, &mut dst, &src, 0x53))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn region_mul(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gf256_region_mul");
+    for &symbol_size in SYMBOL_SIZES {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut dst = vec![0; symbol_size];
+        rng.fill_bytes(&mut dst);
+        group.throughput(Throughput::Bytes(symbol_size as u64));
+        for backend in Backend::supported() {
+            group.bench_function(BenchmarkId::new(backend.name(), format!("{}B", symbol_size)), |b| {
+                b.iter(|| gf256::region_mul_with(backend, &mut dst, 0x53))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// One elimination step over a window of 1200-byte rows
+fn eliminate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gf256_eliminate");
+    for &n_rows in &[64usize, 256, 1024, 4096] {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut pivot = vec![0; 1200];
+        rng.fill_bytes(&mut pivot);
+        let mut coefficients = vec![0; n_rows];
+        rng.fill_bytes(&mut coefficients);
+        let mut rows = vec![vec![0u8; 1200]; n_rows];
+        rows.iter_mut().for_each(|row| rng.fill_bytes(row));
+        let mut rows: Vec<&mut [u8]> = rows.iter_mut().map(|row| row.as_mut_slice()).collect();
+        group.throughput(Throughput::Bytes((n_rows * 1200) as u64));
+        group.bench_function(BenchmarkId::new("sequential", n_rows), |b| {
+            b.iter(|| gf256::eliminate(&mut rows, &pivot, &coefficients))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, region_mul_add, region_mul, eliminate);
+criterion_main!(benches);
@@ -0,0 +1,219 @@
+use std::time::Instant;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+use networkcoding::gf256::{self, Backend};
+use networkcoding::simulator::Codec;
+use networkcoding::{Decoder, DecoderError, Encoder, RepairSymbol, SourceSymbol, source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+const SYMBOL_SIZES: &[usize] = &[64, 1200, 9000];
+const WINDOW_SIZES: &[usize] = &[8, 64, 256, 1024];
+const LOSS_RATES: &[f64] = &[0.01, 0.05, 0.1, 0.2];
+/// one repair symbol is sent after every REPAIR_INTERVAL source symbols in the decoding benchmarks
+const REPAIR_INTERVAL: usize = 5;
+
+fn codecs() -> Vec<(&'static str, Codec)> {
+    vec![
+        ("vlc", Codec::VLC),
+        #[cfg(feature = "enable-rlc")]
+        ("rlc", Codec::RLC { seed: 42 }),
+    ]
+}
+
+fn random_symbol(rng: &mut StdRng, symbol_size: usize) -> Vec<u8> {
+    let mut data = vec![0; symbol_size];
+    rng.fill_bytes(&mut data);
+    data
+}
+
+/// Protects a symbol, removing the oldest one if the window is full
+fn protect(encoder: &mut Encoder, data: Vec<u8>, window_size: usize) -> SourceSymbol {
+    if encoder.n_protected_symbols() >= window_size {
+        if let Some(first) = encoder.first_metadata() {
+            encoder.remove_up_to(first);
+        }
+    }
+    let mut md = source_symbol_metadata_from_u64(0);
+    encoder.protect_data(data.clone(), &mut md).unwrap();
+    SourceSymbol::new(md, data)
+}
+
+fn full_encoder(codec: Codec, symbol_size: usize, window_size: usize, rng: &mut StdRng) -> Encoder {
+    let mut encoder = codec.new_encoder(symbol_size, window_size);
+    for _ in 0..window_size {
+        protect(&mut encoder, random_symbol(rng, symbol_size), window_size);
+    }
+    encoder
+}
+
+/// Removes the oldest symbols of the decoder window once it spans more than window_size symbols
+fn slide(decoder: &mut Decoder, window_size: usize, now: Instant) {
+    if let Some((first, last)) = decoder.bounds() {
+        let (first, last) = (source_symbol_metadata_to_u64(first), source_symbol_metadata_to_u64(last));
+        if last + 1 - first > window_size as u64 {
+            decoder.remove_up_to(source_symbol_metadata_from_u64(last - window_size as u64), Some(now));
+        }
+    }
+}
+
+fn protect_data(c: &mut Criterion) {
+    let mut group = c.benchmark_group("protect_data");
+    for (name, codec) in codecs() {
+        for &symbol_size in SYMBOL_SIZES {
+            for &window_size in WINDOW_SIZES {
+                let mut rng = StdRng::seed_from_u64(42);
+                let data = random_symbol(&mut rng, symbol_size);
+                let mut encoder = full_encoder(codec, symbol_size, window_size, &mut rng);
+                group.throughput(Throughput::Bytes(symbol_size as u64));
+                group.bench_function(BenchmarkId::new(name, format!("{}B/w{}", symbol_size, window_size)), |b| {
+                    b.iter(|| protect(&mut encoder, data.clone(), window_size))
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+fn generate_repair_symbol(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate_and_serialize_repair_symbol");
+    for (name, codec) in codecs() {
+        for &symbol_size in SYMBOL_SIZES {
+            for &window_size in WINDOW_SIZES {
+                let mut rng = StdRng::seed_from_u64(42);
+                let mut encoder = full_encoder(codec, symbol_size, window_size, &mut rng);
+                group.throughput(Throughput::Bytes(symbol_size as u64));
+                group.bench_function(BenchmarkId::new(name, format!("{}B/w{}", symbol_size, window_size)), |b| {
+                    b.iter(|| encoder.generate_and_serialize_repair_symbol().unwrap())
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+/// The linear combination of a full window that generate_and_serialize_repair_symbol computes, done with
+/// each gf256 backend supported by the CPU. The codecs do their arithmetic in their own crates, so this
+/// shows the gain they would get from the gf256 region operations on the same symbol and window sizes
+fn gf256_linear_combination(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gf256_linear_combination");
+    for &symbol_size in SYMBOL_SIZES {
+        for &window_size in WINDOW_SIZES {
+            let mut rng = StdRng::seed_from_u64(42);
+            let window: Vec<Vec<u8>> = (0..window_size).map(|_| random_symbol(&mut rng, symbol_size)).collect();
+            let coefficients: Vec<u8> = (0..window_size).map(|_| rng.gen_range(1..=255)).collect();
+            let mut repair_symbol = vec![0; symbol_size];
+            group.throughput(Throughput::Bytes(symbol_size as u64));
+            for backend in Backend::supported() {
+                group.bench_function(BenchmarkId::new(backend.name(), format!("{}B/w{}", symbol_size, window_size)), |b| {
+                    b.iter(|| {
+                        repair_symbol.fill(0);
+                        for (symbol, c) in window.iter().zip(&coefficients) {
+                            gf256::region_mul_add_with(backend, &mut repair_symbol, symbol, *c);
+                        }
+                    })
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+fn receive_source_symbol(c: &mut Criterion) {
+    let mut group = c.benchmark_group("receive_source_symbol");
+    for (name, codec) in codecs() {
+        for &symbol_size in SYMBOL_SIZES {
+            for &window_size in WINDOW_SIZES {
+                let mut rng = StdRng::seed_from_u64(42);
+                let data = random_symbol(&mut rng, symbol_size);
+                let mut decoder = codec.new_decoder(symbol_size, window_size);
+                let mut next_id = 0u64;
+                let now = Instant::now();
+                group.throughput(Throughput::Bytes(symbol_size as u64));
+                group.bench_function(BenchmarkId::new(name, format!("{}B/w{}", symbol_size, window_size)), |b| {
+                    b.iter_batched(
+                        || {
+                            next_id += 1;
+                            SourceSymbol::new(source_symbol_metadata_from_u64(next_id - 1), data.clone())
+                        },
+                        |symbol| {
+                            decoder.receive_source_symbol(symbol, now).unwrap();
+                            slide(&mut decoder, window_size, now);
+                        },
+                        BatchSize::SmallInput,
+                    )
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+enum Packet {
+    Source(SourceSymbol),
+    Repair(RepairSymbol),
+}
+
+/// Encodes 4 windows of symbols, one repair symbol every REPAIR_INTERVAL source symbols,
+/// and drops the source symbols with the given loss rate
+fn lossy_stream(codec: Codec, symbol_size: usize, window_size: usize, loss_rate: f64) -> (usize, Vec<Packet>) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut encoder = codec.new_encoder(symbol_size, window_size);
+    let n_symbols = 4 * window_size;
+    let mut packets = Vec::new();
+    for i in 0..n_symbols {
+        let symbol = protect(&mut encoder, random_symbol(&mut rng, symbol_size), window_size);
+        if !rng.gen_bool(loss_rate) {
+            packets.push(Packet::Source(symbol));
+        }
+        if (i + 1) % REPAIR_INTERVAL == 0 {
+            packets.push(Packet::Repair(encoder.generate_and_serialize_repair_symbol().unwrap()));
+        }
+    }
+    (n_symbols, packets)
+}
+
+fn receive_repair_symbol(c: &mut Criterion) {
+    let mut group = c.benchmark_group("receive_and_deserialize_repair_symbol");
+    group.sample_size(10);
+    for (name, codec) in codecs() {
+        for &symbol_size in SYMBOL_SIZES {
+            for &window_size in WINDOW_SIZES {
+                for &loss_rate in LOSS_RATES {
+                    let (n_symbols, packets) = lossy_stream(codec, symbol_size, window_size, loss_rate);
+                    group.throughput(Throughput::Bytes((n_symbols * symbol_size) as u64));
+                    let id = BenchmarkId::new(name, format!("{}B/w{}/loss{}", symbol_size, window_size, loss_rate));
+                    group.bench_function(id, |b| {
+                        b.iter_batched(
+                            || codec.new_decoder(symbol_size, window_size),
+                            |mut decoder| {
+                                let now = Instant::now();
+                                for packet in &packets {
+                                    let res = match packet {
+                                        Packet::Source(symbol) => decoder.receive_source_symbol(symbol.clone(), now),
+                                        Packet::Repair(repair_symbol) => {
+                                            let (_, repair_symbol) = decoder.read_repair_symbol(repair_symbol.get()).unwrap();
+                                            decoder.receive_and_deserialize_repair_symbol(repair_symbol).map(|(_, r)| r)
+                                        }
+                                    };
+                                    match res {
+                                        Ok(_) | Err(DecoderError::UnusedSourceSymbol) | Err(DecoderError::UnusedRepairSymbol) => (),
+                                        Err(err) => panic!("decoding error: {:?}", err),
+                                    }
+                                    slide(&mut decoder, window_size, now);
+                                }
+                                decoder
+                            },
+                            BatchSize::LargeInput,
+                        )
+                    });
+                }
+            }
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, protect_data, generate_repair_symbol, gf256_linear_combination, receive_source_symbol, receive_repair_symbol);
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use networkcoding::simulator::{self, Bernoulli, ChannelConfig, Codec, RepairSchedule, SimulationConfig};
+
+fn main() {
+    let loss_rate = std::env::args().nth(1).map(|arg| arg.parse().expect("invalid loss rate")).unwrap_or(0.05);
+    let config = SimulationConfig {
+        codec: Codec::VLC,
+        symbol_size: 1200,
+        window_size: 64,
+        n_source_symbols: 10_000,
+        source_interval: Duration::from_millis(1),
+        repair: RepairSchedule { interval: 10, count: 2 },
+        channel: ChannelConfig { delay: Duration::from_millis(20), jitter: Duration::from_millis(2) },
+        loss: Box::new(Bernoulli::new(loss_rate).expect("invalid loss rate")),
+        seed: 42,
+        trace: None,
+    };
+    match simulator::run(config) {
+        Ok(report) => print!("{}", report),
+        Err(err) => eprintln!("simulation failed: {:?}", err),
+    }
+}
@@ -2,6 +2,8 @@
 #include <stdbool.h>
 #include <stdint.h>
 #include <stdlib.h>
+#include <sys/types.h>
+#include <time.h>
 
 typedef struct Decoder Decoder;
 
@@ -9,12 +11,96 @@ typedef struct Encoder Encoder;
 
 typedef struct SourceSymbolsBuffer SourceSymbolsBuffer;
 
+/**
+ * Cumulative encoder counters, along with the current window occupancy
+ */
+typedef struct EncoderStats {
+  uint64_t protected_source_symbols;
+  uint64_t generated_repair_symbols;
+  uint64_t acknowledged_source_symbols;
+  uint64_t current_window_size;
+} EncoderStats;
+
+/**
+ * Cumulative decoder counters, along with the current window occupancy
+ */
+typedef struct DecoderStats {
+  uint64_t received_source_symbols;
+  uint64_t received_repair_symbols;
+  uint64_t recovered_source_symbols;
+  uint64_t unused_source_symbols;
+  uint64_t unused_repair_symbols;
+  uint64_t expired_source_symbols;
+  uint64_t current_window_size;
+  uint64_t rejected_symbols;
+} DecoderStats;
+
+/**
+ * Bounds on the symbols a Decoder accepts from the wire, so that a hostile peer cannot make it
+ * buffer an unbounded amount of symbols and equations. Symbols that do not fit are rejected
+ * with DecoderError::InvalidSymbol before reaching the underlying decoder
+ */
+typedef struct DecoderLimits {
+  /**
+   * maximum number of source symbols protected by a single repair symbol
+   */
+  uint64_t max_protected_symbols;
+  /**
+   * maximum distance between the last symbol of the window and a newly received symbol. When the
+   * window is empty, the distance is counted from the first symbol that was not removed yet
+   */
+  uint64_t max_future_symbols;
+  /**
+   * maximum number of source symbols spanned by the window once the symbol is received
+   */
+  uint64_t max_window_span;
+  /**
+   * maximum number of repair symbols buffered for the current window
+   */
+  uint64_t max_buffered_repair_symbols;
+} DecoderLimits;
+
+/**
+ * Parameters of new_codec, see CodecConfig
+ */
+typedef struct FfiCodecConfig {
+  /**
+   * 0 for VLC, 1 for RLC
+   */
+  uint8_t scheme;
+  /**
+   * m for GF(2^m), only 8 is supported
+   */
+  uint8_t field_bits;
+  /**
+   * a coefficient is non-zero with probability (density_threshold + 1) / 16, only 15 is supported
+   */
+  uint8_t density_threshold;
+  /**
+   * whether the RLC encoder uses seed, a random seed is drawn otherwise
+   */
+  bool fixed_seed;
+  uint32_t seed;
+  size_t symbol_size;
+  size_t window_size;
+  size_t repair_interval;
+  size_t repair_count;
+} FfiCodecConfig;
+
 typedef struct Encoder encoder_t;
 
 typedef struct Decoder decoder_t;
 
 typedef uint64_t source_symbol_metadata_t;
 
+typedef struct EncoderStats encoder_stats_t;
+
+typedef struct DecoderStats decoder_stats_t;
+
+typedef struct DecoderLimits decoder_limits_t;
+
+typedef struct FfiCodecConfig codec_config_t;
+
 typedef struct SourceSymbolsBuffer source_symbols_buffer_t;
 
 encoder_t *new_vlc_encoder(size_t symbol_size, size_t window_size);
@@ -67,6 +153,8 @@ void encoder_remove_up_to(encoder_t *encoder, source_symbol_metadata_t up_to);
 
 ssize_t encoder_next_metadata(encoder_t *encoder, uint8_t *out, size_t len);
 
+encoder_stats_t encoder_stats(const encoder_t *encoder);
+
 ssize_t source_symbols_buffer_dequeue(source_symbols_buffer_t *buffer,
                                       uint8_t *out,
                                       size_t out_len,
@@ -76,15 +164,18 @@ bool source_symbols_buffer_is_empty(source_symbols_buffer_t *buffer);
 
 void destroy_source_symbols_buffer(source_symbols_buffer_t *buffer);
 
+#if !(defined(__APPLE__) || defined(_WIN32))
 /**
  * Decoder-specific functions
  *
- * the given source_sylmbol_data is copied
+ * the given source_symbol_data is copied
  */
 source_symbols_buffer_t *decoder_receive_source_symbol(decoder_t *decoder,
                                                        source_symbol_metadata_t metadata,
                                                        uint8_t *source_symbol_data,
-                                                       size_t len);
+                                                       size_t len,
+                                                       struct timespec received_at);
+#endif
 
 /**
  *
@@ -109,4 +200,29 @@ ssize_t decoder_read_source_symbol_metadata(const decoder_t *decoder,
 
 size_t decoder_symbol_size(const decoder_t *decoder);
 
-void decoder_remove_up_to(decoder_t *decoder, source_symbol_metadata_t md);
+/**
+ *
+ * Removes the source symbols up to md, expired at the given CLOCK_MONOTONIC time, and writes the
+ * metadata returned by the decoder to out if it is not NULL.
+ * Returns 0 on success and a negative error code if expired_at is not a valid time
+ */
+ssize_t decoder_remove_up_to(decoder_t *decoder,
+                             source_symbol_metadata_t md,
+                             struct timespec expired_at,
+                             source_symbol_metadata_t *out);
+
+decoder_stats_t decoder_stats(const decoder_t *decoder);
+
+decoder_limits_t decoder_limits(const decoder_t *decoder);
+
+/**
+ * Symbols exceeding the limits are rejected, decoder_stats counts them as rejected_symbols
+ */
+void decoder_set_limits(decoder_t *decoder, decoder_limits_t limits);
+
+/**
+ * Builds the encoder and the decoder of a configuration, either of them can be NULL if only one side is needed.
+ * Returns 0 on success and a negative error code if the configuration is invalid or not supported by this build,
+ * in which case neither encoder nor decoder is written
+ */
+ssize_t new_codec(const codec_config_t *config, encoder_t **encoder, decoder_t **decoder);
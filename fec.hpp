@@ -0,0 +1,260 @@
+// Header-only C++17 RAII wrapper around the C API declared in fec.h
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <iterator>
+#include <memory>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <vector>
+
+extern "C" {
+#include "fec.h"
+}
+
+namespace fec {
+
+using metadata_t = source_symbol_metadata_t;
+
+/// Thrown when a C API call returns a negative error code
+class error : public std::runtime_error {
+public:
+    error(const std::string &what, ssize_t code)
+        : std::runtime_error(what + " (error code " + std::to_string(code) + ")"), code_(code) {}
+
+    ssize_t code() const noexcept { return code_; }
+
+private:
+    ssize_t code_;
+};
+
+namespace detail {
+
+struct encoder_deleter {
+    void operator()(encoder_t *encoder) const noexcept { destroy_encoder(encoder); }
+};
+
+struct decoder_deleter {
+    void operator()(decoder_t *decoder) const noexcept { destroy_decoder(decoder); }
+};
+
+struct buffer_deleter {
+    void operator()(source_symbols_buffer_t *buffer) const noexcept { destroy_source_symbols_buffer(buffer); }
+};
+
+inline size_t check(ssize_t ret, const char *what) {
+    if (ret < 0) {
+        throw error(what, ret);
+    }
+    return static_cast<size_t>(ret);
+}
+
+inline uint8_t *mut(const uint8_t *data) noexcept {
+    // the C API takes non-const pointers but never writes to its inputs
+    return const_cast<uint8_t *>(data);
+}
+
+} // namespace detail
+
+struct RecoveredSymbol {
+    metadata_t metadata;
+    std::vector<uint8_t> data;
+};
+
+/// Owns the symbols recovered by a single decoder call. Iterating over it
+/// consumes the underlying buffer: it is a single-pass range.
+class RecoveredSymbols {
+public:
+    class iterator {
+    public:
+        using iterator_category = std::input_iterator_tag;
+        using value_type = RecoveredSymbol;
+        using difference_type = std::ptrdiff_t;
+        using pointer = const RecoveredSymbol *;
+        using reference = const RecoveredSymbol &;
+
+        iterator() noexcept = default;
+
+        reference operator*() const noexcept { return current_; }
+        pointer operator->() const noexcept { return &current_; }
+
+        iterator &operator++() {
+            if (!range_->next(current_)) {
+                range_ = nullptr;
+            }
+            return *this;
+        }
+
+        void operator++(int) { ++*this; }
+
+        friend bool operator==(const iterator &a, const iterator &b) noexcept { return a.range_ == b.range_; }
+        friend bool operator!=(const iterator &a, const iterator &b) noexcept { return !(a == b); }
+
+    private:
+        friend class RecoveredSymbols;
+
+        explicit iterator(RecoveredSymbols *range) : range_(range) { ++*this; }
+
+        RecoveredSymbols *range_ = nullptr;
+        RecoveredSymbol current_;
+    };
+
+    RecoveredSymbols(source_symbols_buffer_t *buffer, size_t symbol_size) noexcept
+        : buffer_(buffer), symbol_size_(symbol_size) {}
+
+    /// false if the decoder rejected the symbol that produced this range
+    explicit operator bool() const noexcept { return buffer_ != nullptr; }
+
+    bool empty() const noexcept { return !buffer_ || source_symbols_buffer_is_empty(buffer_.get()); }
+
+    iterator begin() { return empty() ? iterator() : iterator(this); }
+    iterator end() noexcept { return iterator(); }
+
+    /// Dequeues the next recovered symbol into out, returns false when the range is exhausted
+    bool next(RecoveredSymbol &out) {
+        if (empty()) {
+            return false;
+        }
+        out.data.resize(symbol_size_);
+        size_t len = detail::check(
+            source_symbols_buffer_dequeue(buffer_.get(), out.data.data(), out.data.size(), &out.metadata),
+            "source_symbols_buffer_dequeue");
+        out.data.resize(len);
+        return true;
+    }
+
+private:
+    std::unique_ptr<source_symbols_buffer_t, detail::buffer_deleter> buffer_;
+    size_t symbol_size_;
+};
+
+class Encoder {
+public:
+    static Encoder vlc(size_t symbol_size, size_t window_size) {
+        return Encoder(new_vlc_encoder(symbol_size, window_size));
+    }
+
+    static Encoder from_config(const codec_config_t &config) {
+        encoder_t *encoder = nullptr;
+        detail::check(new_codec(&config, &encoder, nullptr), "new_codec");
+        return Encoder(encoder);
+    }
+
+    encoder_t *get() const noexcept { return encoder_.get(); }
+
+    size_t symbol_size() const noexcept { return encoder_symbol_size(get()); }
+
+    bool can_send_repair_symbols() const noexcept { return encoder_can_send_repair_symbols(get()); }
+
+    /// Protects the given data and returns the metadata of the new source symbol
+    metadata_t protect(const uint8_t *data, size_t len) {
+        metadata_t md = 0;
+        detail::check(encoder_protect_data(get(), detail::mut(data), len, &md), "encoder_protect_data");
+        return md;
+    }
+
+    metadata_t protect(const std::vector<uint8_t> &data) { return protect(data.data(), data.size()); }
+
+    /// Serializes a new repair symbol into out and returns the amount of written bytes
+    size_t generate_repair_symbol(uint8_t *out, size_t out_len) {
+        return detail::check(encoder_generate_and_serialize_repair_symbol(get(), out, out_len),
+                             "encoder_generate_and_serialize_repair_symbol");
+    }
+
+    size_t generate_repair_symbol_up_to(uint8_t *out, size_t out_len, metadata_t up_to) {
+        return detail::check(encoder_generate_and_serialize_repair_symbol_up_to(get(), out, out_len, up_to),
+                             "encoder_generate_and_serialize_repair_symbol_up_to");
+    }
+
+    void received_symbol(metadata_t md) {
+        uint8_t buf[sizeof(metadata_t)];
+        for (size_t i = 0; i < sizeof(buf); i++) {
+            buf[i] = static_cast<uint8_t>(md >> (8 * (sizeof(buf) - 1 - i)));
+        }
+        detail::check(encoder_received_symbol(get(), buf, sizeof(buf)), "encoder_received_symbol");
+    }
+
+    void remove_up_to(metadata_t md) noexcept { encoder_remove_up_to(get(), md); }
+
+    encoder_stats_t stats() const noexcept { return encoder_stats(get()); }
+
+private:
+    explicit Encoder(encoder_t *encoder) : encoder_(encoder) {
+        if (!encoder_) {
+            throw std::bad_alloc();
+        }
+    }
+
+    std::unique_ptr<encoder_t, detail::encoder_deleter> encoder_;
+};
+
+class Decoder {
+public:
+    static Decoder vlc(size_t symbol_size, size_t window_size) {
+        return Decoder(new_vlc_decoder(symbol_size, window_size));
+    }
+
+    static Decoder from_config(const codec_config_t &config) {
+        decoder_t *decoder = nullptr;
+        detail::check(new_codec(&config, nullptr, &decoder), "new_codec");
+        return Decoder(decoder);
+    }
+
+    decoder_t *get() const noexcept { return decoder_.get(); }
+
+    size_t symbol_size() const noexcept { return decoder_symbol_size(get()); }
+
+    decoder_stats_t stats() const noexcept { return decoder_stats(get()); }
+    decoder_limits_t limits() const noexcept { return decoder_limits(get()); }
+    void set_limits(const decoder_limits_t &limits) noexcept { decoder_set_limits(get(), limits); }
+
+#if !(defined(__APPLE__) || defined(_WIN32))
+    RecoveredSymbols receive_source_symbol(metadata_t md, const uint8_t *data, size_t len, struct timespec received_at) {
+        return RecoveredSymbols(decoder_receive_source_symbol(get(), md, detail::mut(data), len, received_at),
+                                symbol_size());
+    }
+
+    source_symbol_metadata_t remove_up_to(metadata_t md, struct timespec expired_at) {
+        source_symbol_metadata_t removed = 0;
+        detail::check(decoder_remove_up_to(get(), md, expired_at, &removed), "decoder_remove_up_to");
+        return removed;
+    }
+#endif
+
+    /// Feeds the repair symbol at the start of data, consumed is set to its length on success
+    RecoveredSymbols receive_repair_symbol(const uint8_t *data, size_t len, size_t *consumed = nullptr) {
+        size_t cons = 0;
+        RecoveredSymbols ret(decoder_receive_and_deserialize_repair_symbol(get(), detail::mut(data), len, &cons),
+                             symbol_size());
+        if (consumed) {
+            *consumed = cons;
+        }
+        return ret;
+    }
+
+    size_t repair_symbol_length(const uint8_t *data, size_t len) const {
+        return detail::check(decoder_get_repair_symbol_payload_length(get(), detail::mut(data), len),
+                             "decoder_get_repair_symbol_payload_length");
+    }
+
+    /// Reads the source symbol metadata at the start of data, returns (consumed bytes, metadata)
+    std::pair<size_t, metadata_t> read_source_symbol_metadata(const uint8_t *data, size_t len) const {
+        metadata_t md = 0;
+        size_t consumed = detail::check(decoder_read_source_symbol_metadata(get(), detail::mut(data), len, &md),
+                                        "decoder_read_source_symbol_metadata");
+        return {consumed, md};
+    }
+
+private:
+    explicit Decoder(decoder_t *decoder) : decoder_(decoder) {
+        if (!decoder_) {
+            throw std::bad_alloc();
+        }
+    }
+
+    std::unique_ptr<decoder_t, detail::decoder_deleter> decoder_;
+};
+
+} // namespace fec
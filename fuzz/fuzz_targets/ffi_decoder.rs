@@ -0,0 +1,83 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use networkcoding::ffi;
+use networkcoding::simulator::Codec;
+
+const SYMBOL_SIZE: usize = 16;
+
+/// Reads a big-endian i64 from the start of data, padded with zeros
+fn read_i64(data: &[u8]) -> i64 {
+    let mut buf = [0; 8];
+    let len = data.len().min(8);
+    buf[..len].copy_from_slice(&data[..len]);
+    i64::from_be_bytes(buf)
+}
+
+fn timespec(data: &[u8]) -> libc::timespec {
+    let mut ts: libc::timespec = unsafe { std::mem::zeroed() };
+    ts.tv_sec = read_i64(data) as libc::time_t;
+    ts.tv_nsec = read_i64(data.get(8..).unwrap_or(&[])) as libc::c_long;
+    ts
+}
+
+fn drain(buffer: *mut ffi::SourceSymbolsBuffer) {
+    if buffer.is_null() {
+        return;
+    }
+    let mut out = [0u8; SYMBOL_SIZE];
+    let mut md = 0;
+    while !ffi::source_symbols_buffer_is_empty(unsafe { &mut *buffer }) {
+        assert!(ffi::source_symbols_buffer_dequeue(unsafe { &mut *buffer }, out.as_mut_ptr(), out.len(), &mut md) >= 0);
+    }
+    ffi::destroy_source_symbols_buffer(buffer);
+}
+
+// Same input layout as receive_symbols, the operations go through the C API
+fuzz_target!(|data: &[u8]| {
+    let (decoder, mut data) = match data.split_first() {
+        Some((codec, data)) if codec % 2 == 0 => (ffi::new_vlc_decoder(SYMBOL_SIZE, 64), data),
+        Some((_, data)) => (Box::into_raw(Box::new(Codec::RLC { seed: 42 }.new_decoder(SYMBOL_SIZE, 64))), data),
+        None => return,
+    };
+    let dec = unsafe { &mut *decoder };
+    while data.len() >= 2 {
+        let (op, len) = (data[0], data[1] as usize);
+        let payload = &data[2..(2 + len).min(data.len())];
+        data = &data[2 + payload.len()..];
+        let mut payload = payload.to_vec();
+        match op % 3 {
+            0 => {
+                let mut md = 0;
+                let consumed = ffi::decoder_read_source_symbol_metadata(dec, payload.as_mut_ptr(), payload.len(), &mut md);
+                if consumed >= 0 {
+                    // with the high bit of the operation set, the payload ends with the reception timestamp
+                    let rest = &mut payload[consumed as usize..];
+                    let (rest, ts) = if op & 0x80 != 0 {
+                        let split = rest.len().saturating_sub(16);
+                        let ts = timespec(&rest[split..]);
+                        (&mut rest[..split], ts)
+                    } else {
+                        (rest, timespec(&[]))
+                    };
+                    drain(ffi::decoder_receive_source_symbol(dec, md, rest.as_mut_ptr(), rest.len(), ts));
+                }
+            }
+            1 => {
+                let len = ffi::decoder_get_repair_symbol_payload_length(dec, payload.as_mut_ptr(), payload.len());
+                if len >= 0 {
+                    assert!(len as usize <= payload.len());
+                    let mut consumed = 0;
+                    drain(ffi::decoder_receive_and_deserialize_repair_symbol(dec, payload.as_mut_ptr(), len as usize, &mut consumed));
+                    assert!(consumed <= len as usize);
+                }
+            }
+            _ => {
+                let md = read_i64(&payload) as u64;
+                let mut removed = 0;
+                ffi::decoder_remove_up_to(dec, md, timespec(payload.get(8..).unwrap_or(&[])), &mut removed);
+            }
+        }
+    }
+    ffi::destroy_decoder(decoder);
+});
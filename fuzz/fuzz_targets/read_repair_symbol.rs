@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use networkcoding::simulator::Codec;
+
+const SYMBOL_SIZE: usize = 16;
+
+fuzz_target!(|data: &[u8]| {
+    for codec in [Codec::VLC, Codec::RLC { seed: 42 }] {
+        let decoder = codec.new_decoder(SYMBOL_SIZE, 64);
+        if let Ok((consumed, repair_symbol)) = decoder.read_repair_symbol(data) {
+            assert!(consumed <= data.len());
+            assert_eq!(repair_symbol.get()[..], data[..consumed]);
+        }
+        if let Ok(header) = decoder.read_repair_symbol_header(data) {
+            assert!(header.header_len <= data.len());
+        }
+    }
+});
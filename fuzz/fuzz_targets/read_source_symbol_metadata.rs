@@ -0,0 +1,14 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use networkcoding::simulator::Codec;
+
+fuzz_target!(|data: &[u8]| {
+    for codec in [Codec::VLC, Codec::RLC { seed: 42 }] {
+        let decoder = codec.new_decoder(16, 64);
+        if let Ok((consumed, md)) = decoder.read_source_symbol_metadata(data) {
+            assert!(consumed <= data.len());
+            assert_eq!(md[..], data[..md.len()]);
+        }
+    }
+});
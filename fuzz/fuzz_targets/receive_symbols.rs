@@ -0,0 +1,50 @@
+#![no_main]
+use std::time::{Duration, Instant};
+
+use libfuzzer_sys::fuzz_target;
+
+use networkcoding::simulator::Codec;
+use networkcoding::SourceSymbol;
+
+const SYMBOL_SIZE: usize = 16;
+
+// The input is a codec byte followed by a sequence of (operation, length, payload) records
+fuzz_target!(|data: &[u8]| {
+    let (codec, mut data) = match data.split_first() {
+        Some((codec, data)) if codec % 2 == 0 => (Codec::VLC, data),
+        Some((_, data)) => (Codec::RLC { seed: 42 }, data),
+        None => return,
+    };
+    let mut decoder = codec.new_decoder(SYMBOL_SIZE, 64);
+    let start = Instant::now();
+    let mut n_ops = 0;
+    while data.len() >= 2 {
+        let (op, len) = (data[0], data[1] as usize);
+        let payload = &data[2..(2 + len).min(data.len())];
+        data = &data[2 + payload.len()..];
+        n_ops += 1;
+        let now = start + Duration::from_millis(n_ops);
+        match op % 3 {
+            0 => {
+                if let Ok((consumed, md)) = decoder.read_source_symbol_metadata(payload) {
+                    let _ = decoder.receive_source_symbol(SourceSymbol::new(md, payload[consumed..].to_vec()), now);
+                }
+            }
+            1 => {
+                if let Ok((_, repair_symbol)) = decoder.read_repair_symbol(payload) {
+                    if let Ok((consumed, recovered)) = decoder.receive_and_deserialize_repair_symbol(repair_symbol) {
+                        assert!(consumed <= payload.len());
+                        for symbol in recovered {
+                            assert_eq!(symbol.get().len(), SYMBOL_SIZE);
+                        }
+                    }
+                }
+            }
+            _ => {
+                if let Ok((_, md)) = decoder.read_source_symbol_metadata(payload) {
+                    decoder.remove_up_to(md, if op & 0x80 != 0 { Some(now) } else { None });
+                }
+            }
+        }
+    }
+});
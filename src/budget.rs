@@ -0,0 +1,64 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::RepairSymbolHeader;
+
+/// Repair symbols accepted by a Decoder but not handed to the underlying codec yet, when a work
+/// budget paces the repair symbols handed to it by each call. A repair symbol protecting n source
+/// symbols weighs n. This only paces the calls into the codec, it does not bound their CPU cost:
+/// the codec may eliminate all its pending equations when it receives a single symbol
+pub(crate) struct RepairQueue {
+    pub(crate) budget: Option<u64>,
+    pending: VecDeque<(RepairSymbolHeader, Vec<u8>)>,
+}
+
+impl RepairQueue {
+    pub(crate) fn new() -> RepairQueue {
+        RepairQueue {
+            budget: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, header: RepairSymbolHeader, symbol_data: Vec<u8>) {
+        self.pending.push_back((header, symbol_data));
+    }
+
+    ///
+    /// Pops the repair symbols fitting in the budget of a call, at least one so that the decoding
+    /// always progresses
+    pub(crate) fn pop_within_budget(&mut self) -> Vec<(RepairSymbolHeader, Vec<u8>)> {
+        let mut spent = 0u64;
+        let mut ret = Vec::new();
+        while let Some((header, _)) = self.pending.front() {
+            let cost = header.n_protected_symbols;
+            if let Some(budget) = self.budget {
+                if !ret.is_empty() && spent.saturating_add(cost) > budget {
+                    break;
+                }
+            }
+            spent = spent.saturating_add(cost);
+            ret.extend(self.pending.pop_front());
+        }
+        ret
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &(RepairSymbolHeader, Vec<u8>)> {
+        self.pending.iter()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    ///
+    /// Drops the repair symbols only protecting symbols before first_id and returns their number
+    pub(crate) fn window_slid(&mut self, first_id: Option<u64>) -> usize {
+        let before = self.pending.len();
+        match first_id {
+            Some(first_id) => self.pending.retain(|(header, _)| header.first_id + (header.n_protected_symbols - 1) >= first_id),
+            None => self.pending.clear(),
+        }
+        before - self.pending.len()
+    }
+}
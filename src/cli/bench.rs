@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+use networkcoding::simulator::Codec;
+use networkcoding::{DecoderError, RepairSymbol, SourceSymbol, source_symbol_metadata_from_u64};
+
+use super::{CliResult, CodecArg};
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Codecs to benchmark, comma-separated
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "vlc")]
+    pub codecs: Vec<CodecArg>,
+    /// Symbol sizes to benchmark, comma-separated
+    #[arg(long, value_delimiter = ',', default_value = "64,1200,9000", value_parser = super::size_parser(super::MAX_SYMBOL_SIZE))]
+    pub symbol_sizes: Vec<usize>,
+    /// Window sizes to benchmark, comma-separated
+    #[arg(long, value_delimiter = ',', default_value = "8,64,256", value_parser = super::size_parser(super::MAX_WINDOW_SIZE))]
+    pub window_sizes: Vec<usize>,
+    /// Number of source symbols protected in each run
+    #[arg(long, default_value_t = 10_000)]
+    pub symbols: usize,
+    /// One source symbol out of N is lost and one repair symbol is sent every N source symbols
+    #[arg(long, default_value_t = 10)]
+    pub repair_interval: usize,
+}
+
+enum Packet {
+    Source(SourceSymbol),
+    Repair(RepairSymbol),
+}
+
+struct BenchResult {
+    encode: Duration,
+    decode: Duration,
+    bytes: usize,
+    recovered: usize,
+}
+
+fn mbps(bytes: usize, elapsed: Duration) -> f64 {
+    bytes as f64 * 8.0 / elapsed.as_secs_f64().max(1e-9) / 1e6
+}
+
+fn bench_one(codec: Codec, symbol_size: usize, window_size: usize, n_symbols: usize, repair_interval: usize) -> Result<BenchResult, String> {
+    let mut rng = StdRng::seed_from_u64(42);
+    let data: Vec<Vec<u8>> = (0..n_symbols).map(|_| {
+        let mut d = vec![0; symbol_size];
+        rng.fill_bytes(&mut d);
+        d
+    }).collect();
+
+    let mut encoder = codec.new_encoder(symbol_size, window_size);
+    let mut packets = Vec::with_capacity(n_symbols + n_symbols / repair_interval.max(1));
+    let start = Instant::now();
+    for (i, d) in data.into_iter().enumerate() {
+        if encoder.n_protected_symbols() >= window_size {
+            if let Some(first) = encoder.first_metadata() {
+                encoder.remove_up_to(first);
+            }
+        }
+        let mut md = source_symbol_metadata_from_u64(0);
+        encoder.protect_data(d.clone(), &mut md).map_err(|e| format!("{:?}", e))?;
+        packets.push(Packet::Source(SourceSymbol::new(md, d)));
+        if repair_interval > 0 && (i + 1) % repair_interval == 0 {
+            packets.push(Packet::Repair(encoder.generate_and_serialize_repair_symbol().map_err(|e| format!("{:?}", e))?));
+        }
+    }
+    let encode = start.elapsed();
+
+    let mut decoder = codec.new_decoder(symbol_size, window_size);
+    let mut recovered = 0;
+    let mut n_source = 0;
+    let start = Instant::now();
+    for packet in packets {
+        let res = match packet {
+            Packet::Source(symbol) => {
+                n_source += 1;
+                if repair_interval > 0 && n_source % repair_interval == 0 {
+                    continue;
+                }
+                decoder.receive_source_symbol(symbol, start)
+            }
+            Packet::Repair(repair_symbol) => decoder.receive_and_deserialize_repair_symbol(repair_symbol).map(|(_, r)| r),
+        };
+        match res {
+            Ok(r) => recovered += r.len(),
+            Err(DecoderError::UnusedSourceSymbol) | Err(DecoderError::UnusedRepairSymbol) => (),
+            Err(e) => return Err(format!("{:?}", e)),
+        }
+    }
+    Ok(BenchResult {
+        encode,
+        decode: start.elapsed(),
+        bytes: n_symbols * symbol_size,
+        recovered,
+    })
+}
+
+pub fn run(args: BenchArgs) -> CliResult {
+    println!("{:>6} {:>8} {:>8} {:>14} {:>14} {:>10}", "codec", "symbol", "window", "encode Mb/s", "decode Mb/s", "recovered");
+    for codec_arg in &args.codecs {
+        let codec = codec_arg.codec(42)?;
+        for &symbol_size in &args.symbol_sizes {
+            for &window_size in &args.window_sizes {
+                let res = bench_one(codec, symbol_size, window_size, args.symbols, args.repair_interval)?;
+                println!("{:>6} {:>8} {:>8} {:>14.1} {:>14.1} {:>10}",
+                         format!("{:?}", codec_arg).to_lowercase(), symbol_size, window_size,
+                         mbps(res.bytes, res.encode), mbps(res.bytes, res.decode), res.recovered);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Args, ValueEnum};
+
+use networkcoding::pcap::{self, PayloadKind};
+use networkcoding::{Decoder, RepairSymbolCoefficients, source_symbol_metadata_to_u64};
+
+use super::{CliResult, CodecArg};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SymbolKind {
+    Source,
+    Repair,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    #[arg(value_enum)]
+    pub kind: SymbolKind,
+    /// Hex dump of the symbol, whitespace is ignored. Read from stdin if neither it nor --pcap is given
+    pub hex: Option<String>,
+    /// pcap or pcapng capture: inspects the symbol carried by each UDP datagram instead of a hex dump
+    #[arg(long, conflicts_with = "hex")]
+    pub pcap: Option<PathBuf>,
+    /// Only inspects the datagrams of this UDP port, optionally followed by the offset of the symbol in the payload (PORT[:OFFSET])
+    #[arg(long, requires = "pcap")]
+    pub port: Option<String>,
+    #[arg(long, value_enum, default_value = "vlc")]
+    pub codec: CodecArg,
+    #[arg(long, default_value_t = 1200, value_parser = super::size_parser(super::MAX_SYMBOL_SIZE))]
+    pub symbol_size: usize,
+}
+
+pub fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace() && *b != b':').collect();
+    if digits.len() % 2 != 0 {
+        return Err("odd number of hex digits".to_string());
+    }
+    digits.chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).map_err(|_| "invalid hex digit".to_string())?;
+            u8::from_str_radix(pair, 16).map_err(|_| format!("invalid hex byte {:?}", pair))
+        })
+        .collect()
+}
+
+///
+/// Pretty-prints the symbol at the start of data
+pub fn print_symbol(decoder: &Decoder, kind: SymbolKind, data: &[u8]) -> CliResult {
+    match kind {
+        SymbolKind::Source => {
+            let (consumed, md) = decoder.read_source_symbol_metadata(data).map_err(|e| format!("invalid source symbol: {:?}", e))?;
+            println!("source symbol");
+            println!("  id:          {}", source_symbol_metadata_to_u64(md));
+            println!("  header len:  {}", consumed);
+            println!("  payload len: {}", data.len() - consumed);
+        }
+        SymbolKind::Repair => {
+            let header = decoder.read_repair_symbol_header(data).map_err(|e| format!("invalid repair symbol: {:?}", e))?;
+            println!("repair symbol");
+            println!("  first id:            {}", header.first_id);
+            println!("  protected symbols:   {}", header.n_protected_symbols);
+            if header.n_protected_symbols > 0 {
+                println!("  last id:             {}", header.first_id.wrapping_add(header.n_protected_symbols - 1));
+            }
+            match header.coefficients {
+                RepairSymbolCoefficients::SequenceNumber(n) => println!("  sequence number:     {}", n),
+                RepairSymbolCoefficients::Seed(seed) => println!("  seed:                {}", seed),
+            }
+            println!("  header len:          {}", header.header_len);
+            println!("  payload len:         {}", data.len() - header.header_len);
+            if let Err(e) = decoder.read_repair_symbol(data) {
+                println!("  warning: {:?} for symbol size {}", e, decoder.symbol_size());
+            }
+        }
+    }
+    Ok(())
+}
+
+///
+/// Pretty-prints the symbol of each UDP datagram of the capture that matches the port rule, if any
+fn print_capture(decoder: &Decoder, kind: SymbolKind, capture: &Path, port: Option<&str>) -> CliResult {
+    let packets = pcap::read_file(capture).map_err(|e| format!("cannot read {}: {:?}", capture.display(), e))?;
+    let payload_kind = match kind {
+        SymbolKind::Source => PayloadKind::Source,
+        SymbolKind::Repair => PayloadKind::Repair,
+    };
+    let rule = port.map(|port| super::replay::parse_rule(port, payload_kind)).transpose()?;
+    let mut matched = 0;
+    for (i, packet) in packets.iter().enumerate() {
+        let datagram = match pcap::udp_datagram(packet.link_type, &packet.data) {
+            Some(datagram) => datagram,
+            None => continue,
+        };
+        let offset = match &rule {
+            Some(rule) if !rule.matches(&datagram) => continue,
+            Some(rule) => rule.offset,
+            None => 0,
+        };
+        matched += 1;
+        println!("packet {} ({} -> {}):", i, datagram.src_port, datagram.dst_port);
+        if let Err(e) = print_symbol(decoder, kind, &datagram.payload[offset..]) {
+            println!("  {}", e);
+        }
+    }
+    if matched == 0 {
+        return Err(format!("no UDP datagram of {} matches", capture.display()));
+    }
+    Ok(())
+}
+
+pub fn run(args: InspectArgs) -> CliResult {
+    let decoder = args.codec.codec(0)?.new_decoder(args.symbol_size, 1);
+    if let Some(capture) = &args.pcap {
+        return print_capture(&decoder, args.kind, capture, args.port.as_deref());
+    }
+    let hex = match args.hex {
+        Some(hex) => hex,
+        None => std::io::read_to_string(std::io::stdin()).map_err(|e| format!("cannot read stdin: {}", e))?,
+    };
+    let data = parse_hex(&hex)?;
+    print_symbol(&decoder, args.kind, &data)
+}
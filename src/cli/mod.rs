@@ -0,0 +1,60 @@
+use clap::builder::RangedU64ValueParser;
+use clap::ValueEnum;
+
+use networkcoding::simulator::Codec;
+
+pub mod bench;
+pub mod inspect;
+pub mod packets;
+pub mod replay;
+pub mod simulate;
+pub mod trace;
+
+pub type CliResult = Result<(), String>;
+
+/// Largest sizes accepted by the tools, the same as the library configurations
+pub use networkcoding::config::{MAX_SYMBOL_SIZE, MAX_WINDOW_SIZE};
+
+///
+/// Parser of the sizes given on the command line, between 1 and max
+pub fn size_parser(max: usize) -> RangedU64ValueParser<usize> {
+    RangedU64ValueParser::new().range(1..=max as u64)
+}
+
+///
+/// Parser of the probabilities given on the command line, between 0 and 1
+pub fn parse_probability(p: &str) -> Result<f64, String> {
+    let p: f64 = p.parse().map_err(|_| format!("invalid probability {:?}", p))?;
+    if !(0.0..=1.0).contains(&p) {
+        return Err(format!("invalid probability {}: expected a value between 0 and 1", p));
+    }
+    Ok(p)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CodecArg {
+    Vlc,
+    Rlc,
+}
+
+impl CodecArg {
+    pub fn codec(self, seed: u32) -> Result<Codec, String> {
+        match self {
+            CodecArg::Vlc => Ok(Codec::VLC),
+            #[cfg(feature = "enable-rlc")]
+            CodecArg::Rlc => Ok(Codec::RLC { seed }),
+            #[cfg(not(feature = "enable-rlc"))]
+            CodecArg::Rlc => {
+                let _ = seed;
+                Err("RLC support is disabled, rebuild with the enable-rlc feature".to_string())
+            }
+        }
+    }
+}
+
+/// Parses a comma-separated list of values, e.g. "64,1200,9000"
+pub fn parse_list<T: std::str::FromStr>(list: &str) -> Result<Vec<T>, String> {
+    list.split(',')
+        .map(|v| v.trim().parse().map_err(|_| format!("invalid value in list: {:?}", v)))
+        .collect()
+}
@@ -0,0 +1,316 @@
+use std::fs;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use clap::Args;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use networkcoding::tag::TAG_LEN;
+use networkcoding::{DecoderError, SourceSymbol, source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+use super::{CliResult, CodecArg, MAX_SYMBOL_SIZE, MAX_WINDOW_SIZE};
+
+const MAGIC: &[u8; 4] = b"NCPK";
+const VERSION: u8 = 1;
+const HEADER_FILE: &str = "header";
+
+const PACKET_SOURCE: u8 = 0;
+const PACKET_REPAIR: u8 = 1;
+
+#[derive(Args)]
+pub struct EncodeArgs {
+    pub input: PathBuf,
+    /// Packet stream file, or directory of packets with --dir
+    pub output: PathBuf,
+    /// Writes one file per packet in the output directory
+    #[arg(long)]
+    pub dir: bool,
+    #[arg(long, value_enum, default_value = "vlc")]
+    pub codec: CodecArg,
+    #[arg(long, default_value_t = 1200, value_parser = super::size_parser(super::MAX_SYMBOL_SIZE))]
+    pub symbol_size: usize,
+    #[arg(long, default_value_t = 64, value_parser = super::size_parser(super::MAX_WINDOW_SIZE))]
+    pub window_size: usize,
+    /// Send repair symbols after every N source symbols
+    #[arg(long, default_value_t = 10)]
+    pub repair_interval: usize,
+    /// Number of repair symbols sent every repair interval
+    #[arg(long, default_value_t = 2)]
+    pub repair_count: usize,
+    /// Seed of the RLC coefficients generator
+    #[arg(long, default_value_t = 42)]
+    pub seed: u32,
+}
+
+#[derive(Args)]
+pub struct DecodeArgs {
+    /// Packet stream file or directory of packets
+    pub input: PathBuf,
+    pub output: PathBuf,
+    /// Randomly drops packets before decoding them, to emulate a lossy channel
+    #[arg(long, default_value_t = 0.0, value_parser = super::parse_probability)]
+    pub drop_rate: f64,
+    #[arg(long, default_value_t = 42)]
+    pub seed: u64,
+}
+
+/// Describes how the packets were produced, written before them
+struct StreamHeader {
+    codec: CodecArg,
+    seed: u32,
+    symbol_size: usize,
+    window_size: usize,
+    first_id: u64,
+    file_len: u64,
+}
+
+impl StreamHeader {
+    fn write<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_u8(VERSION)?;
+        out.write_u8(match self.codec {
+            CodecArg::Vlc => 0,
+            CodecArg::Rlc => 1,
+        })?;
+        out.write_u32::<BigEndian>(self.seed)?;
+        out.write_u32::<BigEndian>(self.symbol_size as u32)?;
+        out.write_u32::<BigEndian>(self.window_size as u32)?;
+        out.write_u64::<BigEndian>(self.first_id)?;
+        out.write_u64::<BigEndian>(self.file_len)
+    }
+
+    fn read<R: Read>(input: &mut R) -> Result<StreamHeader, String> {
+        let err = |e: std::io::Error| format!("cannot read the stream header: {}", e);
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic).map_err(err)?;
+        if &magic != MAGIC {
+            return Err("not a packet stream".to_string());
+        }
+        let version = input.read_u8().map_err(err)?;
+        if version != VERSION {
+            return Err(format!("unsupported packet stream version {}", version));
+        }
+        let codec = match input.read_u8().map_err(err)? {
+            0 => CodecArg::Vlc,
+            1 => CodecArg::Rlc,
+            c => return Err(format!("unknown codec {}", c)),
+        };
+        let header = StreamHeader {
+            codec,
+            seed: input.read_u32::<BigEndian>().map_err(err)?,
+            symbol_size: input.read_u32::<BigEndian>().map_err(err)? as usize,
+            window_size: input.read_u32::<BigEndian>().map_err(err)? as usize,
+            first_id: input.read_u64::<BigEndian>().map_err(err)?,
+            file_len: input.read_u64::<BigEndian>().map_err(err)?,
+        };
+        if header.symbol_size == 0 || header.symbol_size > MAX_SYMBOL_SIZE {
+            return Err(format!("invalid symbol size {} in the stream header", header.symbol_size));
+        }
+        if header.window_size == 0 || header.window_size > MAX_WINDOW_SIZE {
+            return Err(format!("invalid window size {} in the stream header", header.window_size));
+        }
+        Ok(header)
+    }
+
+    /// Number of source symbols of the file
+    fn n_symbols(&self) -> u64 {
+        self.file_len.div_ceil(self.symbol_size as u64)
+    }
+
+    /// The largest packet is a repair symbol with its tag
+    fn max_packet_len(&self) -> usize {
+        TAG_LEN + 8 + 8 + 4 + self.symbol_size
+    }
+}
+
+/// Writes packets either as a single framed stream or as one file per packet
+enum PacketSink {
+    Stream(BufWriter<fs::File>),
+    Dir { path: PathBuf, count: usize },
+}
+
+impl PacketSink {
+    fn write(&mut self, kind: u8, payload: &[u8]) -> std::io::Result<()> {
+        match self {
+            PacketSink::Stream(out) => {
+                out.write_u8(kind)?;
+                out.write_u32::<BigEndian>(payload.len() as u32)?;
+                out.write_all(payload)
+            }
+            PacketSink::Dir { path, count } => {
+                let ext = if kind == PACKET_SOURCE { "src" } else { "rep" };
+                *count += 1;
+                fs::write(path.join(format!("{:08}.{}", *count - 1, ext)), payload)
+            }
+        }
+    }
+}
+
+pub fn encode(args: EncodeArgs) -> CliResult {
+    let data = fs::read(&args.input).map_err(|e| format!("cannot read {}: {}", args.input.display(), e))?;
+    let codec = args.codec.codec(args.seed)?;
+    let mut encoder = codec.new_encoder(args.symbol_size, args.window_size);
+    let io_err = |e: std::io::Error| format!("cannot write {}: {}", args.output.display(), e);
+
+    let header = StreamHeader {
+        codec: args.codec,
+        seed: args.seed,
+        symbol_size: args.symbol_size,
+        window_size: args.window_size,
+        first_id: source_symbol_metadata_to_u64(encoder.next_metadata().map_err(|e| format!("{:?}", e))?),
+        file_len: data.len() as u64,
+    };
+    let mut sink = if args.dir {
+        fs::create_dir_all(&args.output).map_err(io_err)?;
+        let mut header_file = fs::File::create(args.output.join(HEADER_FILE)).map_err(io_err)?;
+        header.write(&mut header_file).map_err(io_err)?;
+        PacketSink::Dir { path: args.output.clone(), count: 0 }
+    } else {
+        let mut out = BufWriter::new(fs::File::create(&args.output).map_err(io_err)?);
+        header.write(&mut out).map_err(io_err)?;
+        PacketSink::Stream(out)
+    };
+
+    let (mut n_source, mut n_repair) = (0, 0);
+    for (i, chunk) in data.chunks(args.symbol_size).enumerate() {
+        if encoder.n_protected_symbols() >= args.window_size {
+            if let Some(first) = encoder.first_metadata() {
+                encoder.remove_up_to(first);
+            }
+        }
+        let mut symbol = chunk.to_vec();
+        symbol.resize(args.symbol_size, 0);
+        let mut md = source_symbol_metadata_from_u64(0);
+        encoder.protect_data(symbol.clone(), &mut md).map_err(|e| format!("cannot protect symbol {}: {:?}", i, e))?;
+        let mut payload = md.to_vec();
+        payload.extend_from_slice(&symbol);
+        sink.write(PACKET_SOURCE, &payload).map_err(io_err)?;
+        n_source += 1;
+
+        let last = (i + 1) * args.symbol_size >= data.len();
+        if args.repair_interval > 0 && ((i + 1) % args.repair_interval == 0 || last) {
+            for _ in 0..args.repair_count {
+                let repair_symbol = encoder.generate_and_serialize_repair_symbol().map_err(|e| format!("cannot generate repair symbol: {:?}", e))?;
+                sink.write(PACKET_REPAIR, repair_symbol.get()).map_err(io_err)?;
+                n_repair += 1;
+            }
+        }
+    }
+    if let PacketSink::Stream(mut out) = sink {
+        out.flush().map_err(io_err)?;
+    }
+    eprintln!("wrote {} source and {} repair packets", n_source, n_repair);
+    Ok(())
+}
+
+/// Reads all the packets, in order, from a stream file or a packets directory
+fn read_packets(input: &Path) -> Result<(StreamHeader, Vec<(u8, Vec<u8>)>), String> {
+    let io_err = |e: std::io::Error| format!("cannot read {}: {}", input.display(), e);
+    let mut packets = Vec::new();
+    if input.is_dir() {
+        let header = StreamHeader::read(&mut fs::File::open(input.join(HEADER_FILE)).map_err(io_err)?)?;
+        let mut entries = fs::read_dir(input).map_err(io_err)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io_err)?;
+        entries.sort();
+        for path in entries {
+            let kind = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("src") => PACKET_SOURCE,
+                Some("rep") => PACKET_REPAIR,
+                _ => continue,
+            };
+            let len = fs::metadata(&path).map_err(io_err)?.len();
+            if len > header.max_packet_len() as u64 {
+                return Err(format!("{} is {} bytes long, more than a repair symbol of {} bytes", path.display(), len, header.symbol_size));
+            }
+            packets.push((kind, fs::read(&path).map_err(io_err)?));
+        }
+        Ok((header, packets))
+    } else {
+        let mut reader = BufReader::new(fs::File::open(input).map_err(io_err)?);
+        let header = StreamHeader::read(&mut reader)?;
+        loop {
+            let kind = match reader.read_u8() {
+                Ok(kind) => kind,
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(io_err(e)),
+            };
+            let len = reader.read_u32::<BigEndian>().map_err(io_err)? as usize;
+            if len > header.max_packet_len() {
+                return Err(format!("packet {} is {} bytes long, more than a repair symbol of {} bytes", packets.len(), len, header.symbol_size));
+            }
+            let mut payload = vec![0; len];
+            reader.read_exact(&mut payload).map_err(io_err)?;
+            packets.push((kind, payload));
+        }
+        Ok((header, packets))
+    }
+}
+
+pub fn decode(args: DecodeArgs) -> CliResult {
+    let (header, packets) = read_packets(&args.input)?;
+    let codec = header.codec.codec(header.seed)?;
+    let mut decoder = codec.new_decoder(header.symbol_size, header.window_size);
+    let mut rng = StdRng::seed_from_u64(args.seed);
+
+    // each source symbol is either in a source packet or recovered by a repair packet
+    if header.n_symbols() > packets.len() as u64 {
+        return Err(format!("the header announces {} symbols but there are only {} packets", header.n_symbols(), packets.len()));
+    }
+    let mut symbols: Vec<Option<Vec<u8>>> = vec![None; header.n_symbols() as usize];
+    let (mut n_dropped, mut n_recovered) = (0, 0);
+
+    let mut store = |symbol: SourceSymbol, recovered: bool| {
+        let index = source_symbol_metadata_to_u64(symbol.metadata()).wrapping_sub(header.first_id) as usize;
+        if index < symbols.len() && symbols[index].is_none() {
+            symbols[index] = Some(symbol.take());
+            if recovered {
+                n_recovered += 1;
+            }
+        }
+    };
+
+    for (kind, payload) in packets {
+        if args.drop_rate > 0.0 && rng.gen_bool(args.drop_rate) {
+            n_dropped += 1;
+            continue;
+        }
+        let now = Instant::now();
+        let recovered = match kind {
+            PACKET_SOURCE => {
+                let (consumed, md) = decoder.read_source_symbol_metadata(&payload).map_err(|e| format!("bad source packet: {:?}", e))?;
+                let symbol = SourceSymbol::new(md, payload[consumed..].to_vec());
+                store(symbol.clone(), false);
+                decoder.receive_source_symbol(symbol, now)
+            }
+            PACKET_REPAIR => decoder.read_repair_symbol(&payload)
+                .and_then(|(_, repair_symbol)| decoder.receive_and_deserialize_repair_symbol(repair_symbol))
+                .map(|(_, recovered)| recovered),
+            kind => return Err(format!("unknown packet type {}", kind)),
+        };
+        match recovered {
+            Ok(recovered) => recovered.into_iter().for_each(|symbol| store(symbol, true)),
+            Err(DecoderError::UnusedSourceSymbol) | Err(DecoderError::UnusedRepairSymbol) => (),
+            Err(e) => return Err(format!("decoding error: {:?}", e)),
+        }
+        if let Some((first, last)) = decoder.bounds() {
+            let (first, last) = (source_symbol_metadata_to_u64(first), source_symbol_metadata_to_u64(last));
+            if last + 1 - first > header.window_size as u64 {
+                decoder.remove_up_to(source_symbol_metadata_from_u64(last - header.window_size as u64), Some(now));
+            }
+        }
+    }
+
+    let missing = symbols.iter().filter(|s| s.is_none()).count();
+    eprintln!("dropped {} packets, recovered {} symbols, {} symbols missing", n_dropped, n_recovered, missing);
+    if missing > 0 {
+        return Err(format!("cannot rebuild {}: {} symbols could not be recovered", args.output.display(), missing));
+    }
+    let mut data: Vec<u8> = symbols.into_iter().flatten().flatten().collect();
+    data.truncate(header.file_len as usize);
+    fs::write(&args.output, data).map_err(|e| format!("cannot write {}: {}", args.output.display(), e))
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use networkcoding::pcap::{self, ExtractionRule, PayloadKind};
+
+use super::{CliResult, CodecArg};
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// pcap or pcapng capture
+    pub capture: PathBuf,
+    /// UDP port carrying source symbols, optionally followed by the offset of the symbol in the payload (PORT[:OFFSET])
+    #[arg(long = "source", required = true)]
+    pub source_rules: Vec<String>,
+    /// UDP port carrying repair symbols, optionally followed by the offset of the symbol in the payload (PORT[:OFFSET])
+    #[arg(long = "repair", required = true)]
+    pub repair_rules: Vec<String>,
+    #[arg(long, value_enum, default_value = "vlc")]
+    pub codec: CodecArg,
+    #[arg(long, default_value_t = 1200, value_parser = super::size_parser(super::MAX_SYMBOL_SIZE))]
+    pub symbol_size: usize,
+    #[arg(long, default_value_t = 64, value_parser = super::size_parser(super::MAX_WINDOW_SIZE))]
+    pub window_size: usize,
+    #[arg(long, default_value_t = 42)]
+    pub seed: u32,
+    /// Prints each recovered symbol
+    #[arg(long)]
+    pub verbose: bool,
+}
+
+pub fn parse_rule(rule: &str, kind: PayloadKind) -> Result<ExtractionRule, String> {
+    let (port, offset) = rule.split_once(':').unwrap_or((rule, "0"));
+    Ok(ExtractionRule {
+        port: port.parse().map_err(|_| format!("invalid port in rule {:?}", rule))?,
+        offset: offset.parse().map_err(|_| format!("invalid offset in rule {:?}", rule))?,
+        kind,
+    })
+}
+
+pub fn run(args: ReplayArgs) -> CliResult {
+    let packets = pcap::read_file(&args.capture).map_err(|e| format!("cannot read {}: {:?}", args.capture.display(), e))?;
+    let mut rules = Vec::new();
+    for rule in &args.source_rules {
+        rules.push(parse_rule(rule, PayloadKind::Source)?);
+    }
+    for rule in &args.repair_rules {
+        rules.push(parse_rule(rule, PayloadKind::Repair)?);
+    }
+
+    let mut decoder = args.codec.codec(args.seed)?.new_decoder(args.symbol_size, args.window_size);
+    let report = pcap::replay(&mut decoder, &packets, &rules, args.window_size);
+
+    println!("packets:           {}", report.packets);
+    println!("source symbols:    {}", report.source_symbols);
+    println!("repair symbols:    {}", report.repair_symbols);
+    println!("invalid packets:   {}", report.invalid_packets);
+    println!("recovered symbols: {}", report.recovered.len());
+    println!("missing symbols:   {}", report.missing.len());
+    if args.verbose {
+        for (id, at) in &report.recovered {
+            println!("  recovered {} at {:.6}s", id, at.as_secs_f64());
+        }
+        for id in &report.missing {
+            println!("  missing {}", id);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use clap::Args;
+
+use networkcoding::simulator::{self, Bernoulli, ChannelConfig, GilbertElliott, LossModel, PeriodicBursts, RepairSchedule, SimulationConfig, TraceLoss};
+
+use super::{CliResult, CodecArg};
+
+#[derive(Args)]
+pub struct SimulateArgs {
+    #[arg(long, value_enum, default_value = "vlc")]
+    pub codec: CodecArg,
+    #[arg(long, default_value_t = 1200, value_parser = super::size_parser(super::MAX_SYMBOL_SIZE))]
+    pub symbol_size: usize,
+    #[arg(long, default_value_t = 64, value_parser = super::size_parser(super::MAX_WINDOW_SIZE))]
+    pub window_size: usize,
+    /// Number of source symbols to send
+    #[arg(long, default_value_t = 10_000)]
+    pub symbols: usize,
+    /// Time between two source symbols, in microseconds
+    #[arg(long, default_value_t = 1000)]
+    pub interval_us: u64,
+    /// Send repair symbols after every N source symbols
+    #[arg(long, default_value_t = 10)]
+    pub repair_interval: usize,
+    /// Number of repair symbols sent every repair interval
+    #[arg(long, default_value_t = 1)]
+    pub repair_count: usize,
+    /// One-way delay of the channel, in milliseconds
+    #[arg(long, default_value_t = 20)]
+    pub delay_ms: u64,
+    /// Maximum random jitter added to the delay, in milliseconds
+    #[arg(long, default_value_t = 0)]
+    pub jitter_ms: u64,
+    /// Loss model: bernoulli:P, ge:P_GOOD_TO_BAD,P_BAD_TO_GOOD,LOSS_GOOD,LOSS_BAD, bursts:PERIOD,LENGTH or trace:FILE
+    #[arg(long, default_value = "bernoulli:0.05")]
+    pub loss: String,
+    #[arg(long, default_value_t = 42)]
+    pub seed: u64,
+    /// Records every encoder and decoder call in a trace file, replayable with the trace command
+    #[arg(long)]
+    pub record: Option<std::path::PathBuf>,
+}
+
+///
+/// Parses a loss model description as given to --loss
+pub fn parse_loss_model(desc: &str) -> Result<Box<dyn LossModel>, String> {
+    let (kind, params) = desc.split_once(':').ok_or_else(|| format!("invalid loss model: {:?}", desc))?;
+    let floats = || super::parse_list::<f64>(params);
+    match kind {
+        "bernoulli" => match floats()?.as_slice() {
+            [p] => Ok(Box::new(Bernoulli::new(*p).map_err(|e| e.to_string())?)),
+            _ => Err("bernoulli expects one parameter".to_string()),
+        },
+        "ge" => match floats()?.as_slice() {
+            [p_gb, p_bg, loss_good, loss_bad] => Ok(Box::new(GilbertElliott::new(*p_gb, *p_bg, *loss_good, *loss_bad).map_err(|e| e.to_string())?)),
+            _ => Err("ge expects four parameters".to_string()),
+        },
+        "bursts" => match super::parse_list::<usize>(params)?.as_slice() {
+            [period, length] => Ok(Box::new(PeriodicBursts::new(*period, *length))),
+            _ => Err("bursts expects two parameters".to_string()),
+        },
+        "trace" => {
+            let trace = std::fs::read_to_string(params).map_err(|e| format!("cannot read {}: {}", params, e))?;
+            Ok(Box::new(TraceLoss::parse(&trace)))
+        }
+        _ => Err(format!("unknown loss model: {:?}", kind)),
+    }
+}
+
+pub fn run(args: SimulateArgs) -> CliResult {
+    let trace: Option<Box<dyn std::io::Write>> = match &args.record {
+        Some(path) => Some(Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path).map_err(|e| format!("cannot create {}: {}", path.display(), e))?))),
+        None => None,
+    };
+    let config = SimulationConfig {
+        codec: args.codec.codec(args.seed as u32)?,
+        symbol_size: args.symbol_size,
+        window_size: args.window_size,
+        n_source_symbols: args.symbols,
+        source_interval: Duration::from_micros(args.interval_us),
+        repair: RepairSchedule {
+            interval: args.repair_interval,
+            count: args.repair_count,
+        },
+        channel: ChannelConfig {
+            delay: Duration::from_millis(args.delay_ms),
+            jitter: Duration::from_millis(args.jitter_ms),
+        },
+        loss: parse_loss_model(&args.loss)?,
+        seed: args.seed,
+        trace,
+    };
+    let report = simulator::run(config).map_err(|e| format!("simulation failed: {:?}", e))?;
+    print!("{}", report);
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use clap::Args;
+
+use networkcoding::trace::{self, Trace};
+
+use super::CliResult;
+
+#[derive(Args)]
+pub struct TraceArgs {
+    pub trace: PathBuf,
+    /// Maximum number of divergent calls printed
+    #[arg(long, default_value_t = 10)]
+    pub max_divergences: usize,
+}
+
+pub fn run(args: TraceArgs) -> CliResult {
+    let file = std::fs::File::open(&args.trace).map_err(|e| format!("cannot open {}: {}", args.trace.display(), e))?;
+    let trace = Trace::read(&mut BufReader::new(file)).map_err(|e| format!("invalid trace {}: {:?}", args.trace.display(), e))?;
+    println!("codec {:?}, symbol size {}, window size {}, {} calls",
+             trace.header.codec, trace.header.symbol_size, trace.header.window_size, trace.entries.len());
+    let divergences = trace::replay(&trace);
+    for divergence in divergences.iter().take(args.max_divergences) {
+        println!("call #{} at {:?}: {:?}", divergence.index, divergence.entry.at, divergence.entry.call);
+        println!("  recorded: {:?}", divergence.entry.outcome);
+        println!("  replayed: {:?}", divergence.actual);
+    }
+    if divergences.is_empty() {
+        println!("the replay matches the trace");
+        Ok(())
+    } else {
+        Err(format!("{} calls diverged from the trace", divergences.len()))
+    }
+}
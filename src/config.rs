@@ -0,0 +1,310 @@
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::simulator::{Codec, RepairSchedule};
+use crate::{Decoder, Encoder};
+
+/// Density threshold of dense coefficients: a coefficient is non-zero with probability (DT + 1) / 16 (RFC 8681)
+pub const DENSE: u8 = 15;
+
+/// Largest symbol size of a configuration, which is also the largest UDP payload.
+/// Keeps the buffers of the codecs, the symbol plus its tag and header, far from overflowing
+pub const MAX_SYMBOL_SIZE: usize = 65_535;
+/// Largest window size of a configuration
+pub const MAX_WINDOW_SIZE: usize = 65_536;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum Scheme {
+    Vlc,
+    Rlc,
+}
+
+/// Finite field of the coefficients, GF(2^m)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum Field {
+    Gf2,
+    #[default]
+    Gf256,
+}
+
+impl Field {
+    ///
+    /// Returns m for GF(2^m)
+    pub fn bits(&self) -> u8 {
+        match self {
+            Field::Gf2 => 1,
+            Field::Gf256 => 8,
+        }
+    }
+
+    pub fn from_bits(bits: u8) -> Option<Field> {
+        match bits {
+            1 => Some(Field::Gf2),
+            8 => Some(Field::Gf256),
+            _ => None,
+        }
+    }
+}
+
+/// Seed of the RLC coefficient generator. Serialized as the seed or as "random"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(try_from = "SeedRepr", into = "SeedRepr"))]
+pub enum SeedPolicy {
+    Fixed(u32),
+    /// a new seed is drawn for each encoder
+    #[default]
+    Random,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SeedRepr {
+    Fixed(u32),
+    Keyword(String),
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SeedRepr> for SeedPolicy {
+    type Error = String;
+
+    fn try_from(repr: SeedRepr) -> Result<SeedPolicy, String> {
+        match repr {
+            SeedRepr::Fixed(seed) => Ok(SeedPolicy::Fixed(seed)),
+            SeedRepr::Keyword(keyword) if keyword == "random" => Ok(SeedPolicy::Random),
+            SeedRepr::Keyword(keyword) => Err(format!("expected a seed or \"random\", got {:?}", keyword)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SeedPolicy> for SeedRepr {
+    fn from(policy: SeedPolicy) -> SeedRepr {
+        match policy {
+            SeedPolicy::Fixed(seed) => SeedRepr::Fixed(seed),
+            SeedPolicy::Random => SeedRepr::Keyword("random".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// the symbol size and the window size must be positive and at most MAX_SYMBOL_SIZE and MAX_WINDOW_SIZE
+    InvalidSize,
+    /// the density threshold is above 15
+    InvalidDensity(u8),
+    /// the repair interval must be positive
+    InvalidRedundancy,
+    /// RLC is not enabled in this build
+    UnsupportedScheme(Scheme),
+    /// vandermonde_lc and rustrlc only compute in GF(2^8)
+    UnsupportedField(Scheme, Field),
+    /// vandermonde_lc and rustrlc only generate dense coefficients
+    UnsupportedDensity(Scheme, u8),
+    /// VLC derives its coefficients from sequence numbers and takes no seed
+    UnexpectedSeed,
+    /// unknown scheme identifier given to the C API
+    InvalidScheme(u8),
+    /// unknown field size given to the C API
+    InvalidField(u8),
+    #[cfg(feature = "toml")]
+    Toml(String),
+}
+
+impl ConfigError {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn to_c(&self) -> libc::ssize_t {
+        match self {
+            ConfigError::InvalidSize => -1,
+            ConfigError::InvalidDensity(_) => -2,
+            ConfigError::InvalidRedundancy => -3,
+            ConfigError::UnsupportedScheme(_) => -4,
+            ConfigError::UnsupportedField(_, _) => -5,
+            ConfigError::UnsupportedDensity(_, _) => -6,
+            ConfigError::UnexpectedSeed => -7,
+            ConfigError::InvalidScheme(_) => -8,
+            ConfigError::InvalidField(_) => -9,
+            #[cfg(feature = "toml")]
+            ConfigError::Toml(_) => -10,
+        }
+    }
+}
+
+/// Parameters shared by the two ends of a session, from which matching encoders and decoders are built.
+/// Serialized with the field names below, e.g. in TOML:
+/// ```toml
+/// scheme = "rlc"        # "vlc" or "rlc"
+/// field = "gf256"       # optional, "gf2" or "gf256"
+/// symbol_size = 1200
+/// window_size = 64
+/// density_threshold = 15  # optional, 0 to 15
+/// seed = 42             # optional, a seed or "random"
+/// redundancy = { interval = 10, count = 1 }  # optional
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(deny_unknown_fields))]
+pub struct CodecConfig {
+    pub scheme: Scheme,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub field: Field,
+    pub symbol_size: usize,
+    pub window_size: usize,
+    #[cfg_attr(feature = "serde", serde(default = "dense"))]
+    pub density_threshold: u8,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: SeedPolicy,
+    /// repair symbols the application sends for its source symbols
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub redundancy: RepairSchedule,
+}
+
+///
+/// Whether the symbol and window sizes are positive and within MAX_SYMBOL_SIZE and MAX_WINDOW_SIZE
+pub(crate) fn valid_sizes(symbol_size: usize, window_size: usize) -> bool {
+    (1..=MAX_SYMBOL_SIZE).contains(&symbol_size) && (1..=MAX_WINDOW_SIZE).contains(&window_size)
+}
+
+#[cfg(feature = "serde")]
+fn dense() -> u8 {
+    DENSE
+}
+
+impl CodecConfig {
+    ///
+    /// Configuration of the given codec with the default field, density and redundancy
+    pub fn new(codec: Codec, symbol_size: usize, window_size: usize) -> CodecConfig {
+        let (scheme, seed) = match codec {
+            Codec::VLC => (Scheme::Vlc, SeedPolicy::Random),
+            #[cfg(feature = "enable-rlc")]
+            Codec::RLC { seed } => (Scheme::Rlc, SeedPolicy::Fixed(seed)),
+        };
+        CodecConfig {
+            scheme,
+            field: Field::default(),
+            symbol_size,
+            window_size,
+            density_threshold: DENSE,
+            seed,
+            redundancy: RepairSchedule::default(),
+        }
+    }
+
+    pub fn builder(scheme: Scheme, symbol_size: usize, window_size: usize) -> CodecConfigBuilder {
+        CodecConfigBuilder {
+            config: CodecConfig {
+                scheme,
+                field: Field::default(),
+                symbol_size,
+                window_size,
+                density_threshold: DENSE,
+                seed: SeedPolicy::Random,
+                redundancy: RepairSchedule::default(),
+            },
+        }
+    }
+
+    ///
+    /// Parses and validates a TOML configuration
+    #[cfg(feature = "toml")]
+    pub fn from_toml(input: &str) -> Result<CodecConfig, ConfigError> {
+        let config: CodecConfig = toml::from_str(input).map_err(|e| ConfigError::Toml(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    ///
+    /// Checks that the parameters are consistent and supported by the codecs of this build
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !valid_sizes(self.symbol_size, self.window_size) {
+            return Err(ConfigError::InvalidSize);
+        }
+        if self.density_threshold > DENSE {
+            return Err(ConfigError::InvalidDensity(self.density_threshold));
+        }
+        if self.redundancy.interval == 0 {
+            return Err(ConfigError::InvalidRedundancy);
+        }
+        if cfg!(not(feature = "enable-rlc")) && self.scheme == Scheme::Rlc {
+            return Err(ConfigError::UnsupportedScheme(self.scheme));
+        }
+        if self.field != Field::Gf256 {
+            return Err(ConfigError::UnsupportedField(self.scheme, self.field));
+        }
+        if self.density_threshold != DENSE {
+            return Err(ConfigError::UnsupportedDensity(self.scheme, self.density_threshold));
+        }
+        if self.scheme == Scheme::Vlc && self.seed != SeedPolicy::Random {
+            return Err(ConfigError::UnexpectedSeed);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Validates the configuration and returns the codec to build, drawing the seed if it is random
+    pub fn codec(&self) -> Result<Codec, ConfigError> {
+        self.validate()?;
+        match self.scheme {
+            Scheme::Vlc => Ok(Codec::VLC),
+            #[cfg(feature = "enable-rlc")]
+            Scheme::Rlc => Ok(Codec::RLC {
+                seed: match self.seed {
+                    SeedPolicy::Fixed(seed) => seed,
+                    SeedPolicy::Random => rand::random(),
+                },
+            }),
+            #[cfg(not(feature = "enable-rlc"))]
+            Scheme::Rlc => Err(ConfigError::UnsupportedScheme(self.scheme)),
+        }
+    }
+
+    pub fn new_encoder(&self) -> Result<Encoder, ConfigError> {
+        Ok(self.codec()?.new_encoder(self.symbol_size, self.window_size))
+    }
+
+    pub fn new_decoder(&self) -> Result<Decoder, ConfigError> {
+        Ok(self.codec()?.new_decoder(self.symbol_size, self.window_size))
+    }
+
+    ///
+    /// Builds an encoder and the decoder matching it, e.g. for a loopback or a simulation
+    pub fn build(&self) -> Result<(Encoder, Decoder), ConfigError> {
+        let codec = self.codec()?;
+        Ok((codec.new_encoder(self.symbol_size, self.window_size), codec.new_decoder(self.symbol_size, self.window_size)))
+    }
+}
+
+pub struct CodecConfigBuilder {
+    config: CodecConfig,
+}
+
+impl CodecConfigBuilder {
+    pub fn field(mut self, field: Field) -> CodecConfigBuilder {
+        self.config.field = field;
+        self
+    }
+
+    pub fn density_threshold(mut self, density_threshold: u8) -> CodecConfigBuilder {
+        self.config.density_threshold = density_threshold;
+        self
+    }
+
+    pub fn seed(mut self, seed: SeedPolicy) -> CodecConfigBuilder {
+        self.config.seed = seed;
+        self
+    }
+
+    pub fn redundancy(mut self, redundancy: RepairSchedule) -> CodecConfigBuilder {
+        self.config.redundancy = redundancy;
+        self
+    }
+
+    pub fn build(self) -> Result<CodecConfig, ConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
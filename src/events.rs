@@ -0,0 +1,145 @@
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Encoder,
+    Decoder,
+}
+
+impl Role {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Encoder => "encoder",
+            Role::Decoder => "decoder",
+        }
+    }
+}
+
+/// FEC events reported to an EventSink, mirroring qlog event names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FecEvent {
+    SourceSymbolProtected { id: u64, len: usize },
+    RepairSymbolSent { first_id: u64, last_id: u64, len: usize },
+    SourceSymbolRecovered { id: u64 },
+    /// a removal changed the bounds of the window of the encoder or decoder, None if it is now empty
+    WindowSlid { role: Role, bounds: Option<(u64, u64)> },
+}
+
+impl FecEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FecEvent::SourceSymbolProtected { .. } => "fec:source_symbol_protected",
+            FecEvent::RepairSymbolSent { .. } => "fec:repair_symbol_sent",
+            FecEvent::SourceSymbolRecovered { .. } => "fec:source_symbol_recovered",
+            FecEvent::WindowSlid { .. } => "fec:window_slid",
+        }
+    }
+
+    /// Serializes the data field of the qlog event
+    pub fn to_json_data(&self) -> String {
+        match self {
+            FecEvent::SourceSymbolProtected { id, len } => format!("{{\"id\":{},\"length\":{}}}", id, len),
+            FecEvent::RepairSymbolSent { first_id, last_id, len } => format!("{{\"first_id\":{},\"last_id\":{},\"length\":{}}}", first_id, last_id, len),
+            FecEvent::SourceSymbolRecovered { id } => format!("{{\"id\":{}}}", id),
+            FecEvent::WindowSlid { role, bounds: Some((first, last)) } => format!("{{\"role\":\"{}\",\"first_id\":{},\"last_id\":{}}}", role.name(), first, last),
+            FecEvent::WindowSlid { role, bounds: None } => format!("{{\"role\":\"{}\"}}", role.name()),
+        }
+    }
+}
+
+pub trait EventSink: Send {
+    fn on_event(&mut self, event: &FecEvent);
+}
+
+/// Holds the optional sink of an encoder or decoder
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub(crate) struct EventEmitter {
+    sink: Option<Box<dyn EventSink>>,
+}
+
+#[cfg(feature = "std")]
+impl EventEmitter {
+    pub(crate) fn set_sink(&mut self, sink: Option<Box<dyn EventSink>>) {
+        self.sink = sink;
+    }
+
+    /// The event is only built if a sink is installed
+    pub(crate) fn emit<F: FnOnce() -> FecEvent>(&mut self, event: F) {
+        if let Some(sink) = self.sink.as_mut() {
+            let event = event();
+            log::trace!("{}: {}", event.name(), event.to_json_data());
+            sink.on_event(&event);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+const RECORD_SEPARATOR: u8 = 0x1e;
+
+#[cfg(feature = "std")]
+///
+/// Quotes and escapes a string as a JSON string (RFC 8259)
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(feature = "std")]
+///
+/// Writes events in the qlog JSON-SEQ format, with times relative to the creation of the writer
+pub struct QlogWriter<W: Write + Send> {
+    out: W,
+    start: Instant,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write + Send> QlogWriter<W> {
+    pub fn new(mut out: W, title: &str) -> std::io::Result<QlogWriter<W>> {
+        out.write_all(&[RECORD_SEPARATOR])?;
+        writeln!(out, "{{\"qlog_version\":\"0.3\",\"qlog_format\":\"JSON-SEQ\",\"title\":{},\"trace\":{{\"common_fields\":{{\"time_format\":\"relative\",\"reference_time\":0}}}}}}", json_string(title))?;
+        Ok(QlogWriter {
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn write_event(&mut self, event: &FecEvent) -> std::io::Result<()> {
+        let time = self.start.elapsed().as_secs_f64() * 1000.0;
+        self.out.write_all(&[RECORD_SEPARATOR])?;
+        writeln!(self.out, "{{\"time\":{:.3},\"name\":\"{}\",\"data\":{}}}", time, event.name(), event.to_json_data())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write + Send> EventSink for QlogWriter<W> {
+    fn on_event(&mut self, event: &FecEvent) {
+        if let Err(err) = self.write_event(event) {
+            log::warn!("could not write qlog event {}: {}", event.name(), err);
+        }
+    }
+}
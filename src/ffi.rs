@@ -1,11 +1,16 @@
 use core::slice;
 use std::mem;
-use std::ops::Add;
 use libc::size_t;
 use libc::ssize_t;
 use libc::timespec;
 
 use crate::Decoder;
+use crate::config::{CodecConfig, ConfigError, Field, Scheme, SeedPolicy};
+use crate::DecoderError;
+use crate::DecoderStats;
+use crate::EncoderStats;
+use crate::limits::DecoderLimits;
+use crate::simulator::RepairSchedule;
 use crate::RepairSymbol;
 use crate::SourceSymbol;
 use crate::source_symbol_metadata_from_u64;
@@ -21,6 +26,51 @@ type source_symbol_metadata_t = u64;
 type encoder_t = Encoder;
 #[allow(non_camel_case_types)]
 type decoder_t = Decoder;
+#[allow(non_camel_case_types)]
+type encoder_stats_t = EncoderStats;
+#[allow(non_camel_case_types)]
+type decoder_stats_t = DecoderStats;
+#[allow(non_camel_case_types)]
+type decoder_limits_t = DecoderLimits;
+#[allow(non_camel_case_types)]
+type codec_config_t = FfiCodecConfig;
+
+/// Parameters of new_codec, see CodecConfig
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiCodecConfig {
+    /// 0 for VLC, 1 for RLC
+    pub scheme: u8,
+    /// m for GF(2^m), only 8 is supported
+    pub field_bits: u8,
+    /// a coefficient is non-zero with probability (density_threshold + 1) / 16, only 15 is supported
+    pub density_threshold: u8,
+    /// whether the RLC encoder uses seed, a random seed is drawn otherwise
+    pub fixed_seed: bool,
+    pub seed: u32,
+    pub symbol_size: size_t,
+    pub window_size: size_t,
+    pub repair_interval: size_t,
+    pub repair_count: size_t,
+}
+
+impl FfiCodecConfig {
+    fn to_config(self) -> Result<CodecConfig, ConfigError> {
+        let scheme = match self.scheme {
+            0 => Scheme::Vlc,
+            1 => Scheme::Rlc,
+            scheme => return Err(ConfigError::InvalidScheme(scheme)),
+        };
+        let field = Field::from_bits(self.field_bits).ok_or(ConfigError::InvalidField(self.field_bits))?;
+        let seed = if self.fixed_seed { SeedPolicy::Fixed(self.seed) } else { SeedPolicy::Random };
+        CodecConfig::builder(scheme, self.symbol_size, self.window_size)
+            .field(field)
+            .density_threshold(self.density_threshold)
+            .seed(seed)
+            .redundancy(RepairSchedule { interval: self.repair_interval, count: self.repair_count })
+            .build()
+    }
+}
 
 pub struct SourceSymbolsBuffer {
     current_index: size_t,
@@ -139,6 +189,55 @@ pub extern "C" fn encoder_next_metadata(encoder: &mut encoder_t, out: *mut u8, l
     }
 }
 
+#[no_mangle]
+pub extern "C" fn encoder_stats(encoder: &encoder_t) -> encoder_stats_t {
+    encoder.stats()
+}
+
+fn duration_from_timespec(ts: &timespec) -> Option<std::time::Duration> {
+    if ts.tv_sec < 0 || ts.tv_nsec < 0 || ts.tv_nsec >= 1_000_000_000 {
+        return None;
+    }
+    Some(std::time::Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+///
+/// Converts a CLOCK_MONOTONIC time given by the application by offsetting the current Instant,
+/// None if its fields are out of range
+#[cfg(unix)]
+fn instant_from_timespec(ts: timespec) -> Option<std::time::Instant> {
+    let at = duration_from_timespec(&ts)?;
+    let mut now_ts: timespec = unsafe { mem::zeroed() };
+    let now = std::time::Instant::now();
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now_ts) } != 0 {
+        return None;
+    }
+    let now_ts = duration_from_timespec(&now_ts)?;
+    if at <= now_ts {
+        now.checked_sub(now_ts - at)
+    } else {
+        now.checked_add(at - now_ts)
+    }
+}
+
+/// There is no CLOCK_MONOTONIC to relate the timespec to
+#[cfg(not(unix))]
+fn instant_from_timespec(_ts: timespec) -> Option<std::time::Instant> {
+    None
+}
+
+///
+/// Builds a slice from a buffer given by the application, None if it is NULL but not empty
+fn slice_from_raw<'a>(data: *const u8, len: size_t) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(unsafe { slice::from_raw_parts(data, len) })
+    }
+}
+
 fn new_source_symbols_buffer(data: Vec<SourceSymbol>) -> source_symbols_buffer_t {
     source_symbols_buffer_t { current_index: 0, symbols: data }
 }
@@ -154,7 +253,7 @@ pub extern "C" fn source_symbols_buffer_dequeue(buffer: &mut source_symbols_buff
         return -2 as isize;
     }
     let buf = unsafe { slice::from_raw_parts_mut(out, out_len) };
-    buf.copy_from_slice(&out_symbol.data[..]);
+    buf[..out_symbol.data.len()].copy_from_slice(&out_symbol.data[..]);
     *out_metadata = source_symbol_metadata_to_u64(out_symbol.metadata());
     buffer.current_index += 1;
     out_symbol.data.len() as ssize_t
@@ -186,15 +285,11 @@ pub extern "C" fn destroy_source_symbols_buffer(buffer: *mut source_symbols_buff
 #[cfg(not(any(target_os = "macos", target_os = "ios", target_os = "windows")))]
 #[no_mangle]
 pub extern "C" fn decoder_receive_source_symbol(decoder: &mut decoder_t, metadata: source_symbol_metadata_t, source_symbol_data: *mut u8, len: size_t, received_at: timespec) -> *mut source_symbols_buffer_t {
-    if len != decoder.symbol_size() {
-        panic!("source_symbol_data different from decoder's symbol size");
-    }
-    let buf = unsafe { slice::from_raw_parts_mut(source_symbol_data, len) };
+    let (buf, received_at) = match (slice_from_raw(source_symbol_data, len), instant_from_timespec(received_at)) {
+        (Some(buf), Some(received_at)) => (buf, received_at),
+        _ => return std::ptr::null_mut(),
+    };
     let source_symbol = SourceSymbol::new(source_symbol_metadata_from_u64(metadata), Vec::from(buf));
-
-    const INSTANT_ZERO: std::time::Instant =
-        unsafe { std::mem::transmute(std::time::UNIX_EPOCH) };
-    let received_at = INSTANT_ZERO.add(std::time::Duration::from_secs_f64(received_at.tv_nsec as f64 + (received_at.tv_nsec as f64 / 1_000_000_000.0)));
     match decoder.receive_source_symbol(source_symbol, received_at) {
         Ok(recovered) => Box::into_raw(Box::new(new_source_symbols_buffer(recovered))),
         Err(_) => std::ptr::null_mut(),
@@ -205,14 +300,15 @@ pub extern "C" fn decoder_receive_source_symbol(decoder: &mut decoder_t, metadat
 /// Generates a new repair symbol protecting
 #[no_mangle]
 pub extern "C" fn decoder_receive_and_deserialize_repair_symbol(decoder: &mut decoder_t, repair_symbol_data: *mut u8, len: size_t, consumed: *mut size_t) -> *mut source_symbols_buffer_t {
-    
-    // let repair_symbol = RepairSymbol::new(source_symbol_metadata_from_u64(metadata), Vec::from(buf));
-    let repair_symbol = RepairSymbol{
-        data: unsafe { slice::from_raw_parts_mut(repair_symbol_data, len) }.to_vec(),
+    let repair_symbol = match slice_from_raw(repair_symbol_data, len) {
+        Some(buf) => RepairSymbol{ data: buf.to_vec() },
+        None => return std::ptr::null_mut(),
     };
     match decoder.receive_and_deserialize_repair_symbol(repair_symbol) {
         Ok((cons, recovered)) => {
-            unsafe { *consumed = cons };
+            if !consumed.is_null() {
+                unsafe { *consumed = cons };
+            }
             Box::into_raw(Box::new(new_source_symbols_buffer(recovered)))
         }
         Err(_) => std::ptr::null_mut()
@@ -223,7 +319,10 @@ pub extern "C" fn decoder_receive_and_deserialize_repair_symbol(decoder: &mut de
 /// reads the payload and tells the length of the repair symbol including the symbol size + potential metadata
 #[no_mangle]
 pub extern "C" fn decoder_get_repair_symbol_payload_length(decoder: &decoder_t, data: *mut u8, total_len: size_t) -> ssize_t {
-    let buf = unsafe { slice::from_raw_parts_mut(data, total_len) };
+    let buf = match slice_from_raw(data, total_len) {
+        Some(buf) => buf,
+        None => return DecoderError::BufferTooSmall.to_c(),
+    };
     match decoder.read_repair_symbol(buf) {
         Ok((consumed, _)) => {
             consumed as isize
@@ -234,11 +333,15 @@ pub extern "C" fn decoder_get_repair_symbol_payload_length(decoder: &decoder_t,
 
 #[no_mangle]
 pub extern "C" fn decoder_read_source_symbol_metadata(decoder: &decoder_t, data: *mut u8, len: size_t, out: *mut source_symbol_metadata_t) -> ssize_t {
-    let buf = unsafe { slice::from_raw_parts_mut(data, len) };
-
+    let buf = match slice_from_raw(data, len) {
+        Some(buf) => buf,
+        None => return DecoderError::BufferTooSmall.to_c(),
+    };
     match decoder.read_source_symbol_metadata(buf) {
         Ok((consumed, metadata)) => {
-            unsafe { *out = source_symbol_metadata_to_u64(metadata) };
+            if !out.is_null() {
+                unsafe { *out = source_symbol_metadata_to_u64(metadata) };
+            }
             consumed as isize
         }
         Err(e) => e.to_c()
@@ -258,12 +361,72 @@ pub extern "C" fn decoder_symbol_size(decoder: &decoder_t) -> size_t {
     }
 }
 
+///
+/// Removes the source symbols up to md, expired at the given CLOCK_MONOTONIC time, and writes the
+/// metadata returned by the decoder to out if it is not NULL.
+/// Returns 0 on success and a negative error code if expired_at is not a valid time
+#[no_mangle]
+pub extern "C" fn decoder_remove_up_to(decoder: &mut decoder_t, md: source_symbol_metadata_t, expired_at: timespec, out: *mut source_symbol_metadata_t) -> ssize_t {
+    let expired_at = match instant_from_timespec(expired_at) {
+        Some(expired_at) => expired_at,
+        None => return DecoderError::InvalidTimestamp.to_c(),
+    };
+    let removed = source_symbol_metadata_to_u64(decoder.remove_up_to(source_symbol_metadata_from_u64(md), Some(expired_at)));
+    if !out.is_null() {
+        unsafe { *out = removed };
+    }
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn decoder_stats(decoder: &decoder_t) -> decoder_stats_t {
+    decoder.stats()
+}
+
 #[no_mangle]
-pub extern "C" fn decoder_remove_up_to(decoder: &mut decoder_t, md: source_symbol_metadata_t, expired_at: timespec) -> source_symbol_metadata_t {
-    const INSTANT_ZERO: std::time::Instant =
-        unsafe { std::mem::transmute(std::time::UNIX_EPOCH) };
-    let expired_at = INSTANT_ZERO.add(std::time::Duration::from_secs_f64(expired_at.tv_nsec as f64 + (expired_at.tv_nsec as f64 / 1_000_000_000.0)));
-    source_symbol_metadata_to_u64(decoder.remove_up_to(source_symbol_metadata_from_u64(md), Some(expired_at)))
+pub extern "C" fn decoder_limits(decoder: &decoder_t) -> decoder_limits_t {
+    decoder.limits()
 }
 
+///
+/// Symbols exceeding the limits are rejected, decoder_stats counts them as rejected_symbols
+#[no_mangle]
+pub extern "C" fn decoder_set_limits(decoder: &mut decoder_t, limits: decoder_limits_t) {
+    decoder.set_limits(limits)
+}
 
+///
+/// Builds the encoder and the decoder of a configuration, either of them can be NULL if only one side is needed.
+/// Returns 0 on success and a negative error code if the configuration is invalid or not supported by this build,
+/// in which case neither encoder nor decoder is written
+#[no_mangle]
+pub extern "C" fn new_codec(config: &codec_config_t, encoder: *mut *mut encoder_t, decoder: *mut *mut decoder_t) -> ssize_t {
+    let config = match config.to_config() {
+        Ok(config) => config,
+        Err(e) => return e.to_c(),
+    };
+    // both are built before writing either, so that a failure leaves nothing to release
+    let enc = if encoder.is_null() {
+        None
+    } else {
+        match config.new_encoder() {
+            Ok(enc) => Some(enc),
+            Err(e) => return e.to_c(),
+        }
+    };
+    let dec = if decoder.is_null() {
+        None
+    } else {
+        match config.new_decoder() {
+            Ok(dec) => Some(dec),
+            Err(e) => return e.to_c(),
+        }
+    };
+    if let Some(enc) = enc {
+        unsafe { *encoder = Box::into_raw(Box::new(enc)) }
+    }
+    if let Some(dec) = dec {
+        unsafe { *decoder = Box::into_raw(Box::new(dec)) }
+    }
+    0
+}
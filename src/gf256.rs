@@ -0,0 +1,315 @@
+//! GF(2^8) arithmetic on whole symbols, over the x^8 + x^4 + x^3 + x^2 + 1 polynomial used by the VLC
+//! decoder. The region operations pick the widest SIMD implementation supported by the CPU at runtime:
+//! AVX-512BW, AVX2 or SSSE3 on x86_64, NEON on aarch64, and a portable implementation otherwise.
+//! They all use the split-table method: c * x = c * (x & 0x0f) ^ c * (x & 0xf0), each half being looked up
+//! in a 16-entry table with a byte shuffle.
+//! The module stands alone: the VLC and RLC codecs compute inside vandermonde_lc, rustrlc and rustgf and do not
+//! call it, so it does not speed them up.
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const POLYNOMIAL: u16 = 0x11d;
+
+const fn exp_log_tables() -> ([u8; 512], [u8; 256]) {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        exp[i + 255] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= POLYNOMIAL;
+        }
+        i += 1;
+    }
+    (exp, log)
+}
+
+const TABLES: ([u8; 512], [u8; 256]) = exp_log_tables();
+static EXP: [u8; 512] = TABLES.0;
+static LOG: [u8; 256] = TABLES.1;
+
+pub fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+}
+
+///
+/// Multiplicative inverse, None for 0
+pub fn inv(a: u8) -> Option<u8> {
+    if a == 0 {
+        return None;
+    }
+    Some(EXP[255 - LOG[a as usize] as usize])
+}
+
+/// Region operation implementations, in increasing order of width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Portable,
+    Ssse3,
+    Avx2,
+    Avx512,
+    Neon,
+}
+
+impl Backend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Portable => "portable",
+            Backend::Ssse3 => "ssse3",
+            Backend::Avx2 => "avx2",
+            Backend::Avx512 => "avx512",
+            Backend::Neon => "neon",
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Backend::Portable => true,
+            #[cfg(all(feature = "std", target_arch = "x86_64"))]
+            Backend::Ssse3 => is_x86_feature_detected!("ssse3"),
+            #[cfg(all(feature = "std", target_arch = "x86_64"))]
+            Backend::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(all(feature = "std", target_arch = "x86_64"))]
+            Backend::Avx512 => is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512bw"),
+            #[cfg(all(feature = "std", target_arch = "aarch64"))]
+            Backend::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            // without std, only the features enabled at compile time are used
+            #[cfg(all(not(feature = "std"), target_arch = "x86_64"))]
+            Backend::Ssse3 => cfg!(target_feature = "ssse3"),
+            #[cfg(all(not(feature = "std"), target_arch = "x86_64"))]
+            Backend::Avx2 => cfg!(target_feature = "avx2"),
+            #[cfg(all(not(feature = "std"), target_arch = "x86_64"))]
+            Backend::Avx512 => cfg!(target_feature = "avx512f") && cfg!(target_feature = "avx512bw"),
+            #[cfg(all(not(feature = "std"), target_arch = "aarch64"))]
+            Backend::Neon => cfg!(target_feature = "neon"),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    ///
+    /// The backends supported by the CPU, the portable one first
+    pub fn supported() -> Vec<Backend> {
+        [Backend::Portable, Backend::Ssse3, Backend::Avx2, Backend::Avx512, Backend::Neon]
+            .iter()
+            .copied()
+            .filter(Backend::is_supported)
+            .collect()
+    }
+}
+
+const BACKEND_UNKNOWN: u8 = u8::MAX;
+static DETECTED_BACKEND: AtomicU8 = AtomicU8::new(BACKEND_UNKNOWN);
+
+///
+/// The widest backend supported by the CPU, detected once
+pub fn backend() -> Backend {
+    let all = [Backend::Portable, Backend::Ssse3, Backend::Avx2, Backend::Avx512, Backend::Neon];
+    match DETECTED_BACKEND.load(Ordering::Relaxed) {
+        BACKEND_UNKNOWN => {
+            let best = *Backend::supported().last().unwrap();
+            DETECTED_BACKEND.store(all.iter().position(|b| *b == best).unwrap() as u8, Ordering::Relaxed);
+            best
+        }
+        index => all[index as usize],
+    }
+}
+
+/// Products of a constant by every low and high nibble
+struct NibbleTables {
+    lo: [u8; 16],
+    hi: [u8; 16],
+}
+
+impl NibbleTables {
+    fn new(c: u8) -> NibbleTables {
+        let mut tables = NibbleTables { lo: [0; 16], hi: [0; 16] };
+        for i in 0..16 {
+            tables.lo[i] = mul(c, i as u8);
+            tables.hi[i] = mul(c, (i as u8) << 4);
+        }
+        tables
+    }
+
+    #[inline(always)]
+    fn mul(&self, x: u8) -> u8 {
+        self.lo[(x & 0x0f) as usize] ^ self.hi[(x >> 4) as usize]
+    }
+}
+
+///
+/// dst ^= c * src, with the default backend
+pub fn region_mul_add(dst: &mut [u8], src: &[u8], c: u8) {
+    region_mul_add_with(backend(), dst, src, c)
+}
+
+///
+/// dst = c * dst, with the default backend
+pub fn region_mul(dst: &mut [u8], c: u8) {
+    region_mul_with(backend(), dst, c)
+}
+
+///
+/// dst ^= c * src with the given backend, which must be supported by the CPU
+pub fn region_mul_add_with(backend: Backend, dst: &mut [u8], src: &[u8], c: u8) {
+    assert_eq!(dst.len(), src.len(), "regions of different lengths");
+    assert!(backend.is_supported(), "unsupported backend {:?}", backend);
+    match c {
+        0 => (),
+        1 => dst.iter_mut().zip(src).for_each(|(d, s)| *d ^= s),
+        // the pointers come from valid slices of the same length and the backend is supported
+        _ => unsafe { run::<true>(backend, dst.as_mut_ptr(), src.as_ptr(), dst.len(), &NibbleTables::new(c)) },
+    }
+}
+
+///
+/// dst = c * dst with the given backend, which must be supported by the CPU
+pub fn region_mul_with(backend: Backend, dst: &mut [u8], c: u8) {
+    assert!(backend.is_supported(), "unsupported backend {:?}", backend);
+    match c {
+        0 => dst.iter_mut().for_each(|d| *d = 0),
+        1 => (),
+        // the kernels load each chunk of src before storing it to dst, so they can work in place. Both pointers
+        // derive from the same raw pointer: reading through a shared borrow of dst while writing would be UB
+        _ => unsafe {
+            let p = dst.as_mut_ptr();
+            run::<false>(backend, p, p as *const u8, dst.len(), &NibbleTables::new(c))
+        },
+    }
+}
+
+///
+/// Eliminates the pivot from every row, the core step of the Gaussian elimination: rows[i] ^= coefficients[i] * pivot
+pub fn eliminate(rows: &mut [&mut [u8]], pivot: &[u8], coefficients: &[u8]) {
+    assert_eq!(rows.len(), coefficients.len(), "one coefficient per row");
+    let backend = backend();
+    for (row, &c) in rows.iter_mut().zip(coefficients) {
+        region_mul_add_with(backend, row, pivot, c);
+    }
+}
+
+/// Computes dst = c * src, or dst ^= c * src if ADD, for len bytes.
+/// The tail left by a wide kernel goes through the narrower ones, as a 1200-byte symbol leaves 48 bytes after AVX-512
+#[cfg_attr(not(any(target_arch = "x86_64", target_arch = "aarch64")), allow(unused_variables, unused_mut))]
+unsafe fn run<const ADD: bool>(backend: Backend, dst: *mut u8, src: *const u8, len: usize, tables: &NibbleTables) {
+    let mut done = 0;
+    #[cfg(target_arch = "x86_64")]
+    {
+        if backend == Backend::Avx512 {
+            done += x86::avx512::<ADD>(dst.add(done), src.add(done), len - done, tables);
+        }
+        if backend == Backend::Avx512 || backend == Backend::Avx2 {
+            done += x86::avx2::<ADD>(dst.add(done), src.add(done), len - done, tables);
+        }
+        if backend != Backend::Portable {
+            done += x86::ssse3::<ADD>(dst.add(done), src.add(done), len - done, tables);
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if backend == Backend::Neon {
+            done += neon::neon::<ADD>(dst, src, len, tables);
+        }
+    }
+    portable::<ADD>(dst.add(done), src.add(done), len - done, tables);
+}
+
+unsafe fn portable<const ADD: bool>(dst: *mut u8, src: *const u8, len: usize, tables: &NibbleTables) {
+    for i in 0..len {
+        let product = tables.mul(*src.add(i));
+        *dst.add(i) = if ADD { *dst.add(i) ^ product } else { product };
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    use super::NibbleTables;
+
+    /// Each kernel processes the largest multiple of its width and returns the number of processed bytes
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn ssse3<const ADD: bool>(dst: *mut u8, src: *const u8, len: usize, tables: &NibbleTables) -> usize {
+        let lo = _mm_loadu_si128(tables.lo.as_ptr() as *const __m128i);
+        let hi = _mm_loadu_si128(tables.hi.as_ptr() as *const __m128i);
+        let mask = _mm_set1_epi8(0x0f);
+        let n = len / 16 * 16;
+        for i in (0..n).step_by(16) {
+            let s = _mm_loadu_si128(src.add(i) as *const __m128i);
+            let product = _mm_xor_si128(
+                _mm_shuffle_epi8(lo, _mm_and_si128(s, mask)),
+                _mm_shuffle_epi8(hi, _mm_and_si128(_mm_srli_epi64(s, 4), mask)),
+            );
+            let out = if ADD { _mm_xor_si128(_mm_loadu_si128(dst.add(i) as *const __m128i), product) } else { product };
+            _mm_storeu_si128(dst.add(i) as *mut __m128i, out);
+        }
+        n
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn avx2<const ADD: bool>(dst: *mut u8, src: *const u8, len: usize, tables: &NibbleTables) -> usize {
+        // vpshufb shuffles within each 128-bit lane, both lanes hold the tables
+        let lo = _mm256_broadcastsi128_si256(_mm_loadu_si128(tables.lo.as_ptr() as *const __m128i));
+        let hi = _mm256_broadcastsi128_si256(_mm_loadu_si128(tables.hi.as_ptr() as *const __m128i));
+        let mask = _mm256_set1_epi8(0x0f);
+        let n = len / 32 * 32;
+        for i in (0..n).step_by(32) {
+            let s = _mm256_loadu_si256(src.add(i) as *const __m256i);
+            let product = _mm256_xor_si256(
+                _mm256_shuffle_epi8(lo, _mm256_and_si256(s, mask)),
+                _mm256_shuffle_epi8(hi, _mm256_and_si256(_mm256_srli_epi64(s, 4), mask)),
+            );
+            let out = if ADD { _mm256_xor_si256(_mm256_loadu_si256(dst.add(i) as *const __m256i), product) } else { product };
+            _mm256_storeu_si256(dst.add(i) as *mut __m256i, out);
+        }
+        n
+    }
+
+    #[target_feature(enable = "avx512f,avx512bw")]
+    pub(super) unsafe fn avx512<const ADD: bool>(dst: *mut u8, src: *const u8, len: usize, tables: &NibbleTables) -> usize {
+        let lo = _mm512_broadcast_i32x4(_mm_loadu_si128(tables.lo.as_ptr() as *const __m128i));
+        let hi = _mm512_broadcast_i32x4(_mm_loadu_si128(tables.hi.as_ptr() as *const __m128i));
+        let mask = _mm512_set1_epi8(0x0f);
+        let n = len / 64 * 64;
+        for i in (0..n).step_by(64) {
+            let s = _mm512_loadu_si512(src.add(i) as *const _);
+            let product = _mm512_xor_si512(
+                _mm512_shuffle_epi8(lo, _mm512_and_si512(s, mask)),
+                _mm512_shuffle_epi8(hi, _mm512_and_si512(_mm512_srli_epi64(s, 4), mask)),
+            );
+            let out = if ADD { _mm512_xor_si512(_mm512_loadu_si512(dst.add(i) as *const _), product) } else { product };
+            _mm512_storeu_si512(dst.add(i) as *mut _, out);
+        }
+        n
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use core::arch::aarch64::*;
+
+    use super::NibbleTables;
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn neon<const ADD: bool>(dst: *mut u8, src: *const u8, len: usize, tables: &NibbleTables) -> usize {
+        let lo = vld1q_u8(tables.lo.as_ptr());
+        let hi = vld1q_u8(tables.hi.as_ptr());
+        let mask = vdupq_n_u8(0x0f);
+        let n = len / 16 * 16;
+        for i in (0..n).step_by(16) {
+            let s = vld1q_u8(src.add(i));
+            let product = veorq_u8(vqtbl1q_u8(lo, vandq_u8(s, mask)), vqtbl1q_u8(hi, vshrq_n_u8::<4>(s)));
+            let out = if ADD { veorq_u8(vld1q_u8(dst.add(i)), product) } else { product };
+            vst1q_u8(dst.add(i), out);
+        }
+        n
+    }
+}
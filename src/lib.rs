@@ -1,30 +1,102 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
 use byteorder::{BigEndian, ByteOrder};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "enable-rlc")]
 use crate::rlc::decoder::RLCDecoder;
 #[cfg(feature = "enable-rlc")]
 use crate::rlc::encoder::RLCEncoder;
 
-use std::fmt::Debug;
+#[cfg(feature = "std")]
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use crate::events::EventSink;
+#[cfg(feature = "std")]
+use crate::limits::DecoderLimits;
+#[cfg(feature = "std")]
+use crate::simulator::Codec;
+#[cfg(feature = "std")]
+use crate::snapshot::{DecoderSnapshot, EncoderSnapshot, SnapshotError};
+#[cfg(feature = "std")]
 use crate::vandermonde_lc::decoder::VLCDecoder;
+#[cfg(feature = "std")]
 use crate::vandermonde_lc::encoder::VLCEncoder;
 
+#[cfg(feature = "std")]
 pub mod vandermonde_lc;
 
+#[cfg(feature = "std")]
+mod budget;
+
+pub mod events;
+
+pub mod gf256;
+
+pub mod limits;
+
+pub mod pool;
+
+#[cfg(feature = "std")]
+pub mod simulator;
+
+#[cfg(feature = "std")]
+pub mod pcap;
+
+#[cfg(feature = "std")]
+pub mod trace;
+
+#[cfg(feature = "std")]
+pub mod snapshot;
+
+#[cfg(feature = "std")]
+pub mod config;
+
+#[cfg(feature = "std")]
+pub mod negotiation;
+
+#[cfg(feature = "std")]
+pub mod tag;
+
 #[cfg(feature = "enable-rlc")]
 pub mod rlc;
 
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
 pub mod ffi;
 
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// Time at which a symbol is received or expires. It is an Instant with std, and the ticks of
+/// a monotonic clock provided by the application without it
+#[cfg(feature = "std")]
+pub type Timestamp = std::time::Instant;
+
+/// Time at which a symbol is received or expires. It is an Instant with std, and the ticks of
+/// a monotonic clock provided by the application without it
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub u64);
+
 pub type SourceSymbolMetadata = [u8; 8];
 
 #[repr(C)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
 pub enum EncoderError {
     InternalError(String),
     BufferTooSmall,
     NoSymbolToGenerate,
     BadMetadata,
+    #[cfg_attr(feature = "serde", serde(rename = "unimplemented_encoder"))]
     UnImplementedEncoder,
     NoNextMetadata,
 }
@@ -33,17 +105,18 @@ impl EncoderError {
     pub fn to_u64(&self) -> u64 {
         match self {
             EncoderError::InternalError(err) => {
-                println!("encoder internal error: {:?}", err);
+                log::error!("encoder internal error: {:?}", err);
                 0
             },
             EncoderError::BufferTooSmall => 1,
             EncoderError::NoSymbolToGenerate => 2,
             EncoderError::BadMetadata => 3,
             EncoderError::UnImplementedEncoder => 4,
-            EncoderError::NoNextMetadata => 4,
+            EncoderError::NoNextMetadata => 5,
         }
     }
 
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
     fn to_c(self) -> libc::ssize_t {
         match self {
             EncoderError::InternalError(_) => -1,
@@ -60,7 +133,7 @@ impl DecoderError {
     pub fn to_u64(&self) -> u64 {
         match self {
             DecoderError::InternalError(err) => {
-                println!("decoder internal error: {:?}", err);
+                log::error!("decoder internal error: {:?}", err);
                 0
             },
             DecoderError::BufferTooSmall => 1,
@@ -68,9 +141,13 @@ impl DecoderError {
             DecoderError::UnImplementedDecoder => 3,
             DecoderError::UnusedRepairSymbol => 4,
             DecoderError::UnusedSourceSymbol => 5,
+            DecoderError::InvalidSymbol => 6,
+            DecoderError::MismatchedCodec => 7,
+            DecoderError::InvalidTimestamp => 8,
         }
     }
 
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
     fn to_c(self) -> libc::ssize_t {
         match self {
             DecoderError::InternalError(_) => -1,
@@ -79,31 +156,153 @@ impl DecoderError {
             DecoderError::UnImplementedDecoder => -4,
             DecoderError::UnusedRepairSymbol => -5,
             DecoderError::UnusedSourceSymbol => -6,
+            DecoderError::InvalidSymbol => -7,
+            DecoderError::MismatchedCodec => -8,
+            DecoderError::InvalidTimestamp => -9,
         }
     }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
 pub enum DecoderError {
     InternalError(String),
     BufferTooSmall,
     BadMetadata,
+    #[cfg_attr(feature = "serde", serde(rename = "unimplemented_decoder"))]
     UnImplementedDecoder,
     UnusedRepairSymbol,
     UnusedSourceSymbol,
+    /// the symbol was rejected without being decoded: its header is inconsistent or it exceeds the DecoderLimits
+    InvalidSymbol,
+    /// the repair symbol is tagged with another scheme, field or layout version than the one of the decoder
+    MismatchedCodec,
+    /// a timestamp given through the C API is not a valid CLOCK_MONOTONIC time
+    InvalidTimestamp,
+}
+
+/// Cumulative encoder counters, along with the current window occupancy
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EncoderStats {
+    pub protected_source_symbols: u64,
+    pub generated_repair_symbols: u64,
+    pub acknowledged_source_symbols: u64,
+    pub current_window_size: u64,
 }
 
+/// Cumulative decoder counters, along with the current window occupancy
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecoderStats {
+    pub received_source_symbols: u64,
+    pub received_repair_symbols: u64,
+    pub recovered_source_symbols: u64,
+    pub unused_source_symbols: u64,
+    pub unused_repair_symbols: u64,
+    pub expired_source_symbols: u64,
+    pub current_window_size: u64,
+    pub rejected_symbols: u64,
+}
+
+// only used by the codec backends
+#[cfg(feature = "std")]
+impl DecoderStats {
+    fn record_error(&mut self, err: &DecoderError) {
+        match err {
+            DecoderError::UnusedSourceSymbol => self.unused_source_symbols += 1,
+            DecoderError::UnusedRepairSymbol => self.unused_repair_symbols += 1,
+            DecoderError::InvalidSymbol | DecoderError::MismatchedCodec => self.rejected_symbols += 1,
+            _ => (),
+        }
+    }
+
+    ///
+    /// The errors of the queued repair symbols do not reach the caller, the symbols they reject count as unused
+    fn record_queued_error(&mut self, err: &DecoderError) {
+        match err {
+            // already counted by record_error
+            DecoderError::UnusedRepairSymbol => (),
+            _ => self.unused_repair_symbols += 1,
+        }
+    }
+
+    fn record_expiration(&mut self, before: Option<(SourceSymbolMetadata, SourceSymbolMetadata)>, after: Option<(SourceSymbolMetadata, SourceSymbolMetadata)>) {
+        if let Some((first_before, last_before)) = before {
+            let first_before = source_symbol_metadata_to_u64(first_before);
+            let first_after = match after {
+                Some((first_after, _)) => source_symbol_metadata_to_u64(first_after),
+                None => source_symbol_metadata_to_u64(last_before) + 1,
+            };
+            self.expired_source_symbols += first_after.saturating_sub(first_before);
+        }
+    }
+}
+
+/// How the coefficients of a repair symbol are derived by the decoder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairSymbolCoefficients {
+    SequenceNumber(u64),
+    Seed(u32),
+}
+
+/// Parsed header of a serialized repair symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepairSymbolHeader {
+    pub first_id: u64,
+    pub n_protected_symbols: u64,
+    pub coefficients: RepairSymbolCoefficients,
+    /// length of the header, the repair data follows it
+    pub header_len: usize,
+}
+
+#[cfg(feature = "std")]
+impl RepairSymbolHeader {
+    ///
+    /// Rejects the headers whose protected range cannot be handed to the codecs: empty or wrapping around the u64 ids
+    pub(crate) fn validate(&self) -> Result<(), DecoderError> {
+        if self.n_protected_symbols == 0 || self.first_id.checked_add(self.n_protected_symbols - 1).is_none() {
+            log::debug!("invalid repair symbol range: first id {}, {} symbols", self.first_id, self.n_protected_symbols);
+            return Err(DecoderError::InvalidSymbol);
+        }
+        Ok(())
+    }
+}
+
+/// Serialized as {"id": u64, "data": bytes}
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SourceSymbol {
+    #[cfg_attr(feature = "serde", serde(rename = "id", with = "metadata_as_id"))]
     metadata: SourceSymbolMetadata,
+    #[cfg_attr(feature = "serde", serde(with = "serde_bytes"))]
     data: Vec<u8>,
 }
 
+/// Serialized as {"data": bytes}, the serialized repair symbol as sent on the wire
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RepairSymbol {
+    #[cfg_attr(feature = "serde", serde(with = "serde_bytes"))]
     data: Vec<u8>,
 }
 
+/// Serializes the metadata of the source symbols as their id
+#[cfg(feature = "serde")]
+mod metadata_as_id {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::{SourceSymbolMetadata, source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+    pub fn serialize<S: Serializer>(metadata: &SourceSymbolMetadata, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(source_symbol_metadata_to_u64(*metadata))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SourceSymbolMetadata, D::Error> {
+        u64::deserialize(deserializer).map(source_symbol_metadata_from_u64)
+    }
+}
+
 impl RepairSymbol {
     pub fn wire_len(&self) -> usize {
         self.data.len()
@@ -139,28 +338,83 @@ impl SourceSymbol {
     }
 }
 
+#[cfg(feature = "std")]
+/// Symbols recovered by the borrowing Decoder methods. Their data is borrowed from the decoder,
+/// so it stays valid until the next call to the decoder
+pub struct RecoveredSymbols<'a> {
+    decoder: &'a Decoder,
+    ids: alloc::vec::IntoIter<u64>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> Iterator for RecoveredSymbols<'a> {
+    type Item = (SourceSymbolMetadata, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let decoder = self.decoder;
+        self.ids.by_ref()
+            .find_map(|id| decoder.recovered_data(id).map(|data| (source_symbol_metadata_from_u64(id), data)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.ids.len()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> RecoveredSymbols<'a> {
+    ///
+    /// Copies the recovered symbols into the given slots of symbol_size bytes and returns their
+    /// metadata, in the same order. Returns BufferTooSmall without copying anything if there are
+    /// not enough slots or one of them is too small
+    pub fn copy_into(self, slots: &mut [&mut [u8]]) -> Result<Vec<SourceSymbolMetadata>, DecoderError> {
+        let symbol_size = self.decoder.symbol_size();
+        if self.ids.len() > slots.len() || slots.iter().take(self.ids.len()).any(|slot| slot.len() < symbol_size) {
+            return Err(DecoderError::BufferTooSmall);
+        }
+        let mut ret = Vec::with_capacity(self.ids.len());
+        for ((md, data), slot) in self.zip(slots.iter_mut()) {
+            slot[..data.len()].copy_from_slice(data);
+            ret.push(md);
+        }
+        Ok(ret)
+    }
+}
+
+/// A received packet given to Decoder::receive_batch
+#[derive(Debug, Clone, Copy)]
+pub enum ReceivedPacket<'a> {
+    Source(SourceSymbolMetadata, &'a [u8]),
+    /// a serialized repair symbol, as given to read_repair_symbol
+    Repair(&'a [u8]),
+}
+
+#[cfg(feature = "std")]
 pub enum Encoder {
     #[cfg(feature = "enable-rlc")]
     RLC(RLCEncoder),
     VLC(VLCEncoder),
 }
 
+#[cfg(feature = "std")]
 pub enum Decoder {
     #[cfg(feature = "enable-rlc")]
     RLC(RLCDecoder),
     VLC(VLCDecoder),
 }
 
+#[cfg(feature = "std")]
 impl Debug for Encoder {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             #[cfg(feature = "enable-rlc")]
-            Self::VLC(enc) => f.write_fmt(format_args!("RLC Encoder, bounds = [{:?}, {:?}]", enc.first_metadata(), enc.last_metadata())),
+            Self::RLC(enc) => f.write_fmt(format_args!("RLC Encoder, bounds = [{:?}, {:?}]", enc.first_metadata(), enc.last_metadata())),
             Self::VLC(enc) => f.write_fmt(format_args!("VLC Encoder, bounds = [{:?}, {:?}]", enc.first_metadata(), enc.last_metadata())),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl Encoder {
 
     ///
@@ -264,6 +518,50 @@ impl Encoder {
         }
     }
 
+    ///
+    /// Protects every buffer of the batch and pushes the metadata of each protected symbol into metadata.
+    /// Returns the number of protected symbols. On error, the symbols preceding the failing one stay protected.
+    /// This is a convenience wrapper calling protect_data for each buffer, nothing is amortised across the batch
+    pub fn protect_batch<I: IntoIterator<Item = Vec<u8>>>(&mut self, batch: I, metadata: &mut Vec<SourceSymbolMetadata>) -> Result<usize, EncoderError> {
+        let mut n = 0;
+        for data in batch {
+            let mut md = source_symbol_metadata_from_u64(0);
+            self.protect_data(data, &mut md)?;
+            metadata.push(md);
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    ///
+    /// Generates count repair symbols, all protecting the current window. This is a convenience wrapper, the
+    /// codec computes each repair symbol with its own pass over the window
+    pub fn generate_repair_batch(&mut self, count: usize) -> Result<Vec<RepairSymbol>, EncoderError> {
+        let up_to = self.last_metadata().ok_or(EncoderError::NoSymbolToGenerate)?;
+        let mut ret = Vec::with_capacity(count);
+        for _ in 0..count {
+            ret.push(self.generate_and_serialize_repair_symbol_up_to(up_to)?);
+        }
+        Ok(ret)
+    }
+
+    ///
+    /// Fills out with as many repair symbols protecting the current window as it can hold, each one
+    /// in a segment of segment_size bytes, as expected by UDP GSO. Returns the number of generated symbols.
+    /// Like generate_repair_batch, each repair symbol is computed on its own
+    pub fn generate_repair_batch_in_place(&mut self, out: &mut [u8], segment_size: usize) -> Result<usize, EncoderError> {
+        let up_to = self.last_metadata().ok_or(EncoderError::NoSymbolToGenerate)?;
+        if segment_size == 0 || segment_size < self.next_repair_symbol_size(up_to)? {
+            return Err(EncoderError::BufferTooSmall);
+        }
+        let mut n = 0;
+        for segment in out.chunks_exact_mut(segment_size) {
+            self.generate_and_serialize_repair_symbol_in_place_up_to(segment, up_to)?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
     pub fn can_send_repair_symbols(&self) -> bool {
         match self {
             #[cfg(feature = "enable-rlc")]
@@ -362,7 +660,7 @@ impl Encoder {
         }
     }
 
-    pub fn get_sent_time(&self, md: SourceSymbolMetadata) -> Option<std::time::Instant> {
+    pub fn get_sent_time(&self, md: SourceSymbolMetadata) -> Option<Timestamp> {
         match self {
             #[cfg(feature = "enable-rlc")]
             Encoder::RLC(enc) => {
@@ -399,18 +697,134 @@ impl Encoder {
         }
     }
 
+    pub fn stats(&self) -> EncoderStats {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Encoder::RLC(enc) => {
+                enc.stats()
+            }
+            Encoder::VLC(enc) => {
+                enc.stats()
+            }
+        }
+    }
+
+    ///
+    /// Installs (or removes) the sink receiving the events of this encoder
+    pub fn set_event_sink(&mut self, sink: Option<Box<dyn EventSink>>) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Encoder::RLC(enc) => {
+                enc.set_event_sink(sink)
+            }
+            Encoder::VLC(enc) => {
+                enc.set_event_sink(sink)
+            }
+        }
+    }
+
+    ///
+    /// Returns an empty buffer from the pool of the encoder, to be filled and given to protect_data
+    pub fn take_buffer(&mut self) -> Vec<u8> {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Encoder::RLC(enc) => {
+                enc.take_buffer()
+            }
+            Encoder::VLC(enc) => {
+                enc.take_buffer()
+            }
+        }
+    }
+
+    ///
+    /// Gives the buffer of a sent RepairSymbol back to the encoder, to be reused by the next repair symbols
+    pub fn recycle(&mut self, buffer: Vec<u8>) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Encoder::RLC(enc) => {
+                enc.recycle(buffer)
+            }
+            Encoder::VLC(enc) => {
+                enc.recycle(buffer)
+            }
+        }
+    }
+
+
+    ///
+    /// Starts or stops prefixing the generated repair symbols with a tag::RepairSymbolTag identifying the codec.
+    /// The decoders of the session must be configured the same way
+    pub fn set_repair_symbol_tag(&mut self, enabled: bool) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Encoder::RLC(enc) => {
+                enc.set_repair_symbol_tag(enabled)
+            }
+            Encoder::VLC(enc) => {
+                enc.set_repair_symbol_tag(enabled)
+            }
+        }
+    }
+
+    ///
+    /// Starts or stops keeping a copy of the protected data, which snapshot() needs as the codecs do not give
+    /// it back. Snapshots must be enabled before the first symbol of the window to snapshot is protected
+    pub fn set_snapshots(&mut self, enabled: bool) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Encoder::RLC(enc) => {
+                enc.set_snapshots(enabled)
+            }
+            Encoder::VLC(enc) => {
+                enc.set_snapshots(enabled)
+            }
+        }
+    }
+
+    ///
+    /// Captures the window contents, the symbol ids and the repair symbol generation state.
+    /// Fails with SnapshotError::NotRestorable for VLC, whose repair sequence numbers cannot be restored
+    pub fn snapshot(&self) -> Result<EncoderSnapshot, SnapshotError> {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Encoder::RLC(enc) => {
+                enc.snapshot()
+            }
+            Encoder::VLC(enc) => {
+                enc.snapshot()
+            }
+        }
+    }
+
+    ///
+    /// Builds an encoder continuing the session of the snapshot: the next protected symbols and
+    /// repair symbols follow the ones generated before the snapshot. Snapshots are enabled in the restored encoder
+    pub fn restore(snapshot: &EncoderSnapshot) -> Result<Encoder, SnapshotError> {
+        snapshot.check_sizes()?;
+        match snapshot.codec {
+            #[cfg(feature = "enable-rlc")]
+            Codec::RLC { .. } => {
+                Ok(Encoder::RLC(RLCEncoder::restore(snapshot)?))
+            }
+            Codec::VLC => {
+                Ok(Encoder::VLC(VLCEncoder::restore(snapshot)?))
+            }
+        }
+    }
 }
 
+#[cfg(feature = "std")]
 impl Decoder {
 
     ///
     /// Protects the given data and serializes its metadata into output.
     /// Returns the amount of written bytes on success
-    pub fn receive_source_symbol(&mut self, source_symbol: SourceSymbol, received_at: std::time::Instant) -> Result<Vec<SourceSymbol>, DecoderError> {
+    pub fn receive_source_symbol(&mut self, source_symbol: SourceSymbol, received_at: Timestamp) -> Result<Vec<SourceSymbol>, DecoderError> {
         match self {
             #[cfg(feature = "enable-rlc")]
             Decoder::RLC(dec) => {
-                dec.receive_source_symbol(source_symbol)
+                dec.receive_source_symbol(source_symbol, received_at)
             }
             Decoder::VLC(dec) => {
                 dec.receive_source_symbol(source_symbol, received_at)
@@ -433,6 +847,82 @@ impl Decoder {
     }
 
 
+    ///
+    /// Same as receive_source_symbol without taking ownership of the data. The data is still copied once, into
+    /// a buffer of the decoder pool, because the codecs keep their own copy of the source symbols. The recovered
+    /// symbols are borrowed from the decoder instead of being copied out of it
+    pub fn receive_source_symbol_borrowed(&mut self, metadata: SourceSymbolMetadata, data: &[u8], received_at: Timestamp) -> Result<RecoveredSymbols<'_>, DecoderError> {
+        let ids = match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.receive_source_symbol_borrowed(metadata, data, received_at)?
+            }
+            Decoder::VLC(dec) => {
+                dec.receive_source_symbol_borrowed(metadata, data, received_at)?
+            }
+        };
+        Ok(RecoveredSymbols { decoder: self, ids: ids.into_iter() })
+    }
+
+    ///
+    /// Same as read_repair_symbol followed by receive_and_deserialize_repair_symbol, without copying
+    /// the serialized repair symbol: only its payload is copied, into a buffer of the decoder pool, which the
+    /// codecs keep. The recovered symbols are borrowed from the decoder
+    pub fn receive_repair_symbol_borrowed(&mut self, data: &[u8]) -> Result<(usize, RecoveredSymbols<'_>), DecoderError> {
+        let (consumed, ids) = match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.receive_repair_symbol_borrowed(data)?
+            }
+            Decoder::VLC(dec) => {
+                dec.receive_repair_symbol_borrowed(data)?
+            }
+        };
+        Ok((consumed, RecoveredSymbols { decoder: self, ids: ids.into_iter() }))
+    }
+
+    ///
+    /// Receives a batch of packets, the source symbols before the repair symbols so that the latter
+    /// are only used for the symbols missing from the batch. Returns the symbols recovered over the
+    /// whole batch along with the errors of the rejected packets, indexed by their position in the batch.
+    /// This is a convenience wrapper around the borrowing receive functions, the codec still updates its
+    /// window and eliminates the repair symbols one symbol at a time
+    pub fn receive_batch(&mut self, packets: &[ReceivedPacket], received_at: Timestamp) -> (RecoveredSymbols<'_>, Vec<(usize, DecoderError)>) {
+        let mut ids = Vec::new();
+        let mut errors = Vec::new();
+        let sources = packets.iter().enumerate().filter(|(_, packet)| matches!(packet, ReceivedPacket::Source(..)));
+        let repairs = packets.iter().enumerate().filter(|(_, packet)| matches!(packet, ReceivedPacket::Repair(..)));
+        for (i, packet) in sources.chain(repairs) {
+            let res = match (&mut *self, packet) {
+                #[cfg(feature = "enable-rlc")]
+                (Decoder::RLC(dec), ReceivedPacket::Source(md, data)) => dec.receive_source_symbol_borrowed(*md, data, received_at),
+                #[cfg(feature = "enable-rlc")]
+                (Decoder::RLC(dec), ReceivedPacket::Repair(data)) => dec.receive_repair_symbol_borrowed(data).map(|(_, ids)| ids),
+                (Decoder::VLC(dec), ReceivedPacket::Source(md, data)) => dec.receive_source_symbol_borrowed(*md, data, received_at),
+                (Decoder::VLC(dec), ReceivedPacket::Repair(data)) => dec.receive_repair_symbol_borrowed(data).map(|(_, ids)| ids),
+            };
+            match res {
+                Ok(recovered) => ids.extend(recovered),
+                Err(err) => errors.push((i, err)),
+            }
+        }
+        (RecoveredSymbols { decoder: self, ids: ids.into_iter() }, errors)
+    }
+
+    ///
+    /// Data of a symbol received or recovered by the decoder and still in its window
+    pub fn recovered_data(&self, id: u64) -> Option<&[u8]> {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.recovered_data(id)
+            }
+            Decoder::VLC(dec) => {
+                dec.recovered_data(id)
+            }
+        }
+    }
+
     pub fn read_repair_symbol(&self, data: &[u8]) -> Result<(usize, RepairSymbol), DecoderError> {
         match self {
             #[cfg(feature = "enable-rlc")]
@@ -445,6 +935,18 @@ impl Decoder {
         }
     }
 
+    pub fn read_repair_symbol_header(&self, data: &[u8]) -> Result<RepairSymbolHeader, DecoderError> {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.read_repair_symbol_header(data)
+            }
+            Decoder::VLC(dec) => {
+                dec.read_repair_symbol_header(data)
+            }
+        }
+    }
+
     pub fn read_source_symbol_metadata(&self, data: &[u8]) -> Result<(usize, SourceSymbolMetadata), DecoderError> {
         match self {
             #[cfg(feature = "enable-rlc")]
@@ -469,7 +971,7 @@ impl Decoder {
         }
     }
 
-    pub fn remove_up_to(&mut self, md: SourceSymbolMetadata, expired_at: Option<std::time::Instant>) -> SourceSymbolMetadata {
+    pub fn remove_up_to(&mut self, md: SourceSymbolMetadata, expired_at: Option<Timestamp>) -> SourceSymbolMetadata {
         match self {
             #[cfg(feature = "enable-rlc")]
             Decoder::RLC(dec) => {
@@ -504,6 +1006,206 @@ impl Decoder {
             }
         }
     }
+
+    pub fn stats(&self) -> DecoderStats {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.stats()
+            }
+            Decoder::VLC(dec) => {
+                dec.stats()
+            }
+        }
+    }
+
+    ///
+    /// Installs (or removes) the sink receiving the events of this decoder
+    pub fn set_event_sink(&mut self, sink: Option<Box<dyn EventSink>>) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.set_event_sink(sink)
+            }
+            Decoder::VLC(dec) => {
+                dec.set_event_sink(sink)
+            }
+        }
+    }
+
+    pub fn limits(&self) -> DecoderLimits {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.limits()
+            }
+            Decoder::VLC(dec) => {
+                dec.limits()
+            }
+        }
+    }
+
+    ///
+    /// Replaces the limits on the received symbols, the default ones are given by DecoderLimits::new(max_window_size)
+    pub fn set_limits(&mut self, limits: DecoderLimits) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.set_limits(limits)
+            }
+            Decoder::VLC(dec) => {
+                dec.set_limits(limits)
+            }
+        }
+    }
+
+    ///
+    /// Paces the repair symbols handed to the codec by each call: a repair symbol protecting n source symbols
+    /// weighs n, and a call hands over at least one queued repair symbol and then as many as fit in the budget.
+    /// The others are queued and handed to the codec by the next calls or by poll_recovery. This is not a bound on
+    /// the CPU time of a call: the source symbols bypass the budget, and the codec may eliminate all its pending
+    /// equations when it receives a single symbol. None, the default, disables it
+    pub fn set_work_budget(&mut self, budget: Option<u64>) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.set_work_budget(budget)
+            }
+            Decoder::VLC(dec) => {
+                dec.set_work_budget(budget)
+            }
+        }
+    }
+
+    pub fn work_budget(&self) -> Option<u64> {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.work_budget()
+            }
+            Decoder::VLC(dec) => {
+                dec.work_budget()
+            }
+        }
+    }
+
+    ///
+    /// Number of received repair symbols waiting for the work budget
+    pub fn pending_repair_symbols(&self) -> usize {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.pending_repair_symbols()
+            }
+            Decoder::VLC(dec) => {
+                dec.pending_repair_symbols()
+            }
+        }
+    }
+
+    ///
+    /// Hands the queued repair symbols fitting in the work budget to the codec and returns the recovered symbols
+    pub fn poll_recovery(&mut self) -> Vec<SourceSymbol> {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.poll_recovery()
+            }
+            Decoder::VLC(dec) => {
+                dec.poll_recovery()
+            }
+        }
+    }
+
+    ///
+    /// Returns an empty buffer from the pool of the decoder, to be filled with received source data
+    pub fn take_buffer(&mut self) -> Vec<u8> {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.take_buffer()
+            }
+            Decoder::VLC(dec) => {
+                dec.take_buffer()
+            }
+        }
+    }
+
+    ///
+    /// Gives the buffer of a delivered recovered SourceSymbol or of a RepairSymbol back to the decoder,
+    /// to be reused by read_repair_symbol and the next recovered symbols
+    pub fn recycle(&mut self, buffer: Vec<u8>) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.recycle(buffer)
+            }
+            Decoder::VLC(dec) => {
+                dec.recycle(buffer)
+            }
+        }
+    }
+
+    ///
+    /// Expects the repair symbols to start with a tag::RepairSymbolTag, and rejects those generated by another
+    /// scheme, field or layout version with DecoderError::MismatchedCodec instead of decoding them
+    pub fn set_repair_symbol_tag(&mut self, enabled: bool) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.set_repair_symbol_tag(enabled)
+            }
+            Decoder::VLC(dec) => {
+                dec.set_repair_symbol_tag(enabled)
+            }
+        }
+    }
+
+    ///
+    /// Starts or stops keeping a copy of the repair symbols handed to the codec, which snapshot() needs to
+    /// capture the equations not solved yet. Snapshots must be enabled before the first repair symbol of the
+    /// window to snapshot is received
+    pub fn set_snapshots(&mut self, enabled: bool) {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.set_snapshots(enabled)
+            }
+            Decoder::VLC(dec) => {
+                dec.set_snapshots(enabled)
+            }
+        }
+    }
+
+    ///
+    /// Captures the received and recovered symbols of the window, the pending equations and the queued repair symbols
+    pub fn snapshot(&self) -> Result<DecoderSnapshot, SnapshotError> {
+        match self {
+            #[cfg(feature = "enable-rlc")]
+            Decoder::RLC(dec) => {
+                dec.snapshot()
+            }
+            Decoder::VLC(dec) => {
+                dec.snapshot()
+            }
+        }
+    }
+
+    ///
+    /// Builds a decoder continuing the session of the snapshot by replaying its symbols, received at received_at.
+    /// Snapshots are enabled in the restored decoder
+    pub fn restore(snapshot: &DecoderSnapshot, received_at: Timestamp) -> Result<Decoder, SnapshotError> {
+        snapshot.check_sizes()?;
+        match snapshot.codec {
+            #[cfg(feature = "enable-rlc")]
+            Codec::RLC { .. } => {
+                Ok(Decoder::RLC(RLCDecoder::restore(snapshot, received_at)?))
+            }
+            Codec::VLC => {
+                Ok(Decoder::VLC(VLCDecoder::restore(snapshot, received_at)?))
+            }
+        }
+    }
 }
 
 pub fn source_symbol_metadata_from_u64(n: u64) -> SourceSymbolMetadata {
@@ -0,0 +1,116 @@
+#[cfg(feature = "std")]
+use alloc::collections::VecDeque;
+
+#[cfg(feature = "std")]
+use crate::{DecoderError, RepairSymbolHeader, SourceSymbolMetadata, source_symbol_metadata_to_u64};
+
+/// Bounds on the symbols a Decoder accepts from the wire, so that a hostile peer cannot make it
+/// buffer an unbounded amount of symbols and equations. Symbols that do not fit are rejected
+/// with DecoderError::InvalidSymbol before reaching the underlying decoder
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecoderLimits {
+    /// maximum number of source symbols protected by a single repair symbol
+    pub max_protected_symbols: u64,
+    /// maximum distance between the last symbol of the window and a newly received symbol. When the
+    /// window is empty, the distance is counted from the first symbol that was not removed yet
+    pub max_future_symbols: u64,
+    /// maximum number of source symbols spanned by the window once the symbol is received
+    pub max_window_span: u64,
+    /// maximum number of repair symbols buffered for the current window
+    pub max_buffered_repair_symbols: u64,
+}
+
+impl DecoderLimits {
+    ///
+    /// Default limits for a decoder created with the given max_window_size. They leave room for the
+    /// window to temporarily exceed max_window_size before the application slides it
+    pub fn new(max_window_size: usize) -> DecoderLimits {
+        let max_window_size = max_window_size as u64;
+        let max_window_span = max_window_size.saturating_mul(2);
+        DecoderLimits {
+            max_protected_symbols: max_window_size,
+            max_future_symbols: max_window_size,
+            max_window_span,
+            max_buffered_repair_symbols: max_window_span,
+        }
+    }
+}
+
+/// Checks the received symbols against the DecoderLimits, shared by the decoder backends
+#[cfg(feature = "std")]
+pub(crate) struct LimitGuard {
+    pub(crate) limits: DecoderLimits,
+    /// last protected id of each accepted repair symbol, in reception order
+    repair_symbols: VecDeque<u64>,
+    /// id of the first symbol that was not removed from the window
+    pub(crate) window_start: u64,
+}
+
+#[cfg(feature = "std")]
+impl LimitGuard {
+    pub(crate) fn new(max_window_size: usize) -> LimitGuard {
+        LimitGuard {
+            limits: DecoderLimits::new(max_window_size),
+            repair_symbols: VecDeque::new(),
+            window_start: 0,
+        }
+    }
+
+    fn reject(reason: &str, first_id: u64, last_id: u64) -> Result<(), DecoderError> {
+        log::debug!("rejected symbol protecting [{}, {}]: {}", first_id, last_id, reason);
+        Err(DecoderError::InvalidSymbol)
+    }
+
+    /// checks that the range [first_id, last_id] can be added to a window with the given bounds
+    fn check_range(&self, first_id: u64, last_id: u64, bounds: Option<(u64, u64)>) -> Result<(), DecoderError> {
+        let (first, too_far) = match bounds {
+            Some((first, last)) => (first, last_id > last && last_id - last > self.limits.max_future_symbols),
+            // an empty window behaves as if it ended right before the first symbol that was not removed
+            None => (self.window_start, last_id >= self.window_start && last_id - self.window_start >= self.limits.max_future_symbols),
+        };
+        if too_far {
+            return Self::reject("too far ahead of the window", first_id, last_id);
+        }
+        if last_id >= first && last_id - first >= self.limits.max_window_span {
+            return Self::reject("window span exceeded", first_id, last_id);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_source_symbol(&self, id: u64, bounds: Option<(u64, u64)>) -> Result<(), DecoderError> {
+        self.check_range(id, id, bounds)
+    }
+
+    ///
+    /// Checks a header that already passed RepairSymbolHeader::validate
+    pub(crate) fn check_repair_symbol(&self, header: &RepairSymbolHeader, bounds: Option<(u64, u64)>) -> Result<(), DecoderError> {
+        let last_id = header.first_id + (header.n_protected_symbols - 1);
+        if header.n_protected_symbols > self.limits.max_protected_symbols {
+            return Self::reject("too many protected symbols", header.first_id, last_id);
+        }
+        if self.repair_symbols.len() as u64 >= self.limits.max_buffered_repair_symbols {
+            return Self::reject("too many buffered repair symbols", header.first_id, last_id);
+        }
+        self.check_range(header.first_id, last_id, bounds)
+    }
+
+    pub(crate) fn repair_symbol_accepted(&mut self, header: &RepairSymbolHeader) {
+        self.repair_symbols.push_back(header.first_id + (header.n_protected_symbols - 1));
+    }
+
+    ///
+    /// Forgets the repair symbols that only protect symbols removed from the window, after the
+    /// symbols before removed_up_to were removed
+    pub(crate) fn window_slid(&mut self, removed_up_to: u64, bounds: Option<(SourceSymbolMetadata, SourceSymbolMetadata)>) {
+        self.window_start = self.window_start.max(removed_up_to);
+        match bounds {
+            Some((first, _)) => {
+                let first = source_symbol_metadata_to_u64(first);
+                self.window_start = self.window_start.max(first);
+                self.repair_symbols.retain(|last_id| *last_id >= first);
+            }
+            None => self.repair_symbols.clear(),
+        }
+    }
+}
@@ -1,2 +1,46 @@
+use clap::{Parser, Subcommand};
 
-fn main() {}
\ No newline at end of file
+mod cli;
+
+/// Command-line tools for the networkcoding FEC library
+#[derive(Parser)]
+#[command(name = "netcode", version)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs the lossy channel simulator with the given codec and loss model
+    Simulate(cli::simulate::SimulateArgs),
+    /// FEC-protects a file into a packet stream or a directory of packets
+    Encode(cli::packets::EncodeArgs),
+    /// Rebuilds a file from a packet stream or a directory of packets
+    Decode(cli::packets::DecodeArgs),
+    /// Parses and pretty-prints source and repair symbol headers
+    Inspect(cli::inspect::InspectArgs),
+    /// Replays the UDP traffic of a pcap or pcapng capture into a decoder
+    Replay(cli::replay::ReplayArgs),
+    /// Replays a trace recorded by simulate --record and reports the calls whose outcome changed
+    Trace(cli::trace::TraceArgs),
+    /// Measures the throughput of each codec
+    Bench(cli::bench::BenchArgs),
+}
+
+fn main() {
+    let args = Args::parse();
+    let res = match args.command {
+        Command::Simulate(args) => cli::simulate::run(args),
+        Command::Encode(args) => cli::packets::encode(args),
+        Command::Decode(args) => cli::packets::decode(args),
+        Command::Inspect(args) => cli::inspect::run(args),
+        Command::Replay(args) => cli::replay::run(args),
+        Command::Trace(args) => cli::trace::run(args),
+        Command::Bench(args) => cli::bench::run(args),
+    };
+    if let Err(err) = res {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
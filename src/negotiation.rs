@@ -0,0 +1,240 @@
+use std::fmt::Write;
+
+use crate::config::{CodecConfig, ConfigError, Field, Scheme, MAX_SYMBOL_SIZE, MAX_WINDOW_SIZE};
+
+/// FEC Encoding IDs of the FECFRAME registry (RFC 6364) used in the SDP and binary forms
+pub const RLC_GF256_ENCODING_ID: u8 = 9;
+pub const RLC_GF2_ENCODING_ID: u8 = 10;
+/// VLC has no registered FEC Encoding ID, it takes one from the under-specified range of RFC 5052
+pub const VLC_ENCODING_ID: u8 = 250;
+
+const VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NegotiationError {
+    /// an a=fec-repair-flow line does not follow RFC 6364
+    BadSdp(String),
+    /// the binary form ends in the middle of a field
+    Truncated,
+    UnsupportedVersion(u8),
+    /// the peers do not support any scheme in common
+    NoCommonScheme,
+    Config(ConfigError),
+}
+
+/// Side of a peer in the exchange of capabilities, as in the SDP offer/answer model (RFC 3264)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// the peer that sent its capabilities first, whose preferences are followed
+    Offerer,
+    Answerer,
+}
+
+/// A FEC scheme supported by a peer, along with the largest symbols and window it accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FecScheme {
+    pub scheme: Scheme,
+    pub field: Field,
+    pub max_symbol_size: usize,
+    pub max_window_size: usize,
+}
+
+impl FecScheme {
+    pub fn encoding_id(&self) -> Option<u8> {
+        match (self.scheme, self.field) {
+            (Scheme::Rlc, Field::Gf256) => Some(RLC_GF256_ENCODING_ID),
+            (Scheme::Rlc, Field::Gf2) => Some(RLC_GF2_ENCODING_ID),
+            (Scheme::Vlc, Field::Gf256) => Some(VLC_ENCODING_ID),
+            (Scheme::Vlc, Field::Gf2) => None,
+        }
+    }
+
+    fn from_encoding_id(encoding_id: u8, max_symbol_size: usize, max_window_size: usize) -> Option<FecScheme> {
+        let (scheme, field) = match encoding_id {
+            RLC_GF256_ENCODING_ID => (Scheme::Rlc, Field::Gf256),
+            RLC_GF2_ENCODING_ID => (Scheme::Rlc, Field::Gf2),
+            VLC_ENCODING_ID => (Scheme::Vlc, Field::Gf256),
+            _ => return None,
+        };
+        Some(FecScheme {
+            scheme,
+            field,
+            max_symbol_size,
+            max_window_size,
+        })
+    }
+}
+
+/// The FEC schemes a peer supports, by order of preference
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FecCapabilities {
+    pub schemes: Vec<FecScheme>,
+}
+
+impl FecCapabilities {
+    ///
+    /// Capabilities of this build: RLC first if it is enabled, then VLC
+    pub fn local(max_symbol_size: usize, max_window_size: usize) -> FecCapabilities {
+        let mut schemes = Vec::new();
+        if cfg!(feature = "enable-rlc") {
+            schemes.push(FecScheme { scheme: Scheme::Rlc, field: Field::Gf256, max_symbol_size, max_window_size });
+        }
+        schemes.push(FecScheme { scheme: Scheme::Vlc, field: Field::Gf256, max_symbol_size, max_window_size });
+        FecCapabilities { schemes }
+    }
+
+    ///
+    /// One a=fec-repair-flow line per scheme (RFC 6364), with the preference level giving the order.
+    /// The scheme-specific information holds the symbol size E, as in RFC 8681, and the window size W
+    pub fn to_sdp(&self) -> String {
+        let mut sdp = String::new();
+        for (preference, scheme) in self.schemes.iter().enumerate() {
+            if let Some(encoding_id) = scheme.encoding_id() {
+                let _ = write!(sdp, "a=fec-repair-flow: encoding-id={}; preference-lvl={}; ss-fssi=E:{},W:{}\r\n",
+                               encoding_id, preference, scheme.max_symbol_size, scheme.max_window_size);
+            }
+        }
+        sdp
+    }
+
+    ///
+    /// Parses the a=fec-repair-flow lines of an SDP description, ignoring the other lines and the
+    /// FEC Encoding IDs this crate does not know
+    pub fn from_sdp(sdp: &str) -> Result<FecCapabilities, NegotiationError> {
+        let mut schemes = Vec::new();
+        for line in sdp.lines() {
+            let line = line.trim();
+            let params = match line.strip_prefix("a=fec-repair-flow:") {
+                Some(params) => params,
+                None => continue,
+            };
+            let bad_sdp = || NegotiationError::BadSdp(line.to_string());
+            let (mut encoding_id, mut preference, mut symbol_size, mut window_size) = (None, None, None, None);
+            for param in params.split(';') {
+                let (name, value) = param.trim().split_once('=').ok_or_else(bad_sdp)?;
+                match name {
+                    "encoding-id" => encoding_id = Some(value.parse::<u8>().map_err(|_| bad_sdp())?),
+                    "preference-lvl" => preference = Some(value.parse::<u8>().map_err(|_| bad_sdp())?),
+                    "ss-fssi" => {
+                        for element in value.split(',') {
+                            let (name, value) = element.trim().split_once(':').ok_or_else(bad_sdp)?;
+                            match name {
+                                "E" => symbol_size = Some(value.parse::<usize>().map_err(|_| bad_sdp())?),
+                                "W" => window_size = Some(value.parse::<usize>().map_err(|_| bad_sdp())?),
+                                _ => (),
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            let encoding_id = encoding_id.ok_or_else(bad_sdp)?;
+            // the scheme-specific information of other FEC schemes has other elements
+            let mut scheme = match FecScheme::from_encoding_id(encoding_id, 0, 0) {
+                Some(scheme) => scheme,
+                None => continue,
+            };
+            scheme.max_symbol_size = symbol_size.ok_or_else(bad_sdp)?;
+            scheme.max_window_size = window_size.ok_or_else(bad_sdp)?;
+            schemes.push((preference.unwrap_or(u8::MAX), scheme));
+        }
+        // stable: the lines without preference level keep their order
+        schemes.sort_by_key(|(preference, _)| *preference);
+        Ok(FecCapabilities {
+            schemes: schemes.into_iter().map(|(_, scheme)| scheme).collect(),
+        })
+    }
+
+    ///
+    /// Compact form for a QUIC transport parameter: a version byte, the number of schemes and, for each
+    /// of them by order of preference, its FEC Encoding ID followed by the symbol and window sizes as QUIC varints
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let schemes: Vec<(u8, &FecScheme)> = self.schemes.iter()
+            .filter_map(|scheme| scheme.encoding_id().map(|id| (id, scheme)))
+            .take(u8::MAX as usize)
+            .collect();
+        let mut out = vec![VERSION, schemes.len() as u8];
+        for (encoding_id, scheme) in schemes {
+            out.push(encoding_id);
+            write_varint(&mut out, scheme.max_symbol_size as u64);
+            write_varint(&mut out, scheme.max_window_size as u64);
+        }
+        out
+    }
+
+    pub fn from_bytes(mut input: &[u8]) -> Result<FecCapabilities, NegotiationError> {
+        let version = read_u8(&mut input)?;
+        if version != VERSION {
+            return Err(NegotiationError::UnsupportedVersion(version));
+        }
+        let count = read_u8(&mut input)?;
+        let mut schemes = Vec::new();
+        for _ in 0..count {
+            let encoding_id = read_u8(&mut input)?;
+            let symbol_size = read_varint(&mut input)?;
+            let window_size = read_varint(&mut input)?;
+            if let Some(scheme) = FecScheme::from_encoding_id(encoding_id, symbol_size as usize, window_size as usize) {
+                schemes.push(scheme);
+            }
+        }
+        Ok(FecCapabilities { schemes })
+    }
+
+    ///
+    /// Picks the first scheme of the offerer, by order of preference, that the answerer also supports and this
+    /// build can run, with the largest symbol and window sizes both peers accept, up to MAX_SYMBOL_SIZE and MAX_WINDOW_SIZE.
+    /// role is the side of self, so that both peers agree: a.negotiate(&b, Role::Offerer) == b.negotiate(&a, Role::Answerer)
+    pub fn negotiate(&self, remote: &FecCapabilities, role: Role) -> Result<CodecConfig, NegotiationError> {
+        let (offer, answer) = match role {
+            Role::Offerer => (self, remote),
+            Role::Answerer => (remote, self),
+        };
+        let mut last_error = NegotiationError::NoCommonScheme;
+        for offered in &offer.schemes {
+            let answered = match answer.schemes.iter().find(|answered| answered.scheme == offered.scheme && answered.field == offered.field) {
+                Some(answered) => answered,
+                None => continue,
+            };
+            let config = CodecConfig::builder(offered.scheme,
+                                              offered.max_symbol_size.min(answered.max_symbol_size).min(MAX_SYMBOL_SIZE),
+                                              offered.max_window_size.min(answered.max_window_size).min(MAX_WINDOW_SIZE))
+                .field(offered.field)
+                .build();
+            match config {
+                Ok(config) => return Ok(config),
+                Err(err) => last_error = NegotiationError::Config(err),
+            }
+        }
+        Err(last_error)
+    }
+}
+
+fn read_u8(input: &mut &[u8]) -> Result<u8, NegotiationError> {
+    let (byte, rest) = input.split_first().ok_or(NegotiationError::Truncated)?;
+    *input = rest;
+    Ok(*byte)
+}
+
+/// Variable-length integer of RFC 9000, the two high bits of the first byte giving its length
+fn write_varint(out: &mut Vec<u8>, value: u64) {
+    match value {
+        0..=0x3f => out.push(value as u8),
+        0x40..=0x3fff => out.extend_from_slice(&(value as u16 | 0x4000).to_be_bytes()),
+        0x4000..=0x3fff_ffff => out.extend_from_slice(&(value as u32 | 0x8000_0000).to_be_bytes()),
+        _ => out.extend_from_slice(&(value.min((1 << 62) - 1) | 0xc000_0000_0000_0000).to_be_bytes()),
+    }
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64, NegotiationError> {
+    let first = read_u8(input)?;
+    let len = 1usize << (first >> 6);
+    if input.len() < len - 1 {
+        return Err(NegotiationError::Truncated);
+    }
+    let mut value = (first & 0x3f) as u64;
+    for byte in &input[..len - 1] {
+        value = (value << 8) | *byte as u64;
+    }
+    *input = &input[len - 1..];
+    Ok(value)
+}
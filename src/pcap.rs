@@ -0,0 +1,357 @@
+use std::time::{Duration, Instant};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::{Decoder, DecoderError, SourceSymbol, source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+const PCAPNG_OPTION_TSRESOL: u16 = 9;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+
+#[derive(Debug)]
+pub enum PcapError {
+    Io(std::io::Error),
+    BadMagic(u32),
+    Truncated,
+    UnknownInterface(u32),
+}
+
+impl From<std::io::Error> for PcapError {
+    fn from(err: std::io::Error) -> PcapError {
+        PcapError::Io(err)
+    }
+}
+
+/// A captured link-layer frame
+#[derive(Debug, Clone)]
+pub struct CapturedPacket {
+    /// time since the UNIX epoch
+    pub timestamp: Duration,
+    pub link_type: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Endianness {
+    big: bool,
+}
+
+impl Endianness {
+    fn u16(&self, b: &[u8]) -> u16 {
+        if self.big { BigEndian::read_u16(b) } else { LittleEndian::read_u16(b) }
+    }
+
+    fn u32(&self, b: &[u8]) -> u32 {
+        if self.big { BigEndian::read_u32(b) } else { LittleEndian::read_u32(b) }
+    }
+}
+
+fn slice(data: &[u8], start: usize, len: usize) -> Result<&[u8], PcapError> {
+    data.get(start..start.checked_add(len).ok_or(PcapError::Truncated)?).ok_or(PcapError::Truncated)
+}
+
+///
+/// Reads all the packets of a pcap or pcapng capture
+pub fn read_packets(data: &[u8]) -> Result<Vec<CapturedPacket>, PcapError> {
+    if data.len() < 4 {
+        return Err(PcapError::Truncated);
+    }
+    match LittleEndian::read_u32(data) {
+        PCAPNG_SECTION_HEADER => read_pcapng(data),
+        _ => read_pcap(data),
+    }
+}
+
+pub fn read_file<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<CapturedPacket>, PcapError> {
+    read_packets(&std::fs::read(path)?)
+}
+
+fn read_pcap(data: &[u8]) -> Result<Vec<CapturedPacket>, PcapError> {
+    let header = slice(data, 0, 24)?;
+    let (endianness, nanos) = match (LittleEndian::read_u32(header), BigEndian::read_u32(header)) {
+        (PCAP_MAGIC_MICROS, _) => (Endianness { big: false }, false),
+        (PCAP_MAGIC_NANOS, _) => (Endianness { big: false }, true),
+        (_, PCAP_MAGIC_MICROS) => (Endianness { big: true }, false),
+        (_, PCAP_MAGIC_NANOS) => (Endianness { big: true }, true),
+        (magic, _) => return Err(PcapError::BadMagic(magic)),
+    };
+    let link_type = endianness.u32(&header[20..]);
+    let mut packets = Vec::new();
+    let mut offset = 24;
+    while offset < data.len() {
+        let record = slice(data, offset, 16)?;
+        let secs = endianness.u32(record) as u64;
+        let frac = endianness.u32(&record[4..]);
+        let captured_len = endianness.u32(&record[8..]) as usize;
+        let frac = if nanos { Duration::from_nanos(frac as u64) } else { Duration::from_micros(frac as u64) };
+        packets.push(CapturedPacket {
+            timestamp: Duration::from_secs(secs) + frac,
+            link_type,
+            data: slice(data, offset + 16, captured_len)?.to_vec(),
+        });
+        offset += 16 + captured_len;
+    }
+    Ok(packets)
+}
+
+/// (link type, timestamp units per second) of each interface of the current section
+struct Interface {
+    link_type: u32,
+    units_per_sec: u64,
+}
+
+fn read_pcapng(data: &[u8]) -> Result<Vec<CapturedPacket>, PcapError> {
+    let mut packets = Vec::new();
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut endianness = Endianness { big: false };
+    let mut offset = 0;
+    while offset < data.len() {
+        let block_header = slice(data, offset, 12)?;
+        let block_type = endianness.u32(block_header);
+        if block_type == PCAPNG_SECTION_HEADER {
+            endianness = match (LittleEndian::read_u32(&block_header[8..]), BigEndian::read_u32(&block_header[8..])) {
+                (PCAPNG_BYTE_ORDER_MAGIC, _) => Endianness { big: false },
+                (_, PCAPNG_BYTE_ORDER_MAGIC) => Endianness { big: true },
+                (magic, _) => return Err(PcapError::BadMagic(magic)),
+            };
+            interfaces.clear();
+        }
+        let block_len = endianness.u32(&block_header[4..]) as usize;
+        if block_len < 12 {
+            return Err(PcapError::Truncated);
+        }
+        let body = slice(data, offset + 8, block_len - 12)?;
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => {
+                let link_type = endianness.u16(slice(body, 0, 2)?) as u32;
+                interfaces.push(Interface {
+                    link_type,
+                    units_per_sec: read_tsresol(&body[8.min(body.len())..], endianness),
+                });
+            }
+            PCAPNG_ENHANCED_PACKET => {
+                let fields = slice(body, 0, 20)?;
+                let interface_id = endianness.u32(fields);
+                let interface = interfaces.get(interface_id as usize).ok_or(PcapError::UnknownInterface(interface_id))?;
+                let ts = ((endianness.u32(&fields[4..]) as u64) << 32) | endianness.u32(&fields[8..]) as u64;
+                let captured_len = endianness.u32(&fields[12..]) as usize;
+                packets.push(CapturedPacket {
+                    // in u128: the fraction times 10^9 overflows u64 for resolutions finer than the nanosecond
+                    timestamp: Duration::from_secs(ts / interface.units_per_sec)
+                        + Duration::from_nanos(((ts % interface.units_per_sec) as u128 * 1_000_000_000 / interface.units_per_sec as u128) as u64),
+                    link_type: interface.link_type,
+                    data: slice(body, 20, captured_len)?.to_vec(),
+                });
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                let interface = interfaces.first().ok_or(PcapError::UnknownInterface(0))?;
+                let original_len = endianness.u32(slice(body, 0, 4)?) as usize;
+                packets.push(CapturedPacket {
+                    timestamp: Duration::ZERO,
+                    link_type: interface.link_type,
+                    data: slice(body, 4, original_len.min(body.len() - 4))?.to_vec(),
+                });
+            }
+            _ => (),
+        }
+        offset += block_len;
+    }
+    Ok(packets)
+}
+
+fn read_tsresol(mut options: &[u8], endianness: Endianness) -> u64 {
+    while options.len() >= 4 {
+        let code = endianness.u16(options);
+        let len = endianness.u16(&options[2..]) as usize;
+        if code == 0 {
+            break;
+        }
+        if code == PCAPNG_OPTION_TSRESOL && len >= 1 && options.len() > 4 {
+            let resol = options[4];
+            return if resol & 0x80 == 0 {
+                10u64.saturating_pow(resol as u32)
+            } else {
+                1u64.checked_shl((resol & 0x7f) as u32).unwrap_or(u64::MAX)
+            };
+        }
+        let padded_len = (len + 3) & !3;
+        options = &options[(4 + padded_len).min(options.len())..];
+    }
+    1_000_000
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UdpDatagram<'a> {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub payload: &'a [u8],
+}
+
+///
+/// Extracts the UDP datagram carried by the given frame, if any
+pub fn udp_datagram(link_type: u32, frame: &[u8]) -> Option<UdpDatagram<'_>> {
+    let ip = match link_type {
+        LINKTYPE_NULL => frame.get(4..)?,
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ethertype = BigEndian::read_u16(frame.get(offset..offset + 2)?);
+            // skip 802.1Q and 802.1ad tags
+            while ethertype == 0x8100 || ethertype == 0x88a8 {
+                offset += 4;
+                ethertype = BigEndian::read_u16(frame.get(offset..offset + 2)?);
+            }
+            frame.get(offset + 2..)?
+        }
+        LINKTYPE_LINUX_SLL => frame.get(16..)?,
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => frame,
+        _ => return None,
+    };
+    let udp = match ip.first()? >> 4 {
+        4 => {
+            let header_len = ((ip[0] & 0x0f) as usize) * 4;
+            if *ip.get(9)? != 17 {
+                return None;
+            }
+            let total_len = BigEndian::read_u16(ip.get(2..4)?) as usize;
+            ip.get(header_len..total_len.min(ip.len()))?
+        }
+        6 => {
+            if *ip.get(6)? != 17 {
+                return None;
+            }
+            ip.get(40..)?
+        }
+        _ => return None,
+    };
+    let len = BigEndian::read_u16(udp.get(4..6)?) as usize;
+    Some(UdpDatagram {
+        src_port: BigEndian::read_u16(udp.get(0..2)?),
+        dst_port: BigEndian::read_u16(udp.get(2..4)?),
+        payload: udp.get(8..len.clamp(8, udp.len()))?,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadKind {
+    Source,
+    Repair,
+}
+
+/// Symbols of the datagrams matching port (as source or destination port) start at offset in their UDP payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractionRule {
+    pub port: u16,
+    pub offset: usize,
+    pub kind: PayloadKind,
+}
+
+impl ExtractionRule {
+    pub fn matches(&self, datagram: &UdpDatagram) -> bool {
+        (datagram.src_port == self.port || datagram.dst_port == self.port) && datagram.payload.len() >= self.offset
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    pub packets: usize,
+    pub source_symbols: usize,
+    pub repair_symbols: usize,
+    /// packets that matched a rule but could not be parsed or were rejected by the decoder, such as
+    /// source symbols of another size than the symbol size
+    pub invalid_packets: usize,
+    /// (source symbol id, time since the start of the capture) of each recovered symbol
+    pub recovered: Vec<(u64, Duration)>,
+    /// ids of the source symbols of the replayed window that were neither received nor recovered
+    pub missing: Vec<u64>,
+}
+
+///
+/// Replays the given packets into the decoder by order of capture timestamp, packets captured at the
+/// same time keeping their order. The first matching rule of each UDP datagram decides how its payload is decoded
+pub fn replay(decoder: &mut Decoder, packets: &[CapturedPacket], rules: &[ExtractionRule], window_size: usize) -> ReplayReport {
+    let mut report = ReplayReport::default();
+    let start = Instant::now();
+    let first_ts = packets.iter().map(|p| p.timestamp).min().unwrap_or_default();
+    let mut seen = std::collections::BTreeSet::new();
+    // lowest and highest ids of the decoder window during the replay, which the decoder limits keep
+    // within reach of the symbols it accepted
+    let mut replayed: Option<(u64, u64)> = None;
+    let mut packets: Vec<&CapturedPacket> = packets.iter().collect();
+    packets.sort_by_key(|packet| packet.timestamp);
+
+    for packet in packets {
+        report.packets += 1;
+        let datagram = match udp_datagram(packet.link_type, &packet.data) {
+            Some(datagram) => datagram,
+            None => continue,
+        };
+        let rule = match rules.iter().find(|rule| rule.matches(&datagram)) {
+            Some(rule) => rule,
+            None => continue,
+        };
+        let since_start = packet.timestamp.saturating_sub(first_ts);
+        let now = start + since_start;
+        let payload = &datagram.payload[rule.offset..];
+        let res = match rule.kind {
+            PayloadKind::Source => {
+                report.source_symbols += 1;
+                decoder.read_source_symbol_metadata(payload).and_then(|(consumed, md)| {
+                    // payloads of another size than the symbol size are rejected by the decoder
+                    let res = decoder.receive_source_symbol(SourceSymbol::new(md, payload[consumed..].to_vec()), now);
+                    if let Ok(_) | Err(DecoderError::UnusedSourceSymbol) = res {
+                        seen.insert(source_symbol_metadata_to_u64(md));
+                    }
+                    res
+                })
+            }
+            PayloadKind::Repair => {
+                report.repair_symbols += 1;
+                decoder.read_repair_symbol(payload)
+                    .and_then(|(_, repair_symbol)| decoder.receive_and_deserialize_repair_symbol(repair_symbol))
+                    .map(|(_, recovered)| recovered)
+            }
+        };
+        match res {
+            Ok(recovered) => {
+                for symbol in recovered {
+                    let id = source_symbol_metadata_to_u64(symbol.metadata());
+                    if seen.insert(id) {
+                        report.recovered.push((id, since_start));
+                    }
+                }
+            }
+            Err(DecoderError::UnusedSourceSymbol) | Err(DecoderError::UnusedRepairSymbol) => (),
+            Err(err) => {
+                log::debug!("replayed packet rejected: {:?}", err);
+                report.invalid_packets += 1;
+            }
+        }
+        if let Some((first, last)) = decoder.bounds() {
+            let (first, last) = (source_symbol_metadata_to_u64(first), source_symbol_metadata_to_u64(last));
+            replayed = Some(match replayed {
+                Some((lowest, highest)) => (lowest.min(first), highest.max(last)),
+                None => (first, last),
+            });
+            if last + 1 - first > window_size as u64 {
+                decoder.remove_up_to(source_symbol_metadata_from_u64(last - window_size as u64), Some(now));
+            }
+        }
+    }
+
+    if let Some((first, last)) = replayed {
+        report.missing = (first..=last).filter(|id| !seen.contains(id)).collect();
+    }
+    report
+}
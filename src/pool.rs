@@ -0,0 +1,71 @@
+use alloc::vec::Vec;
+
+/// Free list of symbol buffers. The Encoder and Decoder take the buffers of the repair symbols and
+/// recovered symbols they return from their pool, and the application gives them back with
+/// recycle() once it has sent or delivered them. Only these buffers are reused: encoding and decoding
+/// still allocate inside vandermonde_lc and rustrlc, which compute the symbols in buffers of their own
+#[derive(Debug)]
+pub struct BufferPool {
+    buffer_capacity: usize,
+    max_buffers: usize,
+    free: Vec<Vec<u8>>,
+}
+
+impl BufferPool {
+    ///
+    /// Creates a pool keeping up to max_buffers buffers of buffer_capacity bytes. Nothing is allocated
+    /// before buffers are recycled
+    pub fn new(buffer_capacity: usize, max_buffers: usize) -> BufferPool {
+        BufferPool {
+            buffer_capacity,
+            max_buffers,
+            free: Vec::new(),
+        }
+    }
+
+    ///
+    /// Returns a zeroed buffer of len bytes, only allocating if the pool is empty
+    pub fn get(&mut self, len: usize) -> Vec<u8> {
+        match self.free.pop() {
+            Some(mut buffer) if buffer.capacity() >= len => {
+                buffer.clear();
+                buffer.resize(len, 0);
+                buffer
+            }
+            _ => {
+                let mut buffer = Vec::with_capacity(len.max(self.buffer_capacity));
+                buffer.resize(len, 0);
+                buffer
+            }
+        }
+    }
+
+    ///
+    /// Returns an empty buffer of at least buffer_capacity bytes, to be filled by the application
+    pub fn get_empty(&mut self) -> Vec<u8> {
+        let mut buffer = self.get(0);
+        buffer.reserve(self.buffer_capacity);
+        buffer
+    }
+
+    ///
+    /// Gives a buffer back to the pool. Buffers too small to hold a symbol or exceeding
+    /// max_buffers are dropped
+    pub fn recycle(&mut self, buffer: Vec<u8>) {
+        if buffer.capacity() >= self.buffer_capacity && self.free.len() < self.max_buffers {
+            self.free.push(buffer);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+
+    pub fn buffer_capacity(&self) -> usize {
+        self.buffer_capacity
+    }
+}
@@ -0,0 +1,286 @@
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::config::{CodecConfig, ConfigError};
+use crate::simulator::Codec;
+use crate::{Decoder, DecoderError, Encoder, EncoderError, RepairSymbol, SourceSymbol, SourceSymbolMetadata, source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+create_exception!(networkcoding, PyEncoderError, PyException, "Raised when an encoder operation fails.");
+create_exception!(networkcoding, PyDecoderError, PyException, "Raised when a decoder operation fails.");
+
+impl From<EncoderError> for PyErr {
+    fn from(err: EncoderError) -> PyErr {
+        PyEncoderError::new_err(format!("{:?}", err))
+    }
+}
+
+impl From<DecoderError> for PyErr {
+    fn from(err: DecoderError) -> PyErr {
+        PyDecoderError::new_err(format!("{:?}", err))
+    }
+}
+
+impl From<ConfigError> for PyErr {
+    fn from(err: ConfigError) -> PyErr {
+        PyValueError::new_err(format!("{:?}", err))
+    }
+}
+
+fn metadata_from_bytes(md: &[u8]) -> Result<SourceSymbolMetadata, String> {
+    let mut ret = [0; 8];
+    if md.len() != ret.len() {
+        return Err(format!("BadMetadata: expected {} bytes, got {}", ret.len(), md.len()));
+    }
+    ret.copy_from_slice(md);
+    Ok(ret)
+}
+
+fn encoder_metadata(md: &[u8]) -> PyResult<SourceSymbolMetadata> {
+    metadata_from_bytes(md).map_err(PyEncoderError::new_err)
+}
+
+fn decoder_metadata(md: &[u8]) -> PyResult<SourceSymbolMetadata> {
+    metadata_from_bytes(md).map_err(PyDecoderError::new_err)
+}
+
+#[pyclass(name = "SourceSymbol", module = "networkcoding")]
+#[derive(Clone)]
+pub struct PySourceSymbol {
+    inner: SourceSymbol,
+}
+
+#[pymethods]
+impl PySourceSymbol {
+    #[new]
+    fn new(metadata: &[u8], data: &[u8]) -> PyResult<Self> {
+        Ok(PySourceSymbol {
+            inner: SourceSymbol::new(metadata_from_bytes(metadata).map_err(PyValueError::new_err)?, data.to_vec()),
+        })
+    }
+
+    #[getter]
+    fn metadata<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.inner.metadata())
+    }
+
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.inner.get())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SourceSymbol(metadata={}, len={})", source_symbol_metadata_to_u64(self.inner.metadata()), self.inner.get().len())
+    }
+}
+
+#[pyclass(name = "RepairSymbol", module = "networkcoding")]
+#[derive(Clone)]
+pub struct PyRepairSymbol {
+    inner: RepairSymbol,
+}
+
+#[pymethods]
+impl PyRepairSymbol {
+    #[new]
+    fn new(data: &[u8]) -> Self {
+        PyRepairSymbol {
+            inner: RepairSymbol { data: data.to_vec() },
+        }
+    }
+
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.inner.get())
+    }
+
+    fn wire_len(&self) -> usize {
+        self.inner.wire_len()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.wire_len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("RepairSymbol(len={})", self.inner.wire_len())
+    }
+}
+
+fn wrap_source_symbols(symbols: Vec<SourceSymbol>) -> Vec<PySourceSymbol> {
+    symbols.into_iter().map(|inner| PySourceSymbol { inner }).collect()
+}
+
+#[pyclass(name = "Encoder", module = "networkcoding", unsendable)]
+pub struct PyEncoder {
+    inner: Encoder,
+}
+
+#[pymethods]
+impl PyEncoder {
+    #[staticmethod]
+    fn vlc(symbol_size: usize, window_size: usize) -> PyResult<Self> {
+        Ok(PyEncoder {
+            inner: CodecConfig::new(Codec::VLC, symbol_size, window_size).new_encoder()?,
+        })
+    }
+
+    #[cfg(feature = "enable-rlc")]
+    #[staticmethod]
+    fn rlc(symbol_size: usize, window_size: usize, seed: u32) -> PyResult<Self> {
+        Ok(PyEncoder {
+            inner: CodecConfig::new(Codec::RLC { seed }, symbol_size, window_size).new_encoder()?,
+        })
+    }
+
+    ///
+    /// Protects the given data and returns its serialized metadata
+    fn protect_data<'py>(&mut self, py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+        let mut md = source_symbol_metadata_from_u64(0);
+        self.inner.protect_data(data.to_vec(), &mut md)?;
+        Ok(PyBytes::new(py, &md))
+    }
+
+    #[pyo3(signature = (up_to=None))]
+    fn generate_repair_symbol(&mut self, up_to: Option<&[u8]>) -> PyResult<PyRepairSymbol> {
+        let inner = match up_to {
+            Some(md) => self.inner.generate_and_serialize_repair_symbol_up_to(encoder_metadata(md)?)?,
+            None => self.inner.generate_and_serialize_repair_symbol()?,
+        };
+        Ok(PyRepairSymbol { inner })
+    }
+
+    fn received_symbol(&mut self, metadata: &[u8]) -> PyResult<usize> {
+        Ok(self.inner.received_symbol(metadata)?)
+    }
+
+    #[getter]
+    fn symbol_size(&self) -> usize {
+        self.inner.symbol_size()
+    }
+
+    fn can_send_repair_symbols(&self) -> bool {
+        self.inner.can_send_repair_symbols()
+    }
+
+    fn remove_up_to(&mut self, metadata: &[u8]) -> PyResult<()> {
+        self.inner.remove_up_to(encoder_metadata(metadata)?);
+        Ok(())
+    }
+
+    fn next_metadata<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new(py, &self.inner.next_metadata()?))
+    }
+
+    fn first_metadata<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyBytes>> {
+        self.inner.first_metadata().map(|md| PyBytes::new(py, &md))
+    }
+
+    fn last_metadata<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyBytes>> {
+        self.inner.last_metadata().map(|md| PyBytes::new(py, &md))
+    }
+
+    fn n_protected_symbols(&self) -> usize {
+        self.inner.n_protected_symbols()
+    }
+
+    fn contains_symbol(&self, metadata: &[u8]) -> PyResult<bool> {
+        Ok(self.inner.contains_symbol(encoder_metadata(metadata)?))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.inner)
+    }
+}
+
+#[pyclass(name = "Decoder", module = "networkcoding", unsendable)]
+pub struct PyDecoder {
+    inner: Decoder,
+}
+
+#[pymethods]
+impl PyDecoder {
+    #[staticmethod]
+    fn vlc(symbol_size: usize, window_size: usize) -> PyResult<Self> {
+        Ok(PyDecoder {
+            inner: CodecConfig::new(Codec::VLC, symbol_size, window_size).new_decoder()?,
+        })
+    }
+
+    #[cfg(feature = "enable-rlc")]
+    #[staticmethod]
+    fn rlc(symbol_size: usize, window_size: usize) -> PyResult<Self> {
+        // the decoder reads the coefficient seeds from the repair symbols
+        Ok(PyDecoder {
+            inner: CodecConfig::new(Codec::RLC { seed: 0 }, symbol_size, window_size).new_decoder()?,
+        })
+    }
+
+    ///
+    /// Feeds a source symbol to the decoder and returns the recovered source symbols
+    fn receive_source_symbol(&mut self, source_symbol: PySourceSymbol) -> PyResult<Vec<PySourceSymbol>> {
+        let recovered = self.inner.receive_source_symbol(source_symbol.inner, std::time::Instant::now())?;
+        Ok(wrap_source_symbols(recovered))
+    }
+
+    ///
+    /// Parses a repair symbol from the given bytes, feeds it to the decoder and returns
+    /// (consumed_bytes, recovered_source_symbols)
+    fn receive_repair_symbol(&mut self, data: &[u8]) -> PyResult<(usize, Vec<PySourceSymbol>)> {
+        let (_, repair_symbol) = self.inner.read_repair_symbol(data)?;
+        let (consumed, recovered) = self.inner.receive_and_deserialize_repair_symbol(repair_symbol)?;
+        Ok((consumed, wrap_source_symbols(recovered)))
+    }
+
+    fn read_repair_symbol(&self, data: &[u8]) -> PyResult<(usize, PyRepairSymbol)> {
+        let (consumed, inner) = self.inner.read_repair_symbol(data)?;
+        Ok((consumed, PyRepairSymbol { inner }))
+    }
+
+    fn read_source_symbol_metadata<'py>(&self, py: Python<'py>, data: &[u8]) -> PyResult<(usize, Bound<'py, PyBytes>)> {
+        let (consumed, md) = self.inner.read_source_symbol_metadata(data)?;
+        Ok((consumed, PyBytes::new(py, &md)))
+    }
+
+    #[getter]
+    fn symbol_size(&self) -> usize {
+        self.inner.symbol_size()
+    }
+
+    fn remove_up_to<'py>(&mut self, py: Python<'py>, metadata: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+        let md = self.inner.remove_up_to(decoder_metadata(metadata)?, Some(std::time::Instant::now()));
+        Ok(PyBytes::new(py, &md))
+    }
+
+    fn bounds<'py>(&self, py: Python<'py>) -> Option<(Bound<'py, PyBytes>, Bound<'py, PyBytes>)> {
+        self.inner.bounds().map(|(first, last)| (PyBytes::new(py, &first), PyBytes::new(py, &last)))
+    }
+
+    fn largest_contiguously_received<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyBytes>> {
+        self.inner.largest_contiguously_received().map(|md| PyBytes::new(py, &md))
+    }
+}
+
+#[pyfunction]
+fn metadata_from_int(py: Python<'_>, n: u64) -> Bound<'_, PyBytes> {
+    PyBytes::new(py, &source_symbol_metadata_from_u64(n))
+}
+
+#[pyfunction]
+fn metadata_to_int(metadata: &[u8]) -> PyResult<u64> {
+    Ok(source_symbol_metadata_to_u64(metadata_from_bytes(metadata).map_err(PyValueError::new_err)?))
+}
+
+#[pymodule]
+pub fn networkcoding(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySourceSymbol>()?;
+    m.add_class::<PyRepairSymbol>()?;
+    m.add_class::<PyEncoder>()?;
+    m.add_class::<PyDecoder>()?;
+    m.add_function(wrap_pyfunction!(metadata_from_int, m)?)?;
+    m.add_function(wrap_pyfunction!(metadata_to_int, m)?)?;
+    m.add("EncoderError", m.py().get_type::<PyEncoderError>())?;
+    m.add("DecoderError", m.py().get_type::<PyDecoderError>())?;
+    Ok(())
+}
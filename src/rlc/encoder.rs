@@ -1,46 +1,97 @@
 use byteorder::{BigEndian, ByteOrder};
-use rand::Rng;
 use rustgf::system::equation::EquationBounds;
 use rustrlc::SymbolID;
 use rustrlc::encoder::Encoder as RustRLCEncoder;
 
-use crate::{EncoderError, SourceSymbolMetadata, source_symbol_metadata_to_u64, source_symbol_metadata_from_u64};
+use crate::{EncoderError, EncoderStats, SourceSymbolMetadata, source_symbol_metadata_to_u64, source_symbol_metadata_from_u64, Timestamp};
 use crate::EncoderError::{BadMetadata, BufferTooSmall};
+use crate::events::{EventEmitter, EventSink, FecEvent, Role};
+use crate::pool::BufferPool;
+use crate::simulator::Codec;
+use crate::snapshot::{EncoderSnapshot, Journal, SnapshotError, WindowSymbol};
+use crate::config::Scheme;
+use crate::tag::{RepairSymbolTag, TAG_LEN};
 
 pub struct RLCEncoder {
     rust_rlc_encoder: RustRLCEncoder,
     symbol_size: usize,
-    gen: tinymt::TinyMT32,
+    stats: EncoderStats,
+    events: EventEmitter,
+    pool: BufferPool,
+    seed: u32,
+    // number of repair symbols generated, the seed of the next one derives from it and seed
+    draws: u64,
+    max_window_size: usize,
+    // the codec numbers the symbols from 0, a restored encoder continues the ids of the snapshot
+    id_offset: u64,
+    journal: Option<Journal<WindowSymbol>>,
+    tagged: bool,
 }
 
 impl RLCEncoder {
     pub fn new(symbol_size: usize, max_window_size: usize, seed: u32) -> RLCEncoder {
         RLCEncoder{
             rust_rlc_encoder: RustRLCEncoder::new(max_window_size, symbol_size),
-            gen: tinymt::TinyMT32::from_seed_u32(seed),
             symbol_size,
+            stats: EncoderStats::default(),
+            events: EventEmitter::default(),
+            pool: BufferPool::new(symbol_size + TAG_LEN + 8 + 8 + 4, max_window_size),
+            seed,
+            draws: 0,
+            max_window_size,
+            id_offset: 0,
+            journal: None,
+            tagged: false,
         }
     }
 
+    ///
+    /// Seed of the next repair symbol, a SplitMix64 step over the encoder seed and the number of repair
+    /// symbols generated so far. It only depends on draws, so a restored encoder continues the sequence
+    fn next_repair_seed(&self) -> u32 {
+        let mut z = ((self.seed as u64) << 32 ^ self.draws).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (z ^ (z >> 31)) as u32
+    }
+
+    fn codec_id(&self, md: SourceSymbolMetadata) -> Option<SymbolID> {
+        source_symbol_metadata_to_u64(md).checked_sub(self.id_offset)
+    }
+
     pub fn protect_data(&mut self, data: Vec<u8>, output: &mut SourceSymbolMetadata) -> Result<usize, EncoderError> {
         if output.len() < 8 {
             return Err(BufferTooSmall);
         }
+        let len = data.len();
+        let copy = self.journal.as_ref().map(|_| data.clone());
         match self.rust_rlc_encoder.protect_data(data) {
             Err(err) => {
                 Err(EncoderError::InternalError(format!("{:?}", err)))
             }
             Ok(id) => {
+                let id = id + self.id_offset;
+                if let (Some(journal), Some(data)) = (self.journal.as_mut(), copy) {
+                    journal.push(id, WindowSymbol { id, data, acknowledged: false, landed: false });
+                }
                 BigEndian::write_u64(output, id);
+                self.stats.protected_source_symbols += 1;
+                self.events.emit(|| FecEvent::SourceSymbolProtected { id, len });
                 Ok(8)
             }
         }
     }
 
     pub fn generate_and_serialize_repair_symbol_up_to(&mut self, up_to: SourceSymbolMetadata) -> Result<Vec<u8>, EncoderError> {
-        let serialized_size = self.symbol_size + 8 + 8 + 4;
-        let mut out = vec![0; serialized_size];
-        let written = self.generate_and_serialize_repair_symbol_in_place_up_to(&mut out.as_mut_slice(), up_to)?;
+        let serialized_size = self.repair_symbol_len();
+        let mut out = self.pool.get(serialized_size);
+        let written = match self.generate_and_serialize_repair_symbol_in_place_up_to(out.as_mut_slice(), up_to) {
+            Ok(written) => written,
+            Err(err) => {
+                self.pool.recycle(out);
+                return Err(err);
+            }
+        };
         if serialized_size != written {
             Err(EncoderError::InternalError("the serialized size was not equal to prediction".to_string()))
         } else {
@@ -50,16 +101,16 @@ impl RLCEncoder {
 
 
     pub fn generate_and_serialize_repair_symbol(&mut self) -> Result<Vec<u8>, EncoderError> {
-        if let Some(range) = self.rust_rlc_encoder.range() {
-            self.generate_and_serialize_repair_symbol_up_to(source_symbol_metadata_from_u64(*range.end()))
+        if let Some(last) = self.last_metadata() {
+            self.generate_and_serialize_repair_symbol_up_to(last)
         } else {
             Err(EncoderError::NoSymbolToGenerate)
         }
     }
 
     pub fn generate_and_serialize_repair_symbol_in_place(&mut self, output: &mut [u8]) -> Result<usize, EncoderError> {
-        if let Some(range) = self.rust_rlc_encoder.range() {
-            self.generate_and_serialize_repair_symbol_in_place_up_to(output, source_symbol_metadata_from_u64(*range.end()))
+        if let Some(last) = self.last_metadata() {
+            self.generate_and_serialize_repair_symbol_in_place_up_to(output, last)
         } else {
             Err(EncoderError::NoSymbolToGenerate)
         }
@@ -67,11 +118,15 @@ impl RLCEncoder {
 
 
     pub fn generate_and_serialize_repair_symbol_in_place_up_to(&mut self, output: &mut [u8], up_to: SourceSymbolMetadata) -> Result<usize, EncoderError> {
-        if output.len() < 8 + 8 + 4 + self.symbol_size {
+        if output.len() < self.repair_symbol_len() {
             return Err(BufferTooSmall);
         }
-        let seed = self.gen.gen();
-        let up_to = source_symbol_metadata_to_u64(up_to);
+        let up_to = match self.codec_id(up_to) {
+            Some(up_to) => up_to,
+            None => return Err(EncoderError::NoSymbolToGenerate),
+        };
+        let seed = self.next_repair_seed();
+        self.draws += 1;
         match self.rust_rlc_encoder.generate_repair_symbol_up_to(seed, up_to) {
             Err(rustrlc::encoder::EncoderError::WindowEmpty) => Err(EncoderError::NoSymbolToGenerate),
             Err(err) => {
@@ -84,7 +139,11 @@ impl RLCEncoder {
                         pivot, last_nonzero_id
                     } => {
                         let mut written = 0;
-                        let (pivot, last_nonzero_id) = (*pivot, *last_nonzero_id);
+                        if self.tagged {
+                            RepairSymbolTag::new(Scheme::Rlc).write(output);
+                            written += TAG_LEN;
+                        }
+                        let (pivot, last_nonzero_id) = (*pivot + self.id_offset, *last_nonzero_id + self.id_offset);
                         BigEndian::write_u64(&mut output[written..], pivot);
                         written += 8;
                         BigEndian::write_u64(&mut output[written..], last_nonzero_id + 1 - pivot);
@@ -96,6 +155,8 @@ impl RLCEncoder {
                         let len = self.symbol_size;
                         (&mut output[written..written+len]).clone_from_slice(data.as_slice());
                         written += len;
+                        self.stats.generated_repair_symbols += 1;
+                        self.events.emit(|| FecEvent::RepairSymbolSent { first_id: pivot, last_id: last_nonzero_id, len: written });
                         Ok(written)
                     }
                     EquationBounds::EmptyBounds => {
@@ -110,7 +171,14 @@ impl RLCEncoder {
         if metadata.len() < 8 {
             return Err(BadMetadata);
         }
-        self.rust_rlc_encoder.received_symbol(BigEndian::read_u64(metadata));
+        let id = BigEndian::read_u64(metadata);
+        if let Some(codec_id) = id.checked_sub(self.id_offset) {
+            self.rust_rlc_encoder.received_symbol(codec_id);
+        }
+        if let Some(symbol) = self.journal.as_mut().and_then(|journal| journal.get_mut(id)) {
+            symbol.acknowledged = true;
+        }
+        self.stats.acknowledged_source_symbols += 1;
         Ok(8)
     }
 
@@ -123,56 +191,162 @@ impl RLCEncoder {
     }
 
     pub fn remove_up_to(&mut self, md: SourceSymbolMetadata) {
-        self.rust_rlc_encoder.remove_up_to(source_symbol_metadata_to_u64(md) as SymbolID);
+        let before = self.window_bounds();
+        if let Some(id) = self.codec_id(md) {
+            self.rust_rlc_encoder.remove_up_to(id);
+        }
+        self.window_slid(before);
     }
 
     pub fn next_metadata(&mut self) -> SourceSymbolMetadata {
-        source_symbol_metadata_from_u64(self.rust_rlc_encoder.next_id())
+        source_symbol_metadata_from_u64(self.rust_rlc_encoder.next_id() + self.id_offset)
     }
     
     pub fn next_repair_symbol_size(&self, _up_to: SourceSymbolMetadata) -> usize {
-        return self.symbol_size + 8 + 8 + 4;
+        self.repair_symbol_len()
+    }
+
+    fn repair_symbol_len(&self) -> usize {
+        let tag_len = if self.tagged { TAG_LEN } else { 0 };
+        tag_len + 8 + 8 + 4 + self.symbol_size
     }
 
     pub fn first_metadata(&self) -> Option<SourceSymbolMetadata> {
         match self.rust_rlc_encoder.range() {
             None => None,
-            Some(range) => Some(source_symbol_metadata_from_u64(*range.start())),
+            Some(range) => Some(source_symbol_metadata_from_u64(*range.start() + self.id_offset)),
         }
     }
 
     pub fn last_metadata(&self) -> Option<SourceSymbolMetadata> {
         match self.rust_rlc_encoder.range() {
             None => None,
-            Some(range) => Some(source_symbol_metadata_from_u64(*range.end())),
+            Some(range) => Some(source_symbol_metadata_from_u64(*range.end() + self.id_offset)),
         }
     }
 
     pub fn current_window_size(&self) -> usize {
-        match self.rust_vlc_encoder.range() {
+        match self.rust_rlc_encoder.range() {
             None => 0,
             Some(range) => (range.end() + 1 - range.start()) as usize,
         }
     }
 
     pub fn contains_symbol(&self, md: SourceSymbolMetadata) -> bool {
-        match self.rust_vlc_encoder.range() {
-            Some(range) => range.contains(&source_symbol_metadata_to_u64(md)),
-            None => false,
+        match (self.rust_rlc_encoder.range(), self.codec_id(md)) {
+            (Some(range), Some(id)) => range.contains(&id),
+            _ => false,
         }
     }
 
-    pub fn get_sent_time(&self, md: SourceSymbolMetadata) -> Option<std::time::Instant> {
-        self.rust_rlc_encoder.get_sent_time(source_symbol_metadata_to_u64(md))
+    pub fn get_sent_time(&self, md: SourceSymbolMetadata) -> Option<Timestamp> {
+        self.rust_rlc_encoder.get_sent_time(self.codec_id(md)?)
     }
 
 
     // either received or lost
     pub fn symbol_landed(&mut self, md: SourceSymbolMetadata) {
-        self.rust_rlc_encoder.symbol_landed(source_symbol_metadata_to_u64(md))
+        if let Some(id) = self.codec_id(md) {
+            self.rust_rlc_encoder.symbol_landed(id);
+        }
+        let id = source_symbol_metadata_to_u64(md);
+        if let Some(symbol) = self.journal.as_mut().and_then(|journal| journal.get_mut(id)) {
+            symbol.landed = true;
+        }
     }
 
     pub fn remove_landed_symbols(&mut self) {
-        self.rust_rlc_encoder.remove_landed_symbols()
+        let before = self.window_bounds();
+        self.rust_rlc_encoder.remove_landed_symbols();
+        self.window_slid(before);
     }
-}
\ No newline at end of file
+
+    fn window_bounds(&self) -> Option<(u64, u64)> {
+        self.rust_rlc_encoder.range().map(|range| (*range.start() + self.id_offset, *range.end() + self.id_offset))
+    }
+
+    fn window_slid(&mut self, before: Option<(u64, u64)>) {
+        let bounds = self.window_bounds();
+        if let Some(journal) = self.journal.as_mut() {
+            journal.window_slid(bounds.map(|(first, _)| first));
+        }
+        if bounds != before {
+            self.events.emit(|| FecEvent::WindowSlid { role: Role::Encoder, bounds });
+        }
+    }
+
+    pub fn stats(&self) -> EncoderStats {
+        EncoderStats {
+            current_window_size: self.current_window_size() as u64,
+            ..self.stats
+        }
+    }
+
+    pub fn set_event_sink(&mut self, sink: Option<Box<dyn EventSink>>) {
+        self.events.set_sink(sink);
+    }
+
+    pub fn take_buffer(&mut self) -> Vec<u8> {
+        self.pool.get_empty()
+    }
+
+    pub fn recycle(&mut self, buffer: Vec<u8>) {
+        self.pool.recycle(buffer);
+    }
+
+    pub fn set_repair_symbol_tag(&mut self, enabled: bool) {
+        self.tagged = enabled;
+    }
+
+    pub fn set_snapshots(&mut self, enabled: bool) {
+        if !enabled {
+            self.journal = None;
+        } else if self.journal.is_none() {
+            self.journal = Some(Journal::new());
+        }
+    }
+
+    pub fn snapshot(&self) -> Result<EncoderSnapshot, SnapshotError> {
+        let journal = self.journal.as_ref().ok_or(SnapshotError::NotEnabled)?;
+        let window: Vec<WindowSymbol> = journal.iter().cloned().collect();
+        if window.len() != self.current_window_size() {
+            // symbols were protected before the snapshots were enabled
+            return Err(SnapshotError::NotEnabled);
+        }
+        Ok(EncoderSnapshot {
+            codec: Codec::RLC { seed: self.seed },
+            symbol_size: self.symbol_size,
+            window_size: self.max_window_size,
+            stats: self.stats(),
+            next_id: self.rust_rlc_encoder.next_id() + self.id_offset,
+            repair_state: self.draws,
+            window,
+        })
+    }
+
+    pub fn restore(snapshot: &EncoderSnapshot) -> Result<RLCEncoder, SnapshotError> {
+        let seed = match snapshot.codec {
+            Codec::RLC { seed } => seed,
+            Codec::VLC => return Err(SnapshotError::UnsupportedCodec(0)),
+        };
+        let mut encoder = RLCEncoder::new(snapshot.symbol_size, snapshot.window_size, seed);
+        encoder.id_offset = snapshot.window.first().map_or(snapshot.next_id, |symbol| symbol.id);
+        encoder.journal = Some(Journal::new());
+        for symbol in &snapshot.window {
+            let mut md = source_symbol_metadata_from_u64(0);
+            encoder.protect_data(symbol.data.clone(), &mut md).map_err(SnapshotError::Encoder)?;
+            if source_symbol_metadata_to_u64(md) != symbol.id {
+                return Err(SnapshotError::Encoder(EncoderError::InternalError(format!("non-contiguous window: {} restored as {}", symbol.id, source_symbol_metadata_to_u64(md)))));
+            }
+            if symbol.acknowledged {
+                encoder.received_symbol(&md).map_err(SnapshotError::Encoder)?;
+            }
+            if symbol.landed {
+                encoder.symbol_landed(md);
+            }
+        }
+        encoder.draws = snapshot.repair_state;
+        encoder.stats = snapshot.stats;
+        Ok(encoder)
+    }
+}
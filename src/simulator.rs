@@ -0,0 +1,434 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+#[cfg(feature = "enable-rlc")]
+use crate::rlc::decoder::RLCDecoder;
+#[cfg(feature = "enable-rlc")]
+use crate::rlc::encoder::RLCEncoder;
+use crate::vandermonde_lc::decoder::VLCDecoder;
+use crate::vandermonde_lc::encoder::VLCEncoder;
+use crate::trace::{TraceHeader, TraceRecorder};
+use crate::{Decoder, DecoderError, Encoder, EncoderError, SourceSymbol, source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+/// Decides whether each packet sent on the simulated channel is lost
+pub trait LossModel {
+    fn is_lost(&mut self, rng: &mut StdRng) -> bool;
+}
+
+/// A probability of a loss model outside of [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidProbability(pub f64);
+
+impl fmt::Display for InvalidProbability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid probability {}: expected a value between 0 and 1", self.0)
+    }
+}
+
+fn probability(p: f64) -> Result<f64, InvalidProbability> {
+    if (0.0..=1.0).contains(&p) {
+        Ok(p)
+    } else {
+        Err(InvalidProbability(p))
+    }
+}
+
+/// Independent losses with a fixed probability
+pub struct Bernoulli {
+    loss_rate: f64,
+}
+
+impl Bernoulli {
+    pub fn new(loss_rate: f64) -> Result<Bernoulli, InvalidProbability> {
+        Ok(Bernoulli {
+            loss_rate: probability(loss_rate)?,
+        })
+    }
+}
+
+impl LossModel for Bernoulli {
+    fn is_lost(&mut self, rng: &mut StdRng) -> bool {
+        rng.gen_bool(self.loss_rate)
+    }
+}
+
+/// Two-state Markov chain alternating between a good and a bad state
+pub struct GilbertElliott {
+    p_good_to_bad: f64,
+    p_bad_to_good: f64,
+    loss_rate_good: f64,
+    loss_rate_bad: f64,
+    bad: bool,
+}
+
+impl GilbertElliott {
+    pub fn new(p_good_to_bad: f64, p_bad_to_good: f64, loss_rate_good: f64, loss_rate_bad: f64) -> Result<GilbertElliott, InvalidProbability> {
+        Ok(GilbertElliott {
+            p_good_to_bad: probability(p_good_to_bad)?,
+            p_bad_to_good: probability(p_bad_to_good)?,
+            loss_rate_good: probability(loss_rate_good)?,
+            loss_rate_bad: probability(loss_rate_bad)?,
+            bad: false,
+        })
+    }
+}
+
+impl LossModel for GilbertElliott {
+    fn is_lost(&mut self, rng: &mut StdRng) -> bool {
+        let transition = if self.bad { self.p_bad_to_good } else { self.p_good_to_bad };
+        if rng.gen_bool(transition) {
+            self.bad = !self.bad;
+        }
+        rng.gen_bool(if self.bad { self.loss_rate_bad } else { self.loss_rate_good })
+    }
+}
+
+/// Loses burst_length consecutive packets every period packets
+pub struct PeriodicBursts {
+    pub period: usize,
+    pub burst_length: usize,
+    sent: usize,
+}
+
+impl PeriodicBursts {
+    pub fn new(period: usize, burst_length: usize) -> PeriodicBursts {
+        PeriodicBursts {
+            period,
+            burst_length,
+            sent: 0,
+        }
+    }
+}
+
+impl LossModel for PeriodicBursts {
+    fn is_lost(&mut self, _rng: &mut StdRng) -> bool {
+        let lost = self.period > 0 && self.sent % self.period < self.burst_length;
+        self.sent += 1;
+        lost
+    }
+}
+
+/// Replays a recorded loss pattern, looping over it when exhausted
+pub struct TraceLoss {
+    trace: Vec<bool>,
+    index: usize,
+}
+
+impl TraceLoss {
+    pub fn new(trace: Vec<bool>) -> TraceLoss {
+        TraceLoss { trace, index: 0 }
+    }
+
+    ///
+    /// Parses a trace of '0' (received) and '1' (lost) characters, other characters are ignored
+    pub fn parse(trace: &str) -> TraceLoss {
+        TraceLoss::new(trace.chars().filter_map(|c| match c {
+            '0' => Some(false),
+            '1' => Some(true),
+            _ => None,
+        }).collect())
+    }
+}
+
+impl LossModel for TraceLoss {
+    fn is_lost(&mut self, _rng: &mut StdRng) -> bool {
+        if self.trace.is_empty() {
+            return false;
+        }
+        let lost = self.trace[self.index];
+        self.index = (self.index + 1) % self.trace.len();
+        lost
+    }
+}
+
+/// Serialized as {"scheme": "vlc"} or {"scheme": "rlc", "seed": u32}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "scheme"))]
+pub enum Codec {
+    #[cfg_attr(feature = "serde", serde(rename = "vlc"))]
+    VLC,
+    #[cfg(feature = "enable-rlc")]
+    #[cfg_attr(feature = "serde", serde(rename = "rlc"))]
+    RLC { seed: u32 },
+}
+
+impl Codec {
+    pub fn new_encoder(&self, symbol_size: usize, window_size: usize) -> Encoder {
+        match self {
+            Codec::VLC => Encoder::VLC(VLCEncoder::new(symbol_size, window_size)),
+            #[cfg(feature = "enable-rlc")]
+            Codec::RLC { seed } => Encoder::RLC(RLCEncoder::new(symbol_size, window_size, *seed)),
+        }
+    }
+
+    pub fn new_decoder(&self, symbol_size: usize, window_size: usize) -> Decoder {
+        match self {
+            Codec::VLC => Decoder::VLC(VLCDecoder::new(symbol_size, window_size)),
+            #[cfg(feature = "enable-rlc")]
+            Codec::RLC { .. } => Decoder::RLC(RLCDecoder::new(symbol_size, window_size)),
+        }
+    }
+}
+
+/// Sends count repair symbols after every interval source symbols
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RepairSchedule {
+    pub interval: usize,
+    pub count: usize,
+}
+
+impl Default for RepairSchedule {
+    fn default() -> RepairSchedule {
+        RepairSchedule {
+            interval: 10,
+            count: 1,
+        }
+    }
+}
+
+/// One-way delay of the channel: each packet is delayed by delay plus a uniform jitter,
+/// packets are thus reordered when the jitter exceeds the sending interval
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelConfig {
+    pub delay: Duration,
+    pub jitter: Duration,
+}
+
+pub struct SimulationConfig {
+    pub codec: Codec,
+    pub symbol_size: usize,
+    pub window_size: usize,
+    pub n_source_symbols: usize,
+    pub source_interval: Duration,
+    pub repair: RepairSchedule,
+    pub channel: ChannelConfig,
+    pub loss: Box<dyn LossModel>,
+    pub seed: u64,
+    /// records every encoder and decoder call of the simulation, see the trace module
+    pub trace: Option<Box<dyn Write>>,
+}
+
+#[derive(Debug)]
+pub enum SimulationError {
+    Encoder(EncoderError),
+    Decoder(DecoderError),
+    Trace(std::io::Error),
+    /// a sending or arrival time does not fit a Duration or an Instant
+    TimeOverflow,
+}
+
+impl From<EncoderError> for SimulationError {
+    fn from(err: EncoderError) -> SimulationError {
+        SimulationError::Encoder(err)
+    }
+}
+
+impl From<DecoderError> for SimulationError {
+    fn from(err: DecoderError) -> SimulationError {
+        SimulationError::Decoder(err)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SimulationReport {
+    pub source_symbols_sent: usize,
+    pub repair_symbols_sent: usize,
+    pub source_bytes_sent: usize,
+    pub repair_bytes_sent: usize,
+    pub source_symbols_lost: usize,
+    pub repair_symbols_lost: usize,
+    pub source_symbols_recovered: usize,
+    /// recovered symbols whose data differ from the data that was sent
+    pub source_symbols_corrupted: usize,
+    /// time between the sending of a lost source symbol and its recovery, for each recovered symbol
+    pub recovery_latencies: Vec<Duration>,
+}
+
+impl SimulationReport {
+    pub fn channel_loss_rate(&self) -> f64 {
+        ratio(self.source_symbols_lost + self.repair_symbols_lost, self.source_symbols_sent + self.repair_symbols_sent)
+    }
+
+    pub fn residual_loss_rate(&self) -> f64 {
+        ratio(self.source_symbols_lost - self.source_symbols_recovered, self.source_symbols_sent)
+    }
+
+    pub fn overhead(&self) -> f64 {
+        ratio(self.repair_bytes_sent, self.source_bytes_sent)
+    }
+
+    pub fn mean_recovery_latency(&self) -> Option<Duration> {
+        if self.recovery_latencies.is_empty() {
+            return None;
+        }
+        Some(self.recovery_latencies.iter().sum::<Duration>() / self.recovery_latencies.len() as u32)
+    }
+
+    pub fn max_recovery_latency(&self) -> Option<Duration> {
+        self.recovery_latencies.iter().max().copied()
+    }
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        a as f64 / b as f64
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "source symbols sent:      {}", self.source_symbols_sent)?;
+        writeln!(f, "repair symbols sent:      {}", self.repair_symbols_sent)?;
+        writeln!(f, "overhead:                 {:.2}%", 100.0 * self.overhead())?;
+        writeln!(f, "channel loss rate:        {:.2}%", 100.0 * self.channel_loss_rate())?;
+        writeln!(f, "source symbols lost:      {}", self.source_symbols_lost)?;
+        writeln!(f, "source symbols recovered: {}", self.source_symbols_recovered)?;
+        writeln!(f, "residual loss rate:       {:.2}%", 100.0 * self.residual_loss_rate())?;
+        if self.source_symbols_corrupted > 0 {
+            writeln!(f, "CORRUPTED symbols:        {}", self.source_symbols_corrupted)?;
+        }
+        match (self.mean_recovery_latency(), self.max_recovery_latency()) {
+            (Some(mean), Some(max)) => writeln!(f, "recovery latency:         mean {:?}, max {:?}", mean, max),
+            _ => writeln!(f, "recovery latency:         -"),
+        }
+    }
+}
+
+enum Packet {
+    Source(SourceSymbol),
+    Repair(Vec<u8>),
+}
+
+struct InFlight {
+    arrival: Duration,
+    seq: usize,
+    packet: Packet,
+}
+
+///
+/// Runs the simulation: all the packets are generated by the encoder, sent through the
+/// lossy channel, then fed to the decoder in their order of arrival
+pub fn run(config: SimulationConfig) -> Result<SimulationReport, SimulationError> {
+    let SimulationConfig { codec, symbol_size, window_size, n_source_symbols, source_interval, repair, channel, mut loss, seed, trace } = config;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut encoder = codec.new_encoder(symbol_size, window_size);
+    let mut decoder = codec.new_decoder(symbol_size, window_size);
+    let mut report = SimulationReport::default();
+    let header = TraceHeader { codec, symbol_size, window_size };
+    let mut recorder = TraceRecorder::new(trace.unwrap_or_else(|| Box::new(std::io::sink())), &header).map_err(SimulationError::Trace)?;
+
+    let mut in_flight = Vec::new();
+    let mut sent_at = HashMap::new();
+    let mut lost_data = HashMap::new();
+
+    let mut send = |now: Duration, packet: Packet, rng: &mut StdRng, report: &mut SimulationReport| -> Result<(), SimulationError> {
+        let lost = loss.is_lost(rng);
+        match &packet {
+            Packet::Source(symbol) => {
+                report.source_symbols_sent += 1;
+                report.source_bytes_sent += symbol.get().len();
+                if lost {
+                    report.source_symbols_lost += 1;
+                    lost_data.insert(source_symbol_metadata_to_u64(symbol.metadata()), symbol.get().clone());
+                }
+            }
+            Packet::Repair(data) => {
+                report.repair_symbols_sent += 1;
+                report.repair_bytes_sent += data.len();
+                if lost {
+                    report.repair_symbols_lost += 1;
+                }
+            }
+        }
+        if !lost {
+            let jitter = channel.jitter.mul_f64(rng.gen::<f64>());
+            let arrival = now.checked_add(channel.delay).and_then(|t| t.checked_add(jitter)).ok_or(SimulationError::TimeOverflow)?;
+            let seq = in_flight.len();
+            in_flight.push(InFlight {
+                arrival,
+                seq,
+                packet,
+            });
+        }
+        Ok(())
+    };
+
+    for i in 0..n_source_symbols {
+        let now = u32::try_from(i).ok().and_then(|i| source_interval.checked_mul(i)).ok_or(SimulationError::TimeOverflow)?;
+        if encoder.n_protected_symbols() >= window_size {
+            if let Some(first) = encoder.first_metadata() {
+                recorder.encoder_remove_up_to(&mut encoder, first);
+            }
+        }
+        let mut data = vec![0; symbol_size];
+        rng.fill_bytes(&mut data);
+        let mut md = source_symbol_metadata_from_u64(0);
+        recorder.protect_data(&mut encoder, data.clone(), &mut md)?;
+        sent_at.insert(source_symbol_metadata_to_u64(md), now);
+        send(now, Packet::Source(SourceSymbol::new(md, data)), &mut rng, &mut report)?;
+
+        if repair.interval > 0 && (i + 1) % repair.interval == 0 {
+            for _ in 0..repair.count {
+                if !encoder.can_send_repair_symbols() {
+                    break;
+                }
+                let repair_symbol = recorder.generate_and_serialize_repair_symbol(&mut encoder)?;
+                send(now, Packet::Repair(repair_symbol.take()), &mut rng, &mut report)?;
+            }
+        }
+    }
+
+    in_flight.sort_by_key(|p| (p.arrival, p.seq));
+
+    let start = Instant::now();
+    let mut recovered_ids = HashSet::new();
+    for InFlight { arrival, packet, .. } in in_flight {
+        let now = start.checked_add(arrival).ok_or(SimulationError::TimeOverflow)?;
+        let recovered = match packet {
+            Packet::Source(symbol) => match recorder.receive_source_symbol(&mut decoder, symbol, now) {
+                Ok(recovered) => recovered,
+                // the symbol arrived after having been recovered or removed from the window
+                Err(DecoderError::UnusedSourceSymbol) => Vec::new(),
+                Err(err) => return Err(err.into()),
+            },
+            Packet::Repair(data) => {
+                let (_, repair_symbol) = decoder.read_repair_symbol(&data)?;
+                match recorder.receive_and_deserialize_repair_symbol(&mut decoder, repair_symbol) {
+                    Ok((_, recovered)) => recovered,
+                    Err(DecoderError::UnusedRepairSymbol) => Vec::new(),
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        };
+        for symbol in recovered {
+            let id = source_symbol_metadata_to_u64(symbol.metadata());
+            if let Some(data) = lost_data.get(&id) {
+                if recovered_ids.insert(id) {
+                    report.source_symbols_recovered += 1;
+                    report.recovery_latencies.push(arrival.saturating_sub(sent_at[&id]));
+                    if data != symbol.get() {
+                        report.source_symbols_corrupted += 1;
+                    }
+                }
+            }
+        }
+        if let Some((first, last)) = decoder.bounds() {
+            let (first, last) = (source_symbol_metadata_to_u64(first), source_symbol_metadata_to_u64(last));
+            if last + 1 - first > window_size as u64 {
+                recorder.decoder_remove_up_to(&mut decoder, source_symbol_metadata_from_u64(last - window_size as u64), Some(now));
+            }
+        }
+    }
+
+    recorder.finish().map_err(SimulationError::Trace)?;
+    Ok(report)
+}
@@ -0,0 +1,411 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::config::valid_sizes;
+use crate::limits::DecoderLimits;
+use crate::simulator::Codec;
+use crate::{DecoderError, DecoderStats, EncoderError, EncoderStats, RepairSymbolCoefficients, RepairSymbolHeader};
+
+const MAGIC: &[u8; 4] = b"NCSN";
+const VERSION: u8 = 1;
+
+const KIND_ENCODER: u8 = 0;
+const KIND_DECODER: u8 = 1;
+
+const COEFFICIENTS_SEQUENCE_NUMBER: u8 = 0;
+const COEFFICIENTS_SEED: u8 = 1;
+
+const SYMBOL_ACKNOWLEDGED: u8 = 1;
+const SYMBOL_LANDED: u8 = 2;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnsupportedCodec(u8),
+    /// an encoder snapshot was given to a decoder or the opposite
+    WrongKind(u8),
+    /// snapshots were not enabled with set_snapshots before the window was filled
+    NotEnabled,
+    /// the codec cannot be put back in the state of the snapshot
+    NotRestorable(Codec),
+    /// the symbol or window size is out of the bounds of config::validate, or a symbol or a number of
+    /// symbols does not fit the symbol and window sizes of the snapshot
+    InvalidSize,
+    Encoder(EncoderError),
+    Decoder(DecoderError),
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> SnapshotError {
+        SnapshotError::Io(err)
+    }
+}
+
+/// A source symbol of the encoder window, along with what the application reported about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowSymbol {
+    pub id: u64,
+    pub data: Vec<u8>,
+    pub acknowledged: bool,
+    pub landed: bool,
+}
+
+/// State of an Encoder, from which Encoder::restore builds an encoder continuing the same session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncoderSnapshot {
+    pub codec: Codec,
+    pub symbol_size: usize,
+    pub window_size: usize,
+    pub stats: EncoderStats,
+    /// id of the next protected source symbol
+    pub next_id: u64,
+    /// VLC: sequence number of the next repair symbol. RLC: number of repair symbols generated, from which
+    /// the seed of the next one derives
+    pub repair_state: u64,
+    pub window: Vec<WindowSymbol>,
+}
+
+/// State of a Decoder, from which Decoder::restore builds a decoder continuing the same session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecoderSnapshot {
+    pub codec: Codec,
+    pub symbol_size: usize,
+    pub window_size: usize,
+    pub stats: DecoderStats,
+    pub limits: DecoderLimits,
+    /// id of the first symbol that was not removed from the window, even if the window is empty
+    pub window_start: u64,
+    pub work_budget: Option<u64>,
+    /// received and recovered source symbols of the window
+    pub source_symbols: Vec<(u64, Vec<u8>)>,
+    /// repair symbols handed to the codec that still protect symbols of the window
+    pub repair_symbols: Vec<(RepairSymbolHeader, Vec<u8>)>,
+    /// repair symbols waiting for the work budget
+    pub queued_repair_symbols: Vec<(RepairSymbolHeader, Vec<u8>)>,
+}
+
+/// Copies of what the codecs received that they do not give back, kept while snapshots are enabled.
+/// The entries are keyed by the last source symbol they relate to and dropped once it leaves the window
+pub(crate) struct Journal<T> {
+    entries: VecDeque<(u64, T)>,
+}
+
+impl<T> Journal<T> {
+    pub(crate) fn new() -> Journal<T> {
+        Journal {
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, last_id: u64, entry: T) {
+        self.entries.push_back((last_id, entry));
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|(_, entry)| entry)
+    }
+
+    #[cfg(feature = "enable-rlc")]
+    pub(crate) fn get_mut(&mut self, last_id: u64) -> Option<&mut T> {
+        self.entries.iter_mut().find(|(id, _)| *id == last_id).map(|(_, entry)| entry)
+    }
+
+    pub(crate) fn window_slid(&mut self, first_id: Option<u64>) {
+        match first_id {
+            Some(first_id) => self.entries.retain(|(last_id, _)| *last_id >= first_id),
+            None => self.entries.clear(),
+        }
+    }
+}
+
+fn write_header<W: Write>(out: &mut W, kind: u8, codec: Codec, symbol_size: usize, window_size: usize) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_u8(VERSION)?;
+    out.write_u8(kind)?;
+    match codec {
+        Codec::VLC => {
+            out.write_u8(0)?;
+            out.write_u32::<BigEndian>(0)?;
+        }
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { seed } => {
+            out.write_u8(1)?;
+            out.write_u32::<BigEndian>(seed)?;
+        }
+    }
+    out.write_u32::<BigEndian>(symbol_size as u32)?;
+    out.write_u32::<BigEndian>(window_size as u32)
+}
+
+fn read_header<R: Read>(input: &mut R, expected_kind: u8) -> Result<(Codec, usize, usize), SnapshotError> {
+    let mut magic = [0; 4];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = input.read_u8()?;
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let kind = input.read_u8()?;
+    if kind != expected_kind {
+        return Err(SnapshotError::WrongKind(kind));
+    }
+    let codec_id = input.read_u8()?;
+    let seed = input.read_u32::<BigEndian>()?;
+    let codec = match codec_id {
+        0 => Codec::VLC,
+        #[cfg(feature = "enable-rlc")]
+        1 => Codec::RLC { seed },
+        _ => {
+            let _ = seed;
+            return Err(SnapshotError::UnsupportedCodec(codec_id));
+        }
+    };
+    let symbol_size = input.read_u32::<BigEndian>()? as usize;
+    let window_size = input.read_u32::<BigEndian>()? as usize;
+    if !valid_sizes(symbol_size, window_size) {
+        return Err(SnapshotError::InvalidSize);
+    }
+    Ok((codec, symbol_size, window_size))
+}
+
+fn write_data<W: Write>(out: &mut W, data: &[u8]) -> io::Result<()> {
+    out.write_u32::<BigEndian>(data.len() as u32)?;
+    out.write_all(data)
+}
+
+///
+/// Reads the data of a symbol, checking its length before allocating it
+fn read_data<R: Read>(input: &mut R, symbol_size: usize) -> Result<Vec<u8>, SnapshotError> {
+    let len = input.read_u32::<BigEndian>()? as usize;
+    if len != symbol_size {
+        return Err(SnapshotError::InvalidSize);
+    }
+    let mut data = vec![0; len];
+    input.read_exact(&mut data)?;
+    Ok(data)
+}
+
+fn read_count<R: Read>(input: &mut R, max: u64) -> Result<u32, SnapshotError> {
+    let count = input.read_u32::<BigEndian>()?;
+    if count as u64 > max {
+        return Err(SnapshotError::InvalidSize);
+    }
+    Ok(count)
+}
+
+fn write_repair_symbols<W: Write>(out: &mut W, repair_symbols: &[(RepairSymbolHeader, Vec<u8>)]) -> io::Result<()> {
+    out.write_u32::<BigEndian>(repair_symbols.len() as u32)?;
+    for (header, data) in repair_symbols {
+        out.write_u64::<BigEndian>(header.first_id)?;
+        out.write_u64::<BigEndian>(header.n_protected_symbols)?;
+        match header.coefficients {
+            RepairSymbolCoefficients::SequenceNumber(sequence_number) => {
+                out.write_u8(COEFFICIENTS_SEQUENCE_NUMBER)?;
+                out.write_u64::<BigEndian>(sequence_number)?;
+            }
+            RepairSymbolCoefficients::Seed(seed) => {
+                out.write_u8(COEFFICIENTS_SEED)?;
+                out.write_u64::<BigEndian>(seed as u64)?;
+            }
+        }
+        out.write_u32::<BigEndian>(header.header_len as u32)?;
+        write_data(out, data)?;
+    }
+    Ok(())
+}
+
+fn read_repair_symbols<R: Read>(input: &mut R, symbol_size: usize, max_count: u64) -> Result<Vec<(RepairSymbolHeader, Vec<u8>)>, SnapshotError> {
+    let count = read_count(input, max_count)?;
+    let mut repair_symbols = Vec::new();
+    for _ in 0..count {
+        let first_id = input.read_u64::<BigEndian>()?;
+        let n_protected_symbols = input.read_u64::<BigEndian>()?;
+        let coefficients = match (input.read_u8()?, input.read_u64::<BigEndian>()?) {
+            (COEFFICIENTS_SEQUENCE_NUMBER, sequence_number) => RepairSymbolCoefficients::SequenceNumber(sequence_number),
+            (COEFFICIENTS_SEED, seed) => RepairSymbolCoefficients::Seed(seed as u32),
+            _ => return Err(SnapshotError::Decoder(DecoderError::BadMetadata)),
+        };
+        let header = RepairSymbolHeader {
+            first_id,
+            n_protected_symbols,
+            coefficients,
+            header_len: input.read_u32::<BigEndian>()? as usize,
+        };
+        header.validate().map_err(SnapshotError::Decoder)?;
+        repair_symbols.push((header, read_data(input, symbol_size)?));
+    }
+    Ok(repair_symbols)
+}
+
+impl EncoderSnapshot {
+    ///
+    /// Checks that the window fits the window size and holds symbols of the symbol size
+    pub(crate) fn check_sizes(&self) -> Result<(), SnapshotError> {
+        if !valid_sizes(self.symbol_size, self.window_size) || self.window.len() > self.window_size || self.window.iter().any(|symbol| symbol.data.len() != self.symbol_size) {
+            return Err(SnapshotError::InvalidSize);
+        }
+        Ok(())
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write_header(out, KIND_ENCODER, self.codec, self.symbol_size, self.window_size)?;
+        out.write_u64::<BigEndian>(self.stats.protected_source_symbols)?;
+        out.write_u64::<BigEndian>(self.stats.generated_repair_symbols)?;
+        out.write_u64::<BigEndian>(self.stats.acknowledged_source_symbols)?;
+        out.write_u64::<BigEndian>(self.stats.current_window_size)?;
+        out.write_u64::<BigEndian>(self.next_id)?;
+        out.write_u64::<BigEndian>(self.repair_state)?;
+        out.write_u32::<BigEndian>(self.window.len() as u32)?;
+        for symbol in &self.window {
+            out.write_u64::<BigEndian>(symbol.id)?;
+            let mut flags = 0;
+            if symbol.acknowledged {
+                flags |= SYMBOL_ACKNOWLEDGED;
+            }
+            if symbol.landed {
+                flags |= SYMBOL_LANDED;
+            }
+            out.write_u8(flags)?;
+            write_data(out, &symbol.data)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(input: &mut R) -> Result<EncoderSnapshot, SnapshotError> {
+        let (codec, symbol_size, window_size) = read_header(input, KIND_ENCODER)?;
+        let stats = EncoderStats {
+            protected_source_symbols: input.read_u64::<BigEndian>()?,
+            generated_repair_symbols: input.read_u64::<BigEndian>()?,
+            acknowledged_source_symbols: input.read_u64::<BigEndian>()?,
+            current_window_size: input.read_u64::<BigEndian>()?,
+        };
+        let next_id = input.read_u64::<BigEndian>()?;
+        let repair_state = input.read_u64::<BigEndian>()?;
+        let count = read_count(input, window_size as u64)?;
+        let mut window = Vec::new();
+        for _ in 0..count {
+            let id = input.read_u64::<BigEndian>()?;
+            let flags = input.read_u8()?;
+            window.push(WindowSymbol {
+                id,
+                data: read_data(input, symbol_size)?,
+                acknowledged: flags & SYMBOL_ACKNOWLEDGED != 0,
+                landed: flags & SYMBOL_LANDED != 0,
+            });
+        }
+        Ok(EncoderSnapshot {
+            codec,
+            symbol_size,
+            window_size,
+            stats,
+            next_id,
+            repair_state,
+            window,
+        })
+    }
+}
+
+impl DecoderSnapshot {
+    ///
+    /// Checks that the symbols have the symbol size and that their numbers fit the limits of the snapshot
+    pub(crate) fn check_sizes(&self) -> Result<(), SnapshotError> {
+        let mut repair_symbols = self.repair_symbols.iter().chain(&self.queued_repair_symbols);
+        if !valid_sizes(self.symbol_size, self.window_size)
+            || self.source_symbols.len() as u64 > self.limits.max_window_span
+            || (self.repair_symbols.len() + self.queued_repair_symbols.len()) as u64 > self.limits.max_buffered_repair_symbols
+            || self.source_symbols.iter().any(|(_, data)| data.len() != self.symbol_size)
+            || repair_symbols.any(|(_, data)| data.len() != self.symbol_size) {
+            return Err(SnapshotError::InvalidSize);
+        }
+        Ok(())
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write_header(out, KIND_DECODER, self.codec, self.symbol_size, self.window_size)?;
+        for counter in [
+            self.stats.received_source_symbols,
+            self.stats.received_repair_symbols,
+            self.stats.recovered_source_symbols,
+            self.stats.unused_source_symbols,
+            self.stats.unused_repair_symbols,
+            self.stats.expired_source_symbols,
+            self.stats.current_window_size,
+            self.stats.rejected_symbols,
+            self.limits.max_protected_symbols,
+            self.limits.max_future_symbols,
+            self.limits.max_window_span,
+            self.limits.max_buffered_repair_symbols,
+            self.window_start,
+        ] {
+            out.write_u64::<BigEndian>(counter)?;
+        }
+        match self.work_budget {
+            None => out.write_u8(0)?,
+            Some(budget) => {
+                out.write_u8(1)?;
+                out.write_u64::<BigEndian>(budget)?;
+            }
+        }
+        out.write_u32::<BigEndian>(self.source_symbols.len() as u32)?;
+        for (id, data) in &self.source_symbols {
+            out.write_u64::<BigEndian>(*id)?;
+            write_data(out, data)?;
+        }
+        write_repair_symbols(out, &self.repair_symbols)?;
+        write_repair_symbols(out, &self.queued_repair_symbols)
+    }
+
+    pub fn read<R: Read>(input: &mut R) -> Result<DecoderSnapshot, SnapshotError> {
+        let (codec, symbol_size, window_size) = read_header(input, KIND_DECODER)?;
+        let mut counters = [0u64; 13];
+        for counter in counters.iter_mut() {
+            *counter = input.read_u64::<BigEndian>()?;
+        }
+        let stats = DecoderStats {
+            received_source_symbols: counters[0],
+            received_repair_symbols: counters[1],
+            recovered_source_symbols: counters[2],
+            unused_source_symbols: counters[3],
+            unused_repair_symbols: counters[4],
+            expired_source_symbols: counters[5],
+            current_window_size: counters[6],
+            rejected_symbols: counters[7],
+        };
+        let limits = DecoderLimits {
+            max_protected_symbols: counters[8],
+            max_future_symbols: counters[9],
+            max_window_span: counters[10],
+            max_buffered_repair_symbols: counters[11],
+        };
+        let work_budget = match input.read_u8()? {
+            0 => None,
+            _ => Some(input.read_u64::<BigEndian>()?),
+        };
+        let count = read_count(input, limits.max_window_span)?;
+        let mut source_symbols = Vec::new();
+        for _ in 0..count {
+            let id = input.read_u64::<BigEndian>()?;
+            source_symbols.push((id, read_data(input, symbol_size)?));
+        }
+        let repair_symbols = read_repair_symbols(input, symbol_size, limits.max_buffered_repair_symbols)?;
+        let max_queued = limits.max_buffered_repair_symbols - repair_symbols.len() as u64;
+        Ok(DecoderSnapshot {
+            codec,
+            symbol_size,
+            window_size,
+            stats,
+            limits,
+            window_start: counters[12],
+            work_budget,
+            source_symbols,
+            repair_symbols,
+            queued_repair_symbols: read_repair_symbols(input, symbol_size, max_queued)?,
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use crate::config::{Field, Scheme};
+use crate::DecoderError;
+
+pub const TAG_LEN: usize = 4;
+pub const TAG_VERSION: u8 = 1;
+
+/// Optional prefix of the serialized repair symbols identifying the codec that generated them:
+/// the scheme (0 for VLC, 1 for RLC, as in codec_config_t), the version of the repair symbol layout,
+/// m for GF(2^m) and flags, one byte each. No flag is defined yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepairSymbolTag {
+    pub scheme: Scheme,
+    pub version: u8,
+    pub field: Field,
+    pub flags: u8,
+}
+
+impl RepairSymbolTag {
+    ///
+    /// Tag of the repair symbols generated by the given scheme in this version of the crate
+    pub fn new(scheme: Scheme) -> RepairSymbolTag {
+        RepairSymbolTag {
+            scheme,
+            version: TAG_VERSION,
+            field: Field::Gf256,
+            flags: 0,
+        }
+    }
+
+    pub fn write(&self, out: &mut [u8]) {
+        out[0] = match self.scheme {
+            Scheme::Vlc => 0,
+            Scheme::Rlc => 1,
+        };
+        out[1] = self.version;
+        out[2] = self.field.bits();
+        out[3] = self.flags;
+    }
+
+    ///
+    /// Parses the tag at the start of a tagged repair symbol, e.g. to hand it to the decoder of its scheme.
+    /// Unknown schemes and fields are rejected with InvalidSymbol
+    pub fn parse(data: &[u8]) -> Result<RepairSymbolTag, DecoderError> {
+        if data.len() < TAG_LEN {
+            return Err(DecoderError::BufferTooSmall);
+        }
+        let scheme = match data[0] {
+            0 => Scheme::Vlc,
+            1 => Scheme::Rlc,
+            scheme => {
+                log::debug!("unknown scheme {} in repair symbol tag", scheme);
+                return Err(DecoderError::InvalidSymbol);
+            }
+        };
+        let field = Field::from_bits(data[2]).ok_or_else(|| {
+            log::debug!("unknown field GF(2^{}) in repair symbol tag", data[2]);
+            DecoderError::InvalidSymbol
+        })?;
+        Ok(RepairSymbolTag {
+            scheme,
+            version: data[1],
+            field,
+            flags: data[3],
+        })
+    }
+
+    ///
+    /// Checks that a repair symbol was tagged by the given scheme, with a layout and flags this decoder understands
+    pub(crate) fn check(data: &[u8], scheme: Scheme) -> Result<(), DecoderError> {
+        let tag = RepairSymbolTag::parse(data)?;
+        if tag != RepairSymbolTag::new(scheme) {
+            log::debug!("repair symbol tagged {:?} given to a {:?} decoder", tag, scheme);
+            return Err(DecoderError::MismatchedCodec);
+        }
+        Ok(())
+    }
+}
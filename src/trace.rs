@@ -0,0 +1,512 @@
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::config::valid_sizes;
+use crate::simulator::Codec;
+use crate::{Decoder, DecoderError, Encoder, EncoderError, RepairSymbol, SourceSymbol, SourceSymbolMetadata, source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+const MAGIC: &[u8; 4] = b"NCTR";
+const VERSION: u8 = 1;
+
+const CALL_PROTECT_DATA: u8 = 0;
+const CALL_GENERATE_REPAIR_SYMBOL: u8 = 1;
+const CALL_RECEIVED_SYMBOL: u8 = 2;
+const CALL_ENCODER_REMOVE_UP_TO: u8 = 3;
+const CALL_SYMBOL_LANDED: u8 = 4;
+const CALL_REMOVE_LANDED_SYMBOLS: u8 = 5;
+const CALL_RECEIVE_SOURCE_SYMBOL: u8 = 6;
+const CALL_RECEIVE_REPAIR_SYMBOL: u8 = 7;
+const CALL_DECODER_REMOVE_UP_TO: u8 = 8;
+
+const OUTCOME_DONE: u8 = 0;
+const OUTCOME_METADATA: u8 = 1;
+const OUTCOME_CONSUMED: u8 = 2;
+const OUTCOME_REPAIR_SYMBOL: u8 = 3;
+const OUTCOME_RECOVERED: u8 = 4;
+const OUTCOME_ENCODER_ERROR: u8 = 5;
+const OUTCOME_DECODER_ERROR: u8 = 6;
+
+#[derive(Debug)]
+pub enum TraceError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnsupportedCodec(u8),
+    UnknownRecord(u8),
+    /// the symbol or window size is out of the bounds of config::validate
+    InvalidSize,
+}
+
+impl From<io::Error> for TraceError {
+    fn from(err: io::Error) -> TraceError {
+        TraceError::Io(err)
+    }
+}
+
+/// Parameters of the encoder and decoder the trace was recorded with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceHeader {
+    pub codec: Codec,
+    pub symbol_size: usize,
+    pub window_size: usize,
+}
+
+impl TraceHeader {
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_u8(VERSION)?;
+        match self.codec {
+            Codec::VLC => {
+                out.write_u8(0)?;
+                out.write_u32::<BigEndian>(0)?;
+            }
+            #[cfg(feature = "enable-rlc")]
+            Codec::RLC { seed } => {
+                out.write_u8(1)?;
+                out.write_u32::<BigEndian>(seed)?;
+            }
+        }
+        out.write_u32::<BigEndian>(self.symbol_size as u32)?;
+        out.write_u32::<BigEndian>(self.window_size as u32)
+    }
+
+    pub fn read<R: Read>(input: &mut R) -> Result<TraceHeader, TraceError> {
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(TraceError::BadMagic);
+        }
+        let version = input.read_u8()?;
+        if version != VERSION {
+            return Err(TraceError::UnsupportedVersion(version));
+        }
+        let codec_id = input.read_u8()?;
+        let seed = input.read_u32::<BigEndian>()?;
+        let codec = match codec_id {
+            0 => Codec::VLC,
+            #[cfg(feature = "enable-rlc")]
+            1 => Codec::RLC { seed },
+            _ => {
+                let _ = seed;
+                return Err(TraceError::UnsupportedCodec(codec_id));
+            }
+        };
+        let symbol_size = input.read_u32::<BigEndian>()? as usize;
+        let window_size = input.read_u32::<BigEndian>()? as usize;
+        if !valid_sizes(symbol_size, window_size) {
+            return Err(TraceError::InvalidSize);
+        }
+        Ok(TraceHeader {
+            codec,
+            symbol_size,
+            window_size,
+        })
+    }
+}
+
+/// An Encoder or Decoder API call along with its arguments, source symbol ids are stored as u64
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceCall {
+    ProtectData { data: Vec<u8> },
+    GenerateRepairSymbol { up_to: Option<u64> },
+    ReceivedSymbol { metadata: Vec<u8> },
+    EncoderRemoveUpTo { md: u64 },
+    SymbolLanded { md: u64 },
+    RemoveLandedSymbols,
+    ReceiveSourceSymbol { md: u64, data: Vec<u8> },
+    /// the serialized repair symbol, as given to read_repair_symbol
+    ReceiveRepairSymbol { data: Vec<u8> },
+    /// the call timestamp is the expiration time if expired is true
+    DecoderRemoveUpTo { md: u64, expired: bool },
+}
+
+/// What a traced call returned. Repair symbols are summarized by their length and digest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Done,
+    Metadata(u64),
+    Consumed(usize),
+    RepairSymbol { len: usize, digest: u64 },
+    Recovered(Vec<u64>),
+    EncoderError(u64),
+    DecoderError(u64),
+}
+
+impl Outcome {
+    fn repair_symbol(data: &[u8]) -> Outcome {
+        // 64-bit FNV-1a
+        let digest = data.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| (hash ^ *b as u64).wrapping_mul(0x0100_0000_01b3));
+        Outcome::RepairSymbol { len: data.len(), digest }
+    }
+
+    fn recovered(symbols: &[SourceSymbol]) -> Outcome {
+        Outcome::Recovered(symbols.iter().map(|s| source_symbol_metadata_to_u64(s.metadata())).collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// time of the call since the start of the trace
+    pub at: Duration,
+    pub call: TraceCall,
+    pub outcome: Outcome,
+}
+
+fn write_bytes<W: Write>(out: &mut W, data: &[u8]) -> io::Result<()> {
+    out.write_u32::<BigEndian>(data.len() as u32)?;
+    out.write_all(data)
+}
+
+fn read_bytes<R: Read>(input: &mut R) -> io::Result<Vec<u8>> {
+    let len = input.read_u32::<BigEndian>()? as usize;
+    let mut data = Vec::new();
+    input.take(len as u64).read_to_end(&mut data)?;
+    if data.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(data)
+}
+
+impl TraceEntry {
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let tag = match &self.call {
+            TraceCall::ProtectData { .. } => CALL_PROTECT_DATA,
+            TraceCall::GenerateRepairSymbol { .. } => CALL_GENERATE_REPAIR_SYMBOL,
+            TraceCall::ReceivedSymbol { .. } => CALL_RECEIVED_SYMBOL,
+            TraceCall::EncoderRemoveUpTo { .. } => CALL_ENCODER_REMOVE_UP_TO,
+            TraceCall::SymbolLanded { .. } => CALL_SYMBOL_LANDED,
+            TraceCall::RemoveLandedSymbols => CALL_REMOVE_LANDED_SYMBOLS,
+            TraceCall::ReceiveSourceSymbol { .. } => CALL_RECEIVE_SOURCE_SYMBOL,
+            TraceCall::ReceiveRepairSymbol { .. } => CALL_RECEIVE_REPAIR_SYMBOL,
+            TraceCall::DecoderRemoveUpTo { .. } => CALL_DECODER_REMOVE_UP_TO,
+        };
+        out.write_u8(tag)?;
+        out.write_u64::<BigEndian>(self.at.as_micros() as u64)?;
+        match &self.call {
+            TraceCall::ProtectData { data } | TraceCall::ReceivedSymbol { metadata: data } | TraceCall::ReceiveRepairSymbol { data } => write_bytes(out, data)?,
+            TraceCall::GenerateRepairSymbol { up_to } => {
+                out.write_u8(up_to.is_some() as u8)?;
+                out.write_u64::<BigEndian>(up_to.unwrap_or(0))?;
+            }
+            TraceCall::EncoderRemoveUpTo { md } | TraceCall::SymbolLanded { md } => out.write_u64::<BigEndian>(*md)?,
+            TraceCall::RemoveLandedSymbols => (),
+            TraceCall::ReceiveSourceSymbol { md, data } => {
+                out.write_u64::<BigEndian>(*md)?;
+                write_bytes(out, data)?;
+            }
+            TraceCall::DecoderRemoveUpTo { md, expired } => {
+                out.write_u64::<BigEndian>(*md)?;
+                out.write_u8(*expired as u8)?;
+            }
+        }
+        match &self.outcome {
+            Outcome::Done => out.write_u8(OUTCOME_DONE),
+            Outcome::Metadata(md) => {
+                out.write_u8(OUTCOME_METADATA)?;
+                out.write_u64::<BigEndian>(*md)
+            }
+            Outcome::Consumed(n) => {
+                out.write_u8(OUTCOME_CONSUMED)?;
+                out.write_u32::<BigEndian>(*n as u32)
+            }
+            Outcome::RepairSymbol { len, digest } => {
+                out.write_u8(OUTCOME_REPAIR_SYMBOL)?;
+                out.write_u32::<BigEndian>(*len as u32)?;
+                out.write_u64::<BigEndian>(*digest)
+            }
+            Outcome::Recovered(ids) => {
+                out.write_u8(OUTCOME_RECOVERED)?;
+                out.write_u32::<BigEndian>(ids.len() as u32)?;
+                ids.iter().try_for_each(|id| out.write_u64::<BigEndian>(*id))
+            }
+            Outcome::EncoderError(code) => {
+                out.write_u8(OUTCOME_ENCODER_ERROR)?;
+                out.write_u64::<BigEndian>(*code)
+            }
+            Outcome::DecoderError(code) => {
+                out.write_u8(OUTCOME_DECODER_ERROR)?;
+                out.write_u64::<BigEndian>(*code)
+            }
+        }
+    }
+
+    ///
+    /// Reads the next entry, returns None at the end of the trace
+    pub fn read<R: Read>(input: &mut R) -> Result<Option<TraceEntry>, TraceError> {
+        let tag = match input.read_u8() {
+            Ok(tag) => tag,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let at = Duration::from_micros(input.read_u64::<BigEndian>()?);
+        let call = match tag {
+            CALL_PROTECT_DATA => TraceCall::ProtectData { data: read_bytes(input)? },
+            CALL_GENERATE_REPAIR_SYMBOL => {
+                let bounded = input.read_u8()? != 0;
+                let up_to = input.read_u64::<BigEndian>()?;
+                TraceCall::GenerateRepairSymbol { up_to: if bounded { Some(up_to) } else { None } }
+            }
+            CALL_RECEIVED_SYMBOL => TraceCall::ReceivedSymbol { metadata: read_bytes(input)? },
+            CALL_ENCODER_REMOVE_UP_TO => TraceCall::EncoderRemoveUpTo { md: input.read_u64::<BigEndian>()? },
+            CALL_SYMBOL_LANDED => TraceCall::SymbolLanded { md: input.read_u64::<BigEndian>()? },
+            CALL_REMOVE_LANDED_SYMBOLS => TraceCall::RemoveLandedSymbols,
+            CALL_RECEIVE_SOURCE_SYMBOL => TraceCall::ReceiveSourceSymbol {
+                md: input.read_u64::<BigEndian>()?,
+                data: read_bytes(input)?,
+            },
+            CALL_RECEIVE_REPAIR_SYMBOL => TraceCall::ReceiveRepairSymbol { data: read_bytes(input)? },
+            CALL_DECODER_REMOVE_UP_TO => TraceCall::DecoderRemoveUpTo {
+                md: input.read_u64::<BigEndian>()?,
+                expired: input.read_u8()? != 0,
+            },
+            tag => return Err(TraceError::UnknownRecord(tag)),
+        };
+        let outcome = match input.read_u8()? {
+            OUTCOME_DONE => Outcome::Done,
+            OUTCOME_METADATA => Outcome::Metadata(input.read_u64::<BigEndian>()?),
+            OUTCOME_CONSUMED => Outcome::Consumed(input.read_u32::<BigEndian>()? as usize),
+            OUTCOME_REPAIR_SYMBOL => Outcome::RepairSymbol {
+                len: input.read_u32::<BigEndian>()? as usize,
+                digest: input.read_u64::<BigEndian>()?,
+            },
+            OUTCOME_RECOVERED => {
+                let n = input.read_u32::<BigEndian>()?;
+                Outcome::Recovered((0..n).map(|_| input.read_u64::<BigEndian>()).collect::<io::Result<_>>()?)
+            }
+            OUTCOME_ENCODER_ERROR => Outcome::EncoderError(input.read_u64::<BigEndian>()?),
+            OUTCOME_DECODER_ERROR => Outcome::DecoderError(input.read_u64::<BigEndian>()?),
+            tag => return Err(TraceError::UnknownRecord(tag)),
+        };
+        Ok(Some(TraceEntry { at, call, outcome }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub header: TraceHeader,
+    pub entries: Vec<TraceEntry>,
+}
+
+impl Trace {
+    pub fn read<R: Read>(input: &mut R) -> Result<Trace, TraceError> {
+        let header = TraceHeader::read(input)?;
+        let mut entries = Vec::new();
+        while let Some(entry) = TraceEntry::read(input)? {
+            entries.push(entry);
+        }
+        Ok(Trace { header, entries })
+    }
+}
+
+///
+/// Forwards Encoder and Decoder calls and records each of them, with its arguments, timestamp
+/// and outcome, so that a fresh encoder and decoder can replay them deterministically.
+/// An I/O error does not interrupt the calls, it stops the recording and is returned by finish()
+pub struct TraceRecorder<W: Write> {
+    out: W,
+    start: Instant,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> TraceRecorder<W> {
+    pub fn new(mut out: W, header: &TraceHeader) -> io::Result<TraceRecorder<W>> {
+        header.write(&mut out)?;
+        Ok(TraceRecorder {
+            out,
+            start: Instant::now(),
+            error: None,
+        })
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.out.flush().map(|_| self.out),
+        }
+    }
+
+    fn record(&mut self, at: Instant, call: TraceCall, outcome: Outcome) {
+        if self.error.is_some() {
+            return;
+        }
+        let entry = TraceEntry { at: at.saturating_duration_since(self.start), call, outcome };
+        if let Err(err) = entry.write(&mut self.out) {
+            log::warn!("trace recording stopped: {}", err);
+            self.error = Some(err);
+        }
+    }
+
+    fn encoder_outcome<T>(res: &Result<T, EncoderError>, outcome: impl FnOnce(&T) -> Outcome) -> Outcome {
+        match res {
+            Ok(v) => outcome(v),
+            Err(err) => Outcome::EncoderError(err.to_u64()),
+        }
+    }
+
+    fn decoder_outcome<T>(res: &Result<T, DecoderError>, outcome: impl FnOnce(&T) -> Outcome) -> Outcome {
+        match res {
+            Ok(v) => outcome(v),
+            Err(err) => Outcome::DecoderError(err.to_u64()),
+        }
+    }
+
+    pub fn protect_data(&mut self, encoder: &mut Encoder, data: Vec<u8>, output: &mut SourceSymbolMetadata) -> Result<usize, EncoderError> {
+        let call = TraceCall::ProtectData { data: data.clone() };
+        let res = encoder.protect_data(data, output);
+        let md = source_symbol_metadata_to_u64(*output);
+        self.record(Instant::now(), call, Self::encoder_outcome(&res, |_| Outcome::Metadata(md)));
+        res
+    }
+
+    pub fn generate_and_serialize_repair_symbol(&mut self, encoder: &mut Encoder) -> Result<RepairSymbol, EncoderError> {
+        let res = encoder.generate_and_serialize_repair_symbol();
+        self.record(Instant::now(), TraceCall::GenerateRepairSymbol { up_to: None },
+                    Self::encoder_outcome(&res, |r| Outcome::repair_symbol(r.get())));
+        res
+    }
+
+    pub fn generate_and_serialize_repair_symbol_up_to(&mut self, encoder: &mut Encoder, up_to: SourceSymbolMetadata) -> Result<RepairSymbol, EncoderError> {
+        let res = encoder.generate_and_serialize_repair_symbol_up_to(up_to);
+        self.record(Instant::now(), TraceCall::GenerateRepairSymbol { up_to: Some(source_symbol_metadata_to_u64(up_to)) },
+                    Self::encoder_outcome(&res, |r| Outcome::repair_symbol(r.get())));
+        res
+    }
+
+    pub fn received_symbol(&mut self, encoder: &mut Encoder, metadata: &[u8]) -> Result<usize, EncoderError> {
+        let res = encoder.received_symbol(metadata);
+        self.record(Instant::now(), TraceCall::ReceivedSymbol { metadata: metadata.to_vec() },
+                    Self::encoder_outcome(&res, |n| Outcome::Consumed(*n)));
+        res
+    }
+
+    pub fn encoder_remove_up_to(&mut self, encoder: &mut Encoder, md: SourceSymbolMetadata) {
+        encoder.remove_up_to(md);
+        self.record(Instant::now(), TraceCall::EncoderRemoveUpTo { md: source_symbol_metadata_to_u64(md) }, Outcome::Done);
+    }
+
+    pub fn symbol_landed(&mut self, encoder: &mut Encoder, md: SourceSymbolMetadata) {
+        encoder.symbol_landed(md);
+        self.record(Instant::now(), TraceCall::SymbolLanded { md: source_symbol_metadata_to_u64(md) }, Outcome::Done);
+    }
+
+    pub fn remove_landed_symbols(&mut self, encoder: &mut Encoder) {
+        encoder.remove_landed_symbols();
+        self.record(Instant::now(), TraceCall::RemoveLandedSymbols, Outcome::Done);
+    }
+
+    pub fn receive_source_symbol(&mut self, decoder: &mut Decoder, source_symbol: SourceSymbol, received_at: Instant) -> Result<Vec<SourceSymbol>, DecoderError> {
+        let call = TraceCall::ReceiveSourceSymbol {
+            md: source_symbol_metadata_to_u64(source_symbol.metadata()),
+            data: source_symbol.get().clone(),
+        };
+        let res = decoder.receive_source_symbol(source_symbol, received_at);
+        self.record(received_at, call, Self::decoder_outcome(&res, |r| Outcome::recovered(r)));
+        res
+    }
+
+    pub fn receive_and_deserialize_repair_symbol(&mut self, decoder: &mut Decoder, repair_symbol: RepairSymbol) -> Result<(usize, Vec<SourceSymbol>), DecoderError> {
+        let call = TraceCall::ReceiveRepairSymbol { data: repair_symbol.get().clone() };
+        let res = decoder.receive_and_deserialize_repair_symbol(repair_symbol);
+        self.record(Instant::now(), call, Self::decoder_outcome(&res, |(_, r)| Outcome::recovered(r)));
+        res
+    }
+
+    pub fn decoder_remove_up_to(&mut self, decoder: &mut Decoder, md: SourceSymbolMetadata, expired_at: Option<Instant>) -> SourceSymbolMetadata {
+        let res = decoder.remove_up_to(md, expired_at);
+        self.record(expired_at.unwrap_or_else(Instant::now),
+                    TraceCall::DecoderRemoveUpTo { md: source_symbol_metadata_to_u64(md), expired: expired_at.is_some() },
+                    Outcome::Metadata(source_symbol_metadata_to_u64(res)));
+        res
+    }
+}
+
+/// A traced call whose outcome differs from the recorded one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub index: usize,
+    pub entry: TraceEntry,
+    pub actual: Outcome,
+}
+
+///
+/// Re-executes traced calls against a fresh encoder and decoder. Timestamps are replayed
+/// relative to the creation of the replayer, without waiting
+pub struct TraceReplayer {
+    pub encoder: Encoder,
+    pub decoder: Decoder,
+    start: Instant,
+}
+
+impl TraceReplayer {
+    pub fn new(header: &TraceHeader) -> TraceReplayer {
+        TraceReplayer {
+            encoder: header.codec.new_encoder(header.symbol_size, header.window_size),
+            decoder: header.codec.new_decoder(header.symbol_size, header.window_size),
+            start: Instant::now(),
+        }
+    }
+
+    ///
+    /// Executes the call of the entry and returns its outcome
+    pub fn step(&mut self, entry: &TraceEntry) -> Outcome {
+        let at = self.start + entry.at;
+        let encoder_outcome = |res: Result<Outcome, EncoderError>| res.unwrap_or_else(|err| Outcome::EncoderError(err.to_u64()));
+        let decoder_outcome = |res: Result<Outcome, DecoderError>| res.unwrap_or_else(|err| Outcome::DecoderError(err.to_u64()));
+        match &entry.call {
+            TraceCall::ProtectData { data } => {
+                let mut md = source_symbol_metadata_from_u64(0);
+                encoder_outcome(self.encoder.protect_data(data.clone(), &mut md).map(|_| Outcome::Metadata(source_symbol_metadata_to_u64(md))))
+            }
+            TraceCall::GenerateRepairSymbol { up_to } => {
+                let res = match up_to {
+                    Some(up_to) => self.encoder.generate_and_serialize_repair_symbol_up_to(source_symbol_metadata_from_u64(*up_to)),
+                    None => self.encoder.generate_and_serialize_repair_symbol(),
+                };
+                encoder_outcome(res.map(|r| Outcome::repair_symbol(r.get())))
+            }
+            TraceCall::ReceivedSymbol { metadata } => encoder_outcome(self.encoder.received_symbol(metadata).map(Outcome::Consumed)),
+            TraceCall::EncoderRemoveUpTo { md } => {
+                self.encoder.remove_up_to(source_symbol_metadata_from_u64(*md));
+                Outcome::Done
+            }
+            TraceCall::SymbolLanded { md } => {
+                self.encoder.symbol_landed(source_symbol_metadata_from_u64(*md));
+                Outcome::Done
+            }
+            TraceCall::RemoveLandedSymbols => {
+                self.encoder.remove_landed_symbols();
+                Outcome::Done
+            }
+            TraceCall::ReceiveSourceSymbol { md, data } => {
+                let symbol = SourceSymbol::new(source_symbol_metadata_from_u64(*md), data.clone());
+                decoder_outcome(self.decoder.receive_source_symbol(symbol, at).map(|r| Outcome::recovered(&r)))
+            }
+            TraceCall::ReceiveRepairSymbol { data } => {
+                let decoder = &mut self.decoder;
+                decoder_outcome(decoder.read_repair_symbol(data)
+                    .and_then(|(_, repair_symbol)| decoder.receive_and_deserialize_repair_symbol(repair_symbol))
+                    .map(|(_, r)| Outcome::recovered(&r)))
+            }
+            TraceCall::DecoderRemoveUpTo { md, expired } => {
+                let res = self.decoder.remove_up_to(source_symbol_metadata_from_u64(*md), if *expired { Some(at) } else { None });
+                Outcome::Metadata(source_symbol_metadata_to_u64(res))
+            }
+        }
+    }
+}
+
+///
+/// Replays the whole trace and returns the calls whose outcome differ from the recorded ones
+pub fn replay(trace: &Trace) -> Vec<Divergence> {
+    let mut replayer = TraceReplayer::new(&trace.header);
+    trace.entries.iter().enumerate().filter_map(|(index, entry)| {
+        let actual = replayer.step(entry);
+        if actual == entry.outcome {
+            None
+        } else {
+            Some(Divergence { index, entry: entry.clone(), actual })
+        }
+    }).collect()
+}
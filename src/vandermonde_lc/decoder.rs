@@ -3,8 +3,17 @@ use vandermonde_lc::{decoder::Decoder as RustVLCDecoder, SymbolID};
 use vandermonde_lc::common::repair_symbol::RepairSymbol as RustVLCRepairSymbol;
 use vandermonde_lc::common::source_symbol::SourceSymbol as RustVLCSourceSymbol;
 use vandermonde_lc::decoder::DecoderError as VLCDecoderError;
-use crate::{DecoderError, RepairSymbol, source_symbol_metadata_from_u64, SourceSymbol, SourceSymbolMetadata, source_symbol_metadata_to_u64};
+use crate::{DecoderError, DecoderStats, RepairSymbol, RepairSymbolCoefficients, RepairSymbolHeader, source_symbol_metadata_from_u64, SourceSymbol, SourceSymbolMetadata, source_symbol_metadata_to_u64, Timestamp};
 use byteorder::{BigEndian, ByteOrder};
+use crate::events::{EventEmitter, EventSink, FecEvent, Role};
+use crate::budget::RepairQueue;
+use crate::limits::{DecoderLimits, LimitGuard};
+use crate::pool::BufferPool;
+use crate::simulator::Codec;
+use crate::snapshot::{DecoderSnapshot, Journal, SnapshotError};
+use crate::config::Scheme;
+use crate::tag::{RepairSymbolTag, TAG_LEN};
+use std::cell::RefCell;
 use crate::DecoderError::{BufferTooSmall};
 
 
@@ -25,6 +34,15 @@ impl From<VLCDecoderError> for DecoderError {
 pub struct VLCDecoder {
     rust_vlc_decoder: RustVLCDecoder,
     symbol_size: usize,
+    stats: DecoderStats,
+    events: EventEmitter,
+    limits: LimitGuard,
+    // read_repair_symbol only borrows the decoder
+    pool: RefCell<BufferPool>,
+    repair_queue: RepairQueue,
+    max_window_size: usize,
+    journal: Option<Journal<(RepairSymbolHeader, Vec<u8>)>>,
+    tagged: bool,
 }
 
 impl VLCDecoder {
@@ -32,41 +50,135 @@ impl VLCDecoder {
         VLCDecoder{
             rust_vlc_decoder: RustVLCDecoder::new(symbol_size, max_window_size, Some(galois_2p8::PrimitivePolynomialField::new(galois_2p8::IrreducablePolynomial::Poly84320).unwrap())),
             symbol_size,
+            stats: DecoderStats::default(),
+            events: EventEmitter::default(),
+            limits: LimitGuard::new(max_window_size),
+            pool: RefCell::new(BufferPool::new(symbol_size + TAG_LEN + 8 + 8 + 4, max_window_size)),
+            repair_queue: RepairQueue::new(),
+            max_window_size,
+            journal: None,
+            tagged: false,
         }
     }
 
-    pub fn receive_source_symbol(&mut self, source_symbol: SourceSymbol, received_at: std::time::Instant) -> Result<Vec<SourceSymbol>, DecoderError> {
-        let id = BigEndian::read_u64(&source_symbol.metadata[..]);
+    pub fn receive_source_symbol(&mut self, source_symbol: SourceSymbol, received_at: Timestamp) -> Result<Vec<SourceSymbol>, DecoderError> {
+        let recovered_ids = self.add_source_symbol(source_symbol.metadata, source_symbol.data, received_at)?;
+        Ok(self.recovered_symbols(recovered_ids))
+    }
+
+    ///
+    /// Copies the data into a pooled buffer as the codec keeps its own copy of the source symbols,
+    /// and returns the ids of the recovered symbols, whose data is given by recovered_data
+    pub fn receive_source_symbol_borrowed(&mut self, metadata: SourceSymbolMetadata, data: &[u8], received_at: Timestamp) -> Result<Vec<SymbolID>, DecoderError> {
+        let mut buffer = self.pool.get_mut().get(data.len());
+        buffer.copy_from_slice(data);
+        let recovered_ids = self.add_source_symbol(metadata, buffer, received_at)?;
+        Ok(self.recovered_ids(recovered_ids))
+    }
+
+    fn add_source_symbol(&mut self, metadata: SourceSymbolMetadata, data: Vec<u8>, received_at: Timestamp) -> Result<Vec<SymbolID>, DecoderError> {
+        if data.len() != self.symbol_size {
+            log::debug!("source symbol of {} bytes VS symbol size {}", data.len(), self.symbol_size);
+            return Err(DecoderError::BadMetadata);
+        }
+        self.stats.received_source_symbols += 1;
+        let id = BigEndian::read_u64(&metadata[..]);
         if let Some((first_id, _)) =  self.rust_vlc_decoder.bounds(){
             if id < first_id {
                 // the source symbol has already been received and removed
+                self.stats.unused_source_symbols += 1;
                 return Err(DecoderError::UnusedSourceSymbol);
             }
         }
-        let recovered_ids = self.rust_vlc_decoder.add_source_symbol(RustVLCSourceSymbol::new(id, source_symbol.data), received_at)?;
+        if let Err(err) = self.limits.check_source_symbol(id, self.rust_vlc_decoder.bounds()) {
+            self.stats.record_error(&err);
+            return Err(err);
+        }
+        self.rust_vlc_decoder.add_source_symbol(RustVLCSourceSymbol::new(id, data), received_at).map_err(|err| {
+            let err = DecoderError::from(err);
+            self.stats.record_error(&err);
+            err
+        })
+    }
+
+    /// Keeps the recovered symbols whose data is available and accounts for them
+    fn recovered_ids(&mut self, mut recovered_ids: Vec<SymbolID>) -> Vec<SymbolID> {
+        let decoder = &self.rust_vlc_decoder;
+        recovered_ids.retain(|id| {
+            let available = decoder.get_data(*id).is_some();
+            if !available {
+                log::error!("recovered symbol {} has no data", id);
+            }
+            available
+        });
+        for &id in &recovered_ids {
+            self.events.emit(|| FecEvent::SourceSymbolRecovered { id });
+        }
+        self.stats.recovered_source_symbols += recovered_ids.len() as u64;
+        recovered_ids
+    }
+
+    fn recovered_symbols(&mut self, recovered_ids: Vec<SymbolID>) -> Vec<SourceSymbol> {
+        let recovered_ids = self.recovered_ids(recovered_ids);
         let mut ret = Vec::with_capacity(recovered_ids.len());
         for id in recovered_ids {
-            ret.push(SourceSymbol{
-                metadata: source_symbol_metadata_from_u64(id),
-                data: self.rust_vlc_decoder.get_data(id).unwrap().clone(),
-            });
+            if let Some(data) = self.rust_vlc_decoder.get_data(id) {
+                let mut buffer = self.pool.get_mut().get(data.len());
+                buffer.copy_from_slice(data);
+                ret.push(SourceSymbol{
+                    metadata: source_symbol_metadata_from_u64(id),
+                    data: buffer,
+                });
+            }
         }
-        Ok(ret)
+        ret
+    }
+
+    pub fn recovered_data(&self, id: SymbolID) -> Option<&[u8]> {
+        self.rust_vlc_decoder.get_data(id).map(|data| data.as_slice())
     }
 
     pub fn read_repair_symbol(&self, data: &[u8]) -> Result<(usize, RepairSymbol), DecoderError> {
-        if data.len() < 8 + 8 + 4 + self.symbol_size {
-            println!("BUFFER TOO SMALL: {} VS {}", data.len(), 8 + 8 + 4 + self.symbol_size);
+        if self.tagged {
+            RepairSymbolTag::check(data, Scheme::Vlc)?;
+        }
+        let length = self.repair_symbol_len();
+        if data.len() < length {
+            log::debug!("repair symbol buffer too small: {} VS {}", data.len(), length);
             return Err(BufferTooSmall);
         }
-        let length = 8 + 8 + 4 + self.symbol_size;
-        Ok((length, RepairSymbol{ data: data[..length].to_vec() }))
+        let mut buffer = self.pool.borrow_mut().get(length);
+        buffer.copy_from_slice(&data[..length]);
+        Ok((length, RepairSymbol{ data: buffer }))
+    }
+
+    pub fn read_repair_symbol_header(&self, data: &[u8]) -> Result<RepairSymbolHeader, DecoderError> {
+        let mut consumed = 0;
+        if self.tagged {
+            RepairSymbolTag::check(data, Scheme::Vlc)?;
+            consumed += TAG_LEN;
+        }
+        if data.len() < consumed + 8 + 4 + 8 {
+            return Err(BufferTooSmall);
+        }
+        let first_id = BigEndian::read_u64(&data[consumed..]);
+        consumed += 8;
+        let n_protected_symbols = BigEndian::read_u32(&data[consumed..]);
+        consumed += 4;
+        let sequence_number = BigEndian::read_u64(&data[consumed..]);
+        consumed += 8;
+        Ok(RepairSymbolHeader {
+            first_id,
+            n_protected_symbols: n_protected_symbols as u64,
+            coefficients: RepairSymbolCoefficients::SequenceNumber(sequence_number),
+            header_len: consumed,
+        })
     }
 
     // returns (metadata_size, source_symbol)
     pub fn read_source_symbol_metadata(&self, data: &[u8]) -> Result<(usize, SourceSymbolMetadata), DecoderError> {
         if data.len() < 8 {
-            println!("BUFFER TOO SMALL: {} VS {}", data.len(), 8 + 1);
+            log::debug!("source symbol metadata buffer too small: {} VS {}", data.len(), 8);
             return Err(BufferTooSmall);
         }
         let id = BigEndian::read_u64(data);
@@ -74,38 +186,113 @@ impl VLCDecoder {
     }
 
     pub fn receive_and_deserialize_repair_symbol(&mut self, repair_symbol: RepairSymbol) -> Result<(usize, Vec<SourceSymbol>), DecoderError> {
-        let data = repair_symbol.data;
-        if data.len() < 8 + 8 + 4 + self.symbol_size {
+        let mut data = repair_symbol.data;
+        let header = self.check_repair_symbol(&data)?;
+        // the payload is moved to the front of the buffer instead of being copied into a new one
+        data.copy_within(header.header_len..header.header_len+self.symbol_size, 0);
+        data.truncate(self.symbol_size);
+        let recovered_ids = self.add_repair_symbol(&header, data)?;
+        Ok((header.header_len + self.symbol_size, self.recovered_symbols(recovered_ids)))
+    }
+
+    ///
+    /// Receives a serialized repair symbol without going through read_repair_symbol,
+    /// and returns the ids of the recovered symbols, whose data is given by recovered_data
+    pub fn receive_repair_symbol_borrowed(&mut self, data: &[u8]) -> Result<(usize, Vec<SymbolID>), DecoderError> {
+        let header = self.check_repair_symbol(data)?;
+        let mut symbol_data = self.pool.get_mut().get(self.symbol_size);
+        symbol_data.copy_from_slice(&data[header.header_len..header.header_len+self.symbol_size]);
+        let recovered_ids = self.add_repair_symbol(&header, symbol_data)?;
+        Ok((header.header_len + self.symbol_size, self.recovered_ids(recovered_ids)))
+    }
+
+    /// Parses the header of a serialized repair symbol and checks it against the limits
+    fn check_repair_symbol(&mut self, data: &[u8]) -> Result<RepairSymbolHeader, DecoderError> {
+        if data.len() < self.repair_symbol_len() {
             return Err(BufferTooSmall);
         }
+        self.stats.received_repair_symbols += 1;
+        let header = self.read_repair_symbol_header(data).inspect_err(|err| self.stats.record_error(err))?;
+        if let Err(err) = header.validate().and_then(|_| self.limits.check_repair_symbol(&header, self.rust_vlc_decoder.bounds())) {
+            self.stats.record_error(&err);
+            return Err(err);
+        }
+        Ok(header)
+    }
 
-        let mut consumed = 0;
-        let first_id = BigEndian::read_u64(&data[consumed..]);
-        consumed += 8;
-        let n_protected_symbols = BigEndian::read_u32(&data[consumed..]);
-        consumed += 4;
-        let sequence_number = BigEndian::read_u64(&data[consumed..]);
-        consumed += 8;
-        let mut symbol_data = vec![0; self.symbol_size];
-        symbol_data.clone_from_slice(&data[consumed..consumed+self.symbol_size]);
-        consumed += self.symbol_size;
-        let recovered_ids = self.rust_vlc_decoder.add_repair_symbol(RustVLCRepairSymbol::new(first_id, sequence_number, n_protected_symbols as u64, symbol_data))?; 
-        let mut ret = Vec::with_capacity(recovered_ids.len());
-        for id in recovered_ids {
-            ret.push(SourceSymbol{
-                metadata: source_symbol_metadata_from_u64(id),
-                data: self.rust_vlc_decoder.get_data(id).unwrap().clone(),
-            });
+    /// Hands the repair symbol to the codec, or queues it behind the pending ones if a work budget is set
+    fn add_repair_symbol(&mut self, header: &RepairSymbolHeader, symbol_data: Vec<u8>) -> Result<Vec<SymbolID>, DecoderError> {
+        if self.repair_queue.budget.is_none() && self.repair_queue.len() == 0 {
+            let recovered_ids = self.eliminate_repair_symbol(header, symbol_data)?;
+            self.limits.repair_symbol_accepted(header);
+            return Ok(recovered_ids);
+        }
+        self.limits.repair_symbol_accepted(header);
+        self.repair_queue.push(*header, symbol_data);
+        Ok(self.run_repair_queue())
+    }
+
+    /// Hands the queued repair symbols fitting in the work budget to the codec
+    fn run_repair_queue(&mut self) -> Vec<SymbolID> {
+        let mut recovered_ids = Vec::new();
+        for (header, symbol_data) in self.repair_queue.pop_within_budget() {
+            match self.eliminate_repair_symbol(&header, symbol_data) {
+                Ok(ids) => recovered_ids.extend(ids),
+                Err(err) => {
+                    log::debug!("queued repair symbol protecting [{}, {}] not used: {:?}", header.first_id, header.first_id + (header.n_protected_symbols - 1), err);
+                    self.stats.record_queued_error(&err);
+                }
+            }
+        }
+        recovered_ids
+    }
+
+    fn eliminate_repair_symbol(&mut self, header: &RepairSymbolHeader, symbol_data: Vec<u8>) -> Result<Vec<SymbolID>, DecoderError> {
+        let sequence_number = match header.coefficients {
+            RepairSymbolCoefficients::SequenceNumber(sequence_number) => sequence_number,
+            RepairSymbolCoefficients::Seed(_) => return Err(DecoderError::BadMetadata),
+        };
+        let copy = self.journal.as_ref().map(|_| symbol_data.clone());
+        match self.rust_vlc_decoder.add_repair_symbol(RustVLCRepairSymbol::new(header.first_id, sequence_number, header.n_protected_symbols, symbol_data)) {
+            Ok(recovered_ids) => {
+                if let (Some(journal), Some(data)) = (self.journal.as_mut(), copy) {
+                    journal.push(header.first_id + (header.n_protected_symbols - 1), (*header, data));
+                }
+                Ok(recovered_ids)
+            }
+            Err(err) => {
+                let err = DecoderError::from(err);
+                self.stats.record_error(&err);
+                Err(err)
+            }
         }
-        Ok((consumed, ret))
     }
 
     pub fn symbol_size(&self) -> usize {
         self.symbol_size
     }
 
-    pub fn remove_up_to(&mut self, md: SourceSymbolMetadata, expired_at: Option<std::time::Instant>) -> SourceSymbolMetadata {
-        source_symbol_metadata_from_u64(self.rust_vlc_decoder.remove_up_to(source_symbol_metadata_to_u64(md) as SymbolID, expired_at))
+    fn repair_symbol_len(&self) -> usize {
+        let tag_len = if self.tagged { TAG_LEN } else { 0 };
+        tag_len + 8 + 8 + 4 + self.symbol_size
+    }
+
+    pub fn remove_up_to(&mut self, md: SourceSymbolMetadata, expired_at: Option<Timestamp>) -> SourceSymbolMetadata {
+        let before = self.bounds();
+        let ret = source_symbol_metadata_from_u64(self.rust_vlc_decoder.remove_up_to(source_symbol_metadata_to_u64(md) as SymbolID, expired_at));
+        self.stats.record_expiration(before, self.bounds());
+        self.limits.window_slid(source_symbol_metadata_to_u64(md), self.bounds());
+        let first_id = self.rust_vlc_decoder.bounds().map(|(first, _)| first);
+        let dropped = self.repair_queue.window_slid(first_id);
+        if let Some(journal) = self.journal.as_mut() {
+            journal.window_slid(first_id);
+        }
+        self.stats.unused_repair_symbols += dropped as u64;
+        let bounds = self.rust_vlc_decoder.bounds();
+        if self.bounds() != before {
+            self.events.emit(|| FecEvent::WindowSlid { role: Role::Decoder, bounds });
+        }
+        ret
     }
 
     pub fn bounds(&self) -> Option<(SourceSymbolMetadata, SourceSymbolMetadata)> {
@@ -115,4 +302,108 @@ impl VLCDecoder {
     pub fn largest_contiguously_received(&self) -> Option<SourceSymbolMetadata> {
         self.rust_vlc_decoder.largest_contiguously_received_id().map(|md| source_symbol_metadata_from_u64(md))
     }
-}
\ No newline at end of file
+
+    pub fn stats(&self) -> DecoderStats {
+        DecoderStats {
+            current_window_size: self.rust_vlc_decoder.bounds().map_or(0, |(first, last)| last + 1 - first),
+            ..self.stats
+        }
+    }
+
+    pub fn set_event_sink(&mut self, sink: Option<Box<dyn EventSink>>) {
+        self.events.set_sink(sink);
+    }
+
+    pub fn take_buffer(&mut self) -> Vec<u8> {
+        self.pool.get_mut().get_empty()
+    }
+
+    pub fn recycle(&mut self, buffer: Vec<u8>) {
+        self.pool.get_mut().recycle(buffer);
+    }
+
+    pub fn poll_recovery(&mut self) -> Vec<SourceSymbol> {
+        let recovered_ids = self.run_repair_queue();
+        self.recovered_symbols(recovered_ids)
+    }
+
+    pub fn pending_repair_symbols(&self) -> usize {
+        self.repair_queue.len()
+    }
+
+    pub fn work_budget(&self) -> Option<u64> {
+        self.repair_queue.budget
+    }
+
+    pub fn set_work_budget(&mut self, budget: Option<u64>) {
+        self.repair_queue.budget = budget;
+    }
+
+    pub fn limits(&self) -> DecoderLimits {
+        self.limits.limits
+    }
+
+    pub fn set_limits(&mut self, limits: DecoderLimits) {
+        self.limits.limits = limits;
+    }
+
+    pub fn set_repair_symbol_tag(&mut self, enabled: bool) {
+        self.tagged = enabled;
+    }
+
+    pub fn set_snapshots(&mut self, enabled: bool) {
+        if !enabled {
+            self.journal = None;
+        } else if self.journal.is_none() {
+            self.journal = Some(Journal::new());
+        }
+    }
+
+    pub fn snapshot(&self) -> Result<DecoderSnapshot, SnapshotError> {
+        let journal = self.journal.as_ref().ok_or(SnapshotError::NotEnabled)?;
+        let source_symbols = match self.rust_vlc_decoder.bounds() {
+            Some((first, last)) => (first..=last).filter_map(|id| self.rust_vlc_decoder.get_data(id).map(|data| (id, data.clone()))).collect(),
+            None => Vec::new(),
+        };
+        Ok(DecoderSnapshot {
+            codec: Codec::VLC,
+            symbol_size: self.symbol_size,
+            window_size: self.max_window_size,
+            stats: self.stats(),
+            limits: self.limits.limits,
+            window_start: self.limits.window_start,
+            work_budget: self.repair_queue.budget,
+            source_symbols,
+            repair_symbols: journal.iter().cloned().collect(),
+            queued_repair_symbols: self.repair_queue.iter().cloned().collect(),
+        })
+    }
+
+    ///
+    /// Replays the snapshot into a new decoder. The source symbols are received at received_at
+    pub fn restore(snapshot: &DecoderSnapshot, received_at: Timestamp) -> Result<VLCDecoder, SnapshotError> {
+        let mut decoder = VLCDecoder::new(snapshot.symbol_size, snapshot.window_size);
+        decoder.limits.limits = snapshot.limits;
+        decoder.limits.window_start = snapshot.window_start;
+        decoder.repair_queue.budget = snapshot.work_budget;
+        decoder.journal = Some(Journal::new());
+        for (id, data) in &snapshot.source_symbols {
+            if let Err(err) = decoder.rust_vlc_decoder.add_source_symbol(RustVLCSourceSymbol::new(*id, data.clone()), received_at) {
+                log::debug!("source symbol {} not restored: {:?}", id, err);
+            }
+        }
+        for (header, data) in &snapshot.repair_symbols {
+            // the symbols recovered by the replayed equations were already part of the snapshot
+            if let Err(err) = decoder.eliminate_repair_symbol(header, data.clone()) {
+                log::debug!("repair symbol protecting [{}, {}] not restored: {:?}", header.first_id, header.first_id + (header.n_protected_symbols - 1), err);
+            }
+            decoder.limits.repair_symbol_accepted(header);
+        }
+        for (header, data) in &snapshot.queued_repair_symbols {
+            decoder.limits.repair_symbol_accepted(header);
+            decoder.repair_queue.push(*header, data.clone());
+        }
+        decoder.stats = snapshot.stats;
+        Ok(decoder)
+    }
+}
@@ -4,12 +4,22 @@ use rustgf::system::equation::EquationBounds;
 use vandermonde_lc::SymbolID;
 use vandermonde_lc::encoder::Encoder as RustVLCEncoder;
 
-use crate::{EncoderError, SourceSymbolMetadata, source_symbol_metadata_to_u64, source_symbol_metadata_from_u64};
+use crate::{EncoderError, EncoderStats, SourceSymbolMetadata, source_symbol_metadata_to_u64, source_symbol_metadata_from_u64, Timestamp};
 use crate::EncoderError::{BadMetadata, BufferTooSmall};
+use crate::events::{EventEmitter, EventSink, FecEvent, Role};
+use crate::pool::BufferPool;
+use crate::simulator::Codec;
+use crate::snapshot::{EncoderSnapshot, SnapshotError};
+use crate::config::Scheme;
+use crate::tag::{RepairSymbolTag, TAG_LEN};
 
 pub struct VLCEncoder {
     rust_vlc_encoder: RustVLCEncoder,
     symbol_size: usize,
+    stats: EncoderStats,
+    events: EventEmitter,
+    pool: BufferPool,
+    tagged: bool,
 }
 
 impl VLCEncoder {
@@ -17,6 +27,10 @@ impl VLCEncoder {
         VLCEncoder{
             rust_vlc_encoder: RustVLCEncoder::new(max_window_size, symbol_size, Some(galois_2p8::PrimitivePolynomialField::new(galois_2p8::IrreducablePolynomial::Poly84320).unwrap())),
             symbol_size,
+            stats: EncoderStats::default(),
+            events: EventEmitter::default(),
+            pool: BufferPool::new(symbol_size + TAG_LEN + 8 + 8 + 4, max_window_size),
+            tagged: false,
         }
     }
 
@@ -24,21 +38,30 @@ impl VLCEncoder {
         if output.len() < 8 {
             return Err(BufferTooSmall);
         }
+        let len = data.len();
         match self.rust_vlc_encoder.protect_data(data) {
             Err(err) => {
                 Err(EncoderError::InternalError(format!("{:?}", err)))
             }
             Ok(id) => {
                 BigEndian::write_u64(output, id);
+                self.stats.protected_source_symbols += 1;
+                self.events.emit(|| FecEvent::SourceSymbolProtected { id, len });
                 Ok(8)
             }
         }
     }
 
     pub fn generate_and_serialize_repair_symbol_up_to(&mut self, up_to: SourceSymbolMetadata) -> Result<Vec<u8>, EncoderError> {
-        let serialized_size = self.symbol_size + 8 + 8 + 4;
-        let mut out = vec![0; serialized_size];
-        let written = self.generate_and_serialize_repair_symbol_in_place_up_to(out.as_mut_slice(), up_to)?;
+        let serialized_size = self.repair_symbol_len();
+        let mut out = self.pool.get(serialized_size);
+        let written = match self.generate_and_serialize_repair_symbol_in_place_up_to(out.as_mut_slice(), up_to) {
+            Ok(written) => written,
+            Err(err) => {
+                self.pool.recycle(out);
+                return Err(err);
+            }
+        };
         if serialized_size != written {
             Err(EncoderError::InternalError("the serialized size was not equal to prediction".to_string()))
         } else {
@@ -48,16 +71,16 @@ impl VLCEncoder {
 
 
     pub fn generate_and_serialize_repair_symbol(&mut self) -> Result<Vec<u8>, EncoderError> {
-        if let Some(range) = self.rust_vlc_encoder.range() {
-            self.generate_and_serialize_repair_symbol_up_to(source_symbol_metadata_from_u64(*range.end()))
+        if let Some(last) = self.last_metadata() {
+            self.generate_and_serialize_repair_symbol_up_to(last)
         } else {
             Err(EncoderError::NoSymbolToGenerate)
         }
     }
 
     pub fn generate_and_serialize_repair_symbol_in_place(&mut self, output: &mut [u8]) -> Result<usize, EncoderError> {
-        if let Some(range) = self.rust_vlc_encoder.range() {
-            self.generate_and_serialize_repair_symbol_in_place_up_to(output, source_symbol_metadata_from_u64(*range.end()))
+        if let Some(last) = self.last_metadata() {
+            self.generate_and_serialize_repair_symbol_in_place_up_to(output, last)
         } else {
             Err(EncoderError::NoSymbolToGenerate)
         }
@@ -65,7 +88,7 @@ impl VLCEncoder {
 
 
     pub fn generate_and_serialize_repair_symbol_in_place_up_to(&mut self, output: &mut [u8], up_to: SourceSymbolMetadata) -> Result<usize, EncoderError> {
-        if output.len() < 8 + 8 + 4 + self.symbol_size {
+        if output.len() < self.repair_symbol_len() {
             return Err(BufferTooSmall);
         }
         let up_to = source_symbol_metadata_to_u64(up_to);
@@ -82,6 +105,10 @@ impl VLCEncoder {
                         pivot, last_nonzero_id
                     } => {
                         let mut written = 0;
+                        if self.tagged {
+                            RepairSymbolTag::new(Scheme::Vlc).write(output);
+                            written += TAG_LEN;
+                        }
                         let (pivot, last_nonzero_id) = (*pivot, *last_nonzero_id);
                         BigEndian::write_u64(&mut output[written..], pivot);
                         written += 8;
@@ -94,6 +121,8 @@ impl VLCEncoder {
                         let len = self.symbol_size;
                         output[written..written+len].clone_from_slice(data.as_slice());
                         written += len;
+                        self.stats.generated_repair_symbols += 1;
+                        self.events.emit(|| FecEvent::RepairSymbolSent { first_id: pivot, last_id: last_nonzero_id, len: written });
                         Ok(written)
                     }
                     EquationBounds::EmptyBounds => {
@@ -109,6 +138,7 @@ impl VLCEncoder {
             return Err(BadMetadata);
         }
         self.rust_vlc_encoder.received_symbol(BigEndian::read_u64(metadata));
+        self.stats.acknowledged_source_symbols += 1;
         Ok(8)
     }
 
@@ -121,7 +151,9 @@ impl VLCEncoder {
     }
 
     pub fn remove_up_to(&mut self, md: SourceSymbolMetadata) {
+        let before = self.window_bounds();
         self.rust_vlc_encoder.remove_up_to(source_symbol_metadata_to_u64(md) as SymbolID);
+        self.window_slid(before);
     }
 
     pub fn next_metadata(&mut self) -> SourceSymbolMetadata {
@@ -129,7 +161,12 @@ impl VLCEncoder {
     }
 
     pub fn next_repair_symbol_size(&self, _up_to: SourceSymbolMetadata) -> usize {
-        self.symbol_size + 8 + 8 + 4
+        self.repair_symbol_len()
+    }
+
+    fn repair_symbol_len(&self) -> usize {
+        let tag_len = if self.tagged { TAG_LEN } else { 0 };
+        tag_len + 8 + 8 + 4 + self.symbol_size
     }   
     
     pub fn first_metadata(&self) -> Option<SourceSymbolMetadata> {
@@ -154,17 +191,66 @@ impl VLCEncoder {
         }
     }
 
-    pub fn get_sent_time(&self, md: SourceSymbolMetadata) -> Option<std::time::Instant> {
+    pub fn get_sent_time(&self, md: SourceSymbolMetadata) -> Option<Timestamp> {
         self.rust_vlc_encoder.get_sent_time(source_symbol_metadata_to_u64(md))
     }
 
     
     // either received or lost
     pub fn symbol_landed(&mut self, md: SourceSymbolMetadata) {
-        self.rust_vlc_encoder.symbol_landed(source_symbol_metadata_to_u64(md))
+        self.rust_vlc_encoder.symbol_landed(source_symbol_metadata_to_u64(md));
     }
 
     pub fn remove_landed_symbols(&mut self) {
-        self.rust_vlc_encoder.remove_landed_symbols()
+        let before = self.window_bounds();
+        self.rust_vlc_encoder.remove_landed_symbols();
+        self.window_slid(before);
+    }
+
+    fn window_bounds(&self) -> Option<(u64, u64)> {
+        self.rust_vlc_encoder.range().map(|range| (*range.start(), *range.end()))
+    }
+
+    fn window_slid(&mut self, before: Option<(u64, u64)>) {
+        let bounds = self.window_bounds();
+        if bounds != before {
+            self.events.emit(|| FecEvent::WindowSlid { role: Role::Encoder, bounds });
+        }
+    }
+
+    pub fn stats(&self) -> EncoderStats {
+        EncoderStats {
+            current_window_size: self.current_window_size() as u64,
+            ..self.stats
+        }
+    }
+
+    pub fn set_event_sink(&mut self, sink: Option<Box<dyn EventSink>>) {
+        self.events.set_sink(sink);
+    }
+
+    pub fn take_buffer(&mut self) -> Vec<u8> {
+        self.pool.get_empty()
+    }
+
+    pub fn recycle(&mut self, buffer: Vec<u8>) {
+        self.pool.recycle(buffer);
+    }
+
+    pub fn set_repair_symbol_tag(&mut self, enabled: bool) {
+        self.tagged = enabled;
     }
-}
\ No newline at end of file
+
+    ///
+    /// vandermonde_lc gives no way to set its sequence numbers, so a restored encoder would send repair symbols
+    /// with the sequence numbers of the ones sent before the snapshot. Nothing is kept for snapshots
+    pub fn set_snapshots(&mut self, _enabled: bool) {}
+
+    pub fn snapshot(&self) -> Result<EncoderSnapshot, SnapshotError> {
+        Err(SnapshotError::NotRestorable(Codec::VLC))
+    }
+
+    pub fn restore(_snapshot: &EncoderSnapshot) -> Result<VLCEncoder, SnapshotError> {
+        Err(SnapshotError::NotRestorable(Codec::VLC))
+    }
+}
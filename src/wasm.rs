@@ -0,0 +1,223 @@
+use std::time::{Duration, Instant};
+
+use wasm_bindgen::prelude::*;
+
+use crate::vandermonde_lc::decoder::VLCDecoder;
+use crate::vandermonde_lc::encoder::VLCEncoder;
+#[cfg(feature = "enable-rlc")]
+use crate::rlc::decoder::RLCDecoder;
+#[cfg(feature = "enable-rlc")]
+use crate::rlc::encoder::RLCEncoder;
+use crate::{Decoder, DecoderError, Encoder, EncoderError, SourceSymbol, SourceSymbolMetadata, source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+fn encoder_error(err: EncoderError) -> JsError {
+    JsError::new(&format!("encoder error: {:?}", err))
+}
+
+fn decoder_error(err: DecoderError) -> JsError {
+    JsError::new(&format!("decoder error: {:?}", err))
+}
+
+fn metadata_from_slice(md: &[u8]) -> Result<SourceSymbolMetadata, JsError> {
+    let mut ret = [0; 8];
+    if md.len() != ret.len() {
+        return Err(JsError::new(&format!("bad metadata: expected {} bytes, got {}", ret.len(), md.len())));
+    }
+    ret.copy_from_slice(md);
+    Ok(ret)
+}
+
+/// Instant onto which the timestamps given by JavaScript are mapped, taken when the decoder is created.
+/// std has no clock on wasm32-unknown-unknown, where Instant::now panics, and vandermonde_lc and rustrlc
+/// take Instants, so decoders cannot be created on that target
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn clock_reference() -> Result<Instant, JsError> {
+    Ok(Instant::now())
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn clock_reference() -> Result<Instant, JsError> {
+    Err(JsError::new("decoders need std::time::Instant, which wasm32-unknown-unknown does not provide"))
+}
+
+/// Timestamps are given by the caller as milliseconds since the creation of the decoder
+/// and mapped onto the Instant taken at that time
+fn instant_from_millis(reference: Instant, ms: f64) -> Instant {
+    let ms = if ms.is_finite() { ms.max(0.0) } else { 0.0 };
+    reference + Duration::from_millis(ms as u64)
+}
+
+#[wasm_bindgen(js_name = SourceSymbol)]
+pub struct WasmSourceSymbol {
+    inner: SourceSymbol,
+}
+
+#[wasm_bindgen(js_class = SourceSymbol)]
+impl WasmSourceSymbol {
+    #[wasm_bindgen(constructor)]
+    pub fn new(metadata: &[u8], data: &[u8]) -> Result<WasmSourceSymbol, JsError> {
+        Ok(WasmSourceSymbol {
+            inner: SourceSymbol::new(metadata_from_slice(metadata)?, data.to_vec()),
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn metadata(&self) -> Vec<u8> {
+        self.inner.metadata().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.inner.get().clone()
+    }
+}
+
+fn wrap_source_symbols(symbols: Vec<SourceSymbol>) -> Vec<WasmSourceSymbol> {
+    symbols.into_iter().map(|inner| WasmSourceSymbol { inner }).collect()
+}
+
+#[wasm_bindgen(js_name = Encoder)]
+pub struct WasmEncoder {
+    inner: Encoder,
+}
+
+#[wasm_bindgen(js_class = Encoder)]
+impl WasmEncoder {
+    pub fn vlc(symbol_size: usize, window_size: usize) -> WasmEncoder {
+        WasmEncoder {
+            inner: Encoder::VLC(VLCEncoder::new(symbol_size, window_size)),
+        }
+    }
+
+    #[cfg(feature = "enable-rlc")]
+    pub fn rlc(symbol_size: usize, window_size: usize, seed: u32) -> WasmEncoder {
+        WasmEncoder {
+            inner: Encoder::RLC(RLCEncoder::new(symbol_size, window_size, seed)),
+        }
+    }
+
+    ///
+    /// Protects the given data and returns its serialized metadata
+    #[wasm_bindgen(js_name = protectData)]
+    pub fn protect_data(&mut self, data: &[u8]) -> Result<Vec<u8>, JsError> {
+        let mut md = source_symbol_metadata_from_u64(0);
+        self.inner.protect_data(data.to_vec(), &mut md).map_err(encoder_error)?;
+        Ok(md.to_vec())
+    }
+
+    ///
+    /// Generates a new serialized repair symbol protecting the whole window
+    #[wasm_bindgen(js_name = generateRepairSymbol)]
+    pub fn generate_repair_symbol(&mut self) -> Result<Vec<u8>, JsError> {
+        Ok(self.inner.generate_and_serialize_repair_symbol().map_err(encoder_error)?.take())
+    }
+
+    ///
+    /// Generates a new serialized repair symbol protecting the window up to the given metadata
+    #[wasm_bindgen(js_name = generateRepairSymbolUpTo)]
+    pub fn generate_repair_symbol_up_to(&mut self, up_to: &[u8]) -> Result<Vec<u8>, JsError> {
+        let up_to = metadata_from_slice(up_to)?;
+        Ok(self.inner.generate_and_serialize_repair_symbol_up_to(up_to).map_err(encoder_error)?.take())
+    }
+
+    #[wasm_bindgen(js_name = receivedSymbol)]
+    pub fn received_symbol(&mut self, metadata: &[u8]) -> Result<usize, JsError> {
+        self.inner.received_symbol(metadata).map_err(encoder_error)
+    }
+
+    #[wasm_bindgen(getter, js_name = symbolSize)]
+    pub fn symbol_size(&self) -> usize {
+        self.inner.symbol_size()
+    }
+
+    #[wasm_bindgen(js_name = canSendRepairSymbols)]
+    pub fn can_send_repair_symbols(&self) -> bool {
+        self.inner.can_send_repair_symbols()
+    }
+
+    #[wasm_bindgen(js_name = removeUpTo)]
+    pub fn remove_up_to(&mut self, metadata: &[u8]) -> Result<(), JsError> {
+        self.inner.remove_up_to(metadata_from_slice(metadata)?);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = nProtectedSymbols)]
+    pub fn n_protected_symbols(&self) -> usize {
+        self.inner.n_protected_symbols()
+    }
+}
+
+#[wasm_bindgen(js_name = Decoder)]
+pub struct WasmDecoder {
+    inner: Decoder,
+    created_at: Instant,
+}
+
+#[wasm_bindgen(js_class = Decoder)]
+impl WasmDecoder {
+    pub fn vlc(symbol_size: usize, window_size: usize) -> Result<WasmDecoder, JsError> {
+        Ok(WasmDecoder {
+            created_at: clock_reference()?,
+            inner: Decoder::VLC(VLCDecoder::new(symbol_size, window_size)),
+        })
+    }
+
+    #[cfg(feature = "enable-rlc")]
+    pub fn rlc(symbol_size: usize, window_size: usize) -> Result<WasmDecoder, JsError> {
+        Ok(WasmDecoder {
+            created_at: clock_reference()?,
+            inner: Decoder::RLC(RLCDecoder::new(symbol_size, window_size)),
+        })
+    }
+
+    ///
+    /// Feeds a source symbol received at the given time (in milliseconds since the creation of the decoder) and returns the recovered source symbols
+    #[wasm_bindgen(js_name = receiveSourceSymbol)]
+    pub fn receive_source_symbol(&mut self, metadata: &[u8], data: &[u8], received_at_ms: f64) -> Result<Vec<WasmSourceSymbol>, JsError> {
+        let source_symbol = SourceSymbol::new(metadata_from_slice(metadata)?, data.to_vec());
+        let recovered = self.inner.receive_source_symbol(source_symbol, instant_from_millis(self.created_at, received_at_ms)).map_err(decoder_error)?;
+        Ok(wrap_source_symbols(recovered))
+    }
+
+    ///
+    /// Parses the repair symbol at the start of data, feeds it to the decoder and returns the recovered source symbols
+    #[wasm_bindgen(js_name = receiveRepairSymbol)]
+    pub fn receive_repair_symbol(&mut self, data: &[u8]) -> Result<Vec<WasmSourceSymbol>, JsError> {
+        let (_, repair_symbol) = self.inner.read_repair_symbol(data).map_err(decoder_error)?;
+        let (_, recovered) = self.inner.receive_and_deserialize_repair_symbol(repair_symbol).map_err(decoder_error)?;
+        Ok(wrap_source_symbols(recovered))
+    }
+
+    ///
+    /// Returns the length of the repair symbol at the start of data
+    #[wasm_bindgen(js_name = repairSymbolLength)]
+    pub fn repair_symbol_length(&self, data: &[u8]) -> Result<usize, JsError> {
+        self.inner.read_repair_symbol(data).map(|(consumed, _)| consumed).map_err(decoder_error)
+    }
+
+    #[wasm_bindgen(js_name = readSourceSymbolMetadata)]
+    pub fn read_source_symbol_metadata(&self, data: &[u8]) -> Result<Vec<u8>, JsError> {
+        self.inner.read_source_symbol_metadata(data).map(|(_, md)| md.to_vec()).map_err(decoder_error)
+    }
+
+    #[wasm_bindgen(getter, js_name = symbolSize)]
+    pub fn symbol_size(&self) -> usize {
+        self.inner.symbol_size()
+    }
+
+    #[wasm_bindgen(js_name = removeUpTo)]
+    pub fn remove_up_to(&mut self, metadata: &[u8], expired_at_ms: f64) -> Result<Vec<u8>, JsError> {
+        let md = self.inner.remove_up_to(metadata_from_slice(metadata)?, Some(instant_from_millis(self.created_at, expired_at_ms)));
+        Ok(md.to_vec())
+    }
+}
+
+#[wasm_bindgen(js_name = metadataFromU64)]
+pub fn metadata_from_u64(n: u64) -> Vec<u8> {
+    source_symbol_metadata_from_u64(n).to_vec()
+}
+
+#[wasm_bindgen(js_name = metadataToU64)]
+pub fn metadata_to_u64(metadata: &[u8]) -> Result<u64, JsError> {
+    Ok(source_symbol_metadata_to_u64(metadata_from_slice(metadata)?))
+}
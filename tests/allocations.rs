@@ -0,0 +1,112 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use networkcoding::pool::BufferPool;
+use networkcoding::simulator::Codec;
+use networkcoding::source_symbol_metadata_from_u64;
+
+/// Counts the allocations of the current thread, so that the tests can run in parallel
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations<T>(f: impl FnOnce() -> T) -> (usize, T) {
+    let before = ALLOCATIONS.with(|n| n.get());
+    let ret = f();
+    (ALLOCATIONS.with(|n| n.get()) - before, ret)
+}
+
+const SYMBOL_SIZE: usize = 1200;
+const WINDOW_SIZE: usize = 16;
+
+fn codecs() -> Vec<Codec> {
+    vec![
+        Codec::VLC,
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { seed: 42 },
+    ]
+}
+
+#[test]
+fn pool_reuses_buffers() {
+    let mut pool = BufferPool::new(SYMBOL_SIZE, 4);
+    let buffers: Vec<Vec<u8>> = (0..4).map(|_| pool.get(SYMBOL_SIZE)).collect();
+    buffers.into_iter().for_each(|buffer| pool.recycle(buffer));
+    let (n, _) = allocations(|| {
+        for _ in 0..100 {
+            let buffer = pool.get(SYMBOL_SIZE);
+            let empty = pool.get_empty();
+            pool.recycle(buffer);
+            pool.recycle(empty);
+        }
+    });
+    assert_eq!(n, 0);
+}
+
+#[test]
+fn pool_allocates_lazily() {
+    let (n, mut pool) = allocations(|| BufferPool::new(SYMBOL_SIZE, usize::MAX));
+    assert_eq!(n, 0);
+    assert!(pool.is_empty());
+    let buffer = pool.get(SYMBOL_SIZE);
+    pool.recycle(buffer);
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn read_repair_symbol_reuses_recycled_buffers() {
+    for codec in codecs() {
+        let mut decoder = codec.new_decoder(SYMBOL_SIZE, WINDOW_SIZE);
+        let wire = vec![0; SYMBOL_SIZE + 8 + 8 + 4];
+        let (_, repair_symbol) = decoder.read_repair_symbol(&wire).unwrap();
+        decoder.recycle(repair_symbol.take());
+        let (n, _) = allocations(|| {
+            for _ in 0..100 {
+                let (_, repair_symbol) = decoder.read_repair_symbol(&wire).unwrap();
+                decoder.recycle(repair_symbol.take());
+            }
+        });
+        assert_eq!(n, 0, "{:?}", codec);
+    }
+}
+
+/// The codecs allocate internally to compute the repair symbols, the wrapper must not add anything on top of them
+#[test]
+fn repair_symbols_only_allocate_in_the_codec() {
+    for codec in codecs() {
+        let mut encoder = codec.new_encoder(SYMBOL_SIZE, WINDOW_SIZE);
+        for _ in 0..WINDOW_SIZE {
+            let mut data = encoder.take_buffer();
+            data.resize(SYMBOL_SIZE, 42);
+            encoder.protect_data(data, &mut source_symbol_metadata_from_u64(0)).unwrap();
+        }
+        let mut out = vec![0; SYMBOL_SIZE + 8 + 8 + 4];
+        let (in_place, _) = allocations(|| encoder.generate_and_serialize_repair_symbol_in_place(&mut out).unwrap());
+        let repair_symbol = encoder.generate_and_serialize_repair_symbol().unwrap();
+        encoder.recycle(repair_symbol.take());
+        let (pooled, repair_symbol) = allocations(|| encoder.generate_and_serialize_repair_symbol().unwrap());
+        assert_eq!(pooled, in_place, "{:?}", codec);
+        encoder.recycle(repair_symbol.take());
+    }
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "cli")]
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn netcode(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_netcode")).args(args).output().unwrap()
+}
+
+fn tmp_file(name: &str, content: &[u8]) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+/// The command exits with the given code and error message instead of panicking
+fn assert_fails_with(code: i32, args: &[&str], message: &str) {
+    let output = netcode(args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(code), "{:?}: {}", args, stderr);
+    assert!(!stderr.contains("panicked"), "{:?}: {}", args, stderr);
+    assert!(stderr.contains(message), "{:?}: {}", args, stderr);
+}
+
+fn assert_fails(args: &[&str], message: &str) {
+    assert_fails_with(1, args, message)
+}
+
+/// Clap rejects the arguments
+fn assert_usage_error(args: &[&str], message: &str) {
+    assert_fails_with(2, args, message)
+}
+
+/// Packet stream header of the encode command
+fn stream_header(symbol_size: u32, window_size: u32, file_len: u64) -> Vec<u8> {
+    let mut header = b"NCPK".to_vec();
+    header.extend_from_slice(&[1, 0]);
+    header.extend_from_slice(&42u32.to_be_bytes());
+    header.extend_from_slice(&symbol_size.to_be_bytes());
+    header.extend_from_slice(&window_size.to_be_bytes());
+    header.extend_from_slice(&0u64.to_be_bytes());
+    header.extend_from_slice(&file_len.to_be_bytes());
+    header
+}
+
+/// Classic pcap capture of raw IPv4 packets, each carrying a UDP datagram from port 1234 to dst_port
+fn udp_capture(dst_port: u16, payloads: &[&[u8]]) -> Vec<u8> {
+    let mut capture = Vec::new();
+    capture.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    capture.extend_from_slice(&2u16.to_le_bytes());
+    capture.extend_from_slice(&4u16.to_le_bytes());
+    capture.extend_from_slice(&[0; 8]);
+    capture.extend_from_slice(&65535u32.to_le_bytes());
+    // LINKTYPE_RAW
+    capture.extend_from_slice(&101u32.to_le_bytes());
+    for (i, payload) in payloads.iter().enumerate() {
+        let udp_len = 8 + payload.len();
+        let mut frame = vec![0x45, 0];
+        frame.extend_from_slice(&(20 + udp_len as u16).to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 0, 64, 17, 0, 0, 127, 0, 0, 1, 127, 0, 0, 1]);
+        frame.extend_from_slice(&1234u16.to_be_bytes());
+        frame.extend_from_slice(&dst_port.to_be_bytes());
+        frame.extend_from_slice(&(udp_len as u16).to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(payload);
+        capture.extend_from_slice(&(i as u32).to_le_bytes());
+        capture.extend_from_slice(&0u32.to_le_bytes());
+        capture.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        capture.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        capture.extend_from_slice(&frame);
+    }
+    capture
+}
+
+#[test]
+fn simulate_rejects_invalid_probabilities() {
+    assert_fails(&["simulate", "--symbols", "10", "--loss", "bernoulli:1.5"], "invalid probability");
+    assert_fails(&["simulate", "--symbols", "10", "--loss", "bernoulli:-0.1"], "invalid probability");
+    assert_fails(&["simulate", "--symbols", "10", "--loss", "ge:0.1,2,0,1"], "invalid probability");
+}
+
+#[test]
+fn encode_and_decode_reject_invalid_arguments() {
+    let input = tmp_file("cli-input", b"some data");
+    let input = input.to_str().unwrap();
+    let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli-output");
+    let output = output.to_str().unwrap();
+    assert_usage_error(&["encode", input, output, "--symbol-size", "0"], "--symbol-size");
+    assert_usage_error(&["encode", input, output, "--symbol-size", "100000"], "--symbol-size");
+    assert_usage_error(&["encode", input, output, "--window-size", "0"], "--window-size");
+    assert_usage_error(&["decode", input, output, "--drop-rate", "1.5"], "invalid probability");
+    assert_usage_error(&["decode", input, output, "--drop-rate=-1"], "invalid probability");
+}
+
+#[test]
+fn decode_validates_the_stream_header_before_allocating() {
+    let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli-decoded");
+    let output = output.to_str().unwrap();
+
+    let stream = tmp_file("cli-zero-symbol-size", &stream_header(0, 64, 100));
+    assert_fails(&["decode", stream.to_str().unwrap(), output], "invalid symbol size 0");
+
+    let stream = tmp_file("cli-huge-window", &stream_header(1200, u32::MAX, 100));
+    assert_fails(&["decode", stream.to_str().unwrap(), output], "invalid window size");
+
+    let stream = tmp_file("cli-huge-file", &stream_header(1, 64, u64::MAX));
+    assert_fails(&["decode", stream.to_str().unwrap(), output], "announces");
+
+    let mut packets = stream_header(1200, 64, 100);
+    packets.push(0);
+    packets.extend_from_slice(&u32::MAX.to_be_bytes());
+    let stream = tmp_file("cli-huge-packet", &packets);
+    assert_fails(&["decode", stream.to_str().unwrap(), output], "bytes long");
+}
+
+#[test]
+fn inspect_reads_symbols_from_a_capture() {
+    let mut source_symbol = 7u64.to_be_bytes().to_vec();
+    source_symbol.extend_from_slice(&[0xab; 16]);
+    let capture = tmp_file("cli-inspect.pcap", &udp_capture(4433, &[&source_symbol, &source_symbol]));
+    let capture = capture.to_str().unwrap();
+
+    let output = netcode(&["inspect", "source", "--symbol-size", "16", "--pcap", capture, "--port", "4433"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(stdout.matches("source symbol").count(), 2, "{}", stdout);
+    assert_eq!(stdout.matches("payload len: 16").count(), 2, "{}", stdout);
+
+    assert_fails(&["inspect", "source", "--pcap", capture, "--port", "53"], "no UDP datagram");
+    assert_usage_error(&["inspect", "source", "00", "--pcap", capture], "cannot be used with");
+}
@@ -0,0 +1,56 @@
+use networkcoding::config::{CodecConfig, ConfigError, DENSE, Field, MAX_SYMBOL_SIZE, MAX_WINDOW_SIZE, Scheme, SeedPolicy};
+use networkcoding::simulator::RepairSchedule;
+
+#[test]
+fn builder_rejects_invalid_combinations() {
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, 0, 64).build(), Err(ConfigError::InvalidSize));
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, 1200, 0).build(), Err(ConfigError::InvalidSize));
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, MAX_SYMBOL_SIZE + 1, 64).build(), Err(ConfigError::InvalidSize));
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, usize::MAX, 64).build(), Err(ConfigError::InvalidSize));
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, 1200, MAX_WINDOW_SIZE + 1).build(), Err(ConfigError::InvalidSize));
+    assert!(CodecConfig::builder(Scheme::Vlc, MAX_SYMBOL_SIZE, MAX_WINDOW_SIZE).build().is_ok());
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, 1200, 64).density_threshold(16).build(), Err(ConfigError::InvalidDensity(16)));
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, 1200, 64).density_threshold(3).build(), Err(ConfigError::UnsupportedDensity(Scheme::Vlc, 3)));
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, 1200, 64).field(Field::Gf2).build(), Err(ConfigError::UnsupportedField(Scheme::Vlc, Field::Gf2)));
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, 1200, 64).seed(SeedPolicy::Fixed(1)).build(), Err(ConfigError::UnexpectedSeed));
+    assert_eq!(CodecConfig::builder(Scheme::Vlc, 1200, 64).redundancy(RepairSchedule { interval: 0, count: 1 }).build(), Err(ConfigError::InvalidRedundancy));
+    #[cfg(not(feature = "enable-rlc"))]
+    assert_eq!(CodecConfig::builder(Scheme::Rlc, 1200, 64).build(), Err(ConfigError::UnsupportedScheme(Scheme::Rlc)));
+}
+
+#[test]
+fn builder_fills_the_defaults() {
+    let config = CodecConfig::builder(Scheme::Vlc, 1200, 64).build().unwrap();
+    assert_eq!(config.field, Field::Gf256);
+    assert_eq!(config.density_threshold, DENSE);
+    assert_eq!(config.seed, SeedPolicy::Random);
+    assert_eq!(config.redundancy, RepairSchedule::default());
+    let (encoder, decoder) = config.build().unwrap();
+    assert_eq!(encoder.symbol_size(), 1200);
+    assert_eq!(decoder.symbol_size(), 1200);
+}
+
+#[cfg(feature = "enable-rlc")]
+#[test]
+fn fixed_seeds_are_kept() {
+    use networkcoding::simulator::Codec;
+
+    let config = CodecConfig::builder(Scheme::Rlc, 1200, 64).seed(SeedPolicy::Fixed(42)).build().unwrap();
+    assert_eq!(config.codec(), Ok(Codec::RLC { seed: 42 }));
+    assert_eq!(CodecConfig::new(Codec::RLC { seed: 42 }, 1200, 64), config);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn configs_load_from_toml() {
+    let config = CodecConfig::from_toml(r#"
+        scheme = "vlc"
+        symbol_size = 1200
+        window_size = 64
+        redundancy = { interval = 4, count = 2 }
+    "#).unwrap();
+    assert_eq!(config, CodecConfig::builder(Scheme::Vlc, 1200, 64).redundancy(RepairSchedule { interval: 4, count: 2 }).build().unwrap());
+
+    assert!(matches!(CodecConfig::from_toml("scheme = \"vlc\"\nsymbol_size = 1200\nwindow_size = 64\nwindows = 3"), Err(ConfigError::Toml(_))));
+    assert_eq!(CodecConfig::from_toml("scheme = \"vlc\"\nsymbol_size = 1200\nwindow_size = 64\ndensity_threshold = 7"), Err(ConfigError::UnsupportedDensity(Scheme::Vlc, 7)));
+}
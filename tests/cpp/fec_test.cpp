@@ -0,0 +1,85 @@
+// Exercises the fec.hpp wrapper: protects two symbols, loses one and recovers it.
+#include "fec.hpp"
+
+#include <cstdio>
+#include <cstdlib>
+
+#define CHECK(cond)                                                              \
+    do {                                                                         \
+        if (!(cond)) {                                                           \
+            std::fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+            std::exit(1);                                                        \
+        }                                                                        \
+    } while (0)
+
+int main() {
+    const size_t symbol_size = 64;
+    auto encoder = fec::Encoder::vlc(symbol_size, 16);
+    auto decoder = fec::Decoder::vlc(symbol_size, 16);
+    CHECK(encoder.symbol_size() == symbol_size);
+    CHECK(decoder.symbol_size() == symbol_size);
+
+    std::vector<uint8_t> lost(symbol_size, 1);
+    std::vector<uint8_t> received(symbol_size, 2);
+    fec::metadata_t lost_md = encoder.protect(lost);
+    fec::metadata_t received_md = encoder.protect(received);
+    CHECK(encoder.can_send_repair_symbols());
+
+    std::vector<uint8_t> repair(symbol_size + 64);
+    size_t repair_len = encoder.generate_repair_symbol(repair.data(), repair.size());
+    CHECK(decoder.repair_symbol_length(repair.data(), repair_len) == repair_len);
+
+    struct timespec now = {0, 0};
+    auto none = decoder.receive_source_symbol(received_md, received.data(), received.size(), now);
+    CHECK(none);
+    CHECK(none.empty());
+
+    size_t consumed = 0;
+    auto recovered = decoder.receive_repair_symbol(repair.data(), repair_len, &consumed);
+    CHECK(recovered);
+    CHECK(consumed == repair_len);
+
+    size_t n_recovered = 0;
+    for (const auto &symbol : recovered) {
+        CHECK(symbol.metadata == lost_md);
+        CHECK(symbol.data == lost);
+        n_recovered++;
+    }
+    CHECK(n_recovered == 1);
+    CHECK(recovered.empty());
+
+    CHECK(encoder.stats().protected_source_symbols == 2);
+    CHECK(encoder.stats().generated_repair_symbols == 1);
+    CHECK(decoder.stats().received_repair_symbols == 1);
+    CHECK(decoder.stats().recovered_source_symbols == 1);
+
+    bool thrown = false;
+    try {
+        uint8_t too_small[4];
+        encoder.generate_repair_symbol(too_small, sizeof(too_small));
+    } catch (const fec::error &e) {
+        thrown = e.code() < 0;
+    }
+    CHECK(thrown);
+
+    codec_config_t config = {};
+    config.scheme = 0;
+    config.field_bits = 8;
+    config.density_threshold = 15;
+    config.symbol_size = symbol_size;
+    config.window_size = 16;
+    config.repair_interval = 10;
+    config.repair_count = 1;
+    CHECK(fec::Encoder::from_config(config).symbol_size() == symbol_size);
+    CHECK(fec::Decoder::from_config(config).symbol_size() == symbol_size);
+
+    config.density_threshold = 3;
+    bool rejected = false;
+    try {
+        fec::Decoder::from_config(config);
+    } catch (const fec::error &e) {
+        rejected = e.code() < 0;
+    }
+    CHECK(rejected);
+    return 0;
+}
@@ -0,0 +1,37 @@
+#![cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Builds tests/cpp/fec_test.cpp against fec.hpp and the staticlib produced by this build, then runs it.
+#[test]
+fn cpp_wrapper() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let exe = std::env::current_exe().unwrap();
+    // target/<profile>/deps/<test binary>
+    let profile_dir = exe.parent().and_then(|deps| deps.parent()).unwrap();
+    let staticlib = profile_dir.join("libnetworkcoding.a");
+    assert!(staticlib.exists(), "{} not found", staticlib.display());
+
+    let cxx = std::env::var("CXX").unwrap_or_else(|_| "c++".to_string());
+    let out = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("fec_test");
+    let status = match Command::new(&cxx)
+        .arg("-std=c++17")
+        .arg("-Wall")
+        .arg("-I")
+        .arg(&manifest_dir)
+        .arg(manifest_dir.join("tests/cpp/fec_test.cpp"))
+        .arg(&staticlib)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&out)
+        .status()
+    {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("skipping C++ wrapper test, cannot run {}: {}", cxx, err);
+            return;
+        }
+    };
+    assert!(status.success(), "failed to compile the C++ wrapper test");
+    assert!(Command::new(&out).status().unwrap().success(), "C++ wrapper test failed");
+}
@@ -0,0 +1,117 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use networkcoding::events::{EventSink, FecEvent, QlogWriter, Role};
+use networkcoding::simulator::Codec;
+use networkcoding::{source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+/// Keeps the events it receives, shared with the test
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<FecEvent>>>);
+
+impl EventSink for Recorder {
+    fn on_event(&mut self, event: &FecEvent) {
+        self.0.lock().unwrap().push(*event);
+    }
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<FecEvent> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+fn codecs() -> Vec<Codec> {
+    vec![
+        Codec::VLC,
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { seed: 42 },
+    ]
+}
+
+fn window_slid(events: &[FecEvent]) -> Vec<FecEvent> {
+    events.iter().filter(|event| matches!(event, FecEvent::WindowSlid { .. })).copied().collect()
+}
+
+#[test]
+fn qlog_writer_writes_valid_json_seq() {
+    let title = "a \"quoted\" title\\ with\na newline and a \u{1} control character";
+    let mut writer = QlogWriter::new(Vec::new(), title).unwrap();
+    writer.on_event(&FecEvent::SourceSymbolProtected { id: 3, len: 1200 });
+    writer.on_event(&FecEvent::WindowSlid { role: Role::Decoder, bounds: Some((4, 9)) });
+    writer.on_event(&FecEvent::WindowSlid { role: Role::Encoder, bounds: None });
+    let out = writer.into_inner();
+
+    assert_eq!(out[0], 0x1e);
+    let records: Vec<serde_json::Value> = out[1..].split(|byte| *byte == 0x1e)
+        .map(|record| serde_json::from_slice(record).unwrap())
+        .collect();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0]["title"], title);
+    assert_eq!(records[0]["qlog_format"], "JSON-SEQ");
+    assert_eq!(records[1]["name"], "fec:source_symbol_protected");
+    assert_eq!(records[1]["data"]["id"], 3);
+    assert_eq!(records[1]["data"]["length"], 1200);
+    assert_eq!(records[2]["name"], "fec:window_slid");
+    assert_eq!(records[2]["data"]["role"], "decoder");
+    assert_eq!((records[2]["data"]["first_id"].as_u64(), records[2]["data"]["last_id"].as_u64()), (Some(4), Some(9)));
+    assert_eq!(records[3]["data"], serde_json::json!({"role": "encoder"}));
+    assert!(records[1..].iter().all(|record| record["time"].as_f64().unwrap() >= 0.0));
+}
+
+#[test]
+fn encoder_reports_events_to_its_sink() {
+    for codec in codecs() {
+        let recorder = Recorder::default();
+        let mut encoder = codec.new_encoder(16, 8);
+        encoder.set_event_sink(Some(Box::new(recorder.clone())));
+
+        let mut ids = Vec::new();
+        for i in 0..4 {
+            let mut md = source_symbol_metadata_from_u64(0);
+            encoder.protect_data(vec![i; 16], &mut md).unwrap();
+            ids.push(source_symbol_metadata_to_u64(md));
+        }
+        let protected: Vec<FecEvent> = ids.iter().map(|id| FecEvent::SourceSymbolProtected { id: *id, len: 16 }).collect();
+        assert_eq!(recorder.take(), protected, "{:?}", codec);
+
+        encoder.remove_up_to(source_symbol_metadata_from_u64(ids[2]));
+        let slid = window_slid(&recorder.take());
+        assert_eq!(slid.len(), 1, "{:?}", codec);
+        assert!(matches!(slid[0], FecEvent::WindowSlid { role: Role::Encoder, bounds: Some((first, last)) } if first > ids[0] && last == ids[3]), "{:?}", codec);
+
+        // nothing left to remove: the bounds do not change
+        encoder.remove_up_to(source_symbol_metadata_from_u64(ids[2]));
+        assert!(window_slid(&recorder.take()).is_empty(), "{:?}", codec);
+
+        encoder.set_event_sink(None);
+        let mut md = source_symbol_metadata_from_u64(0);
+        encoder.protect_data(vec![4; 16], &mut md).unwrap();
+        assert!(recorder.take().is_empty(), "{:?}", codec);
+    }
+}
+
+#[test]
+fn decoder_reports_window_slid_only_when_the_bounds_change() {
+    for codec in codecs() {
+        let recorder = Recorder::default();
+        let mut decoder = codec.new_decoder(16, 8);
+        decoder.set_event_sink(Some(Box::new(recorder.clone())));
+
+        decoder.remove_up_to(source_symbol_metadata_from_u64(0), None);
+        assert!(recorder.take().is_empty(), "{:?}", codec);
+
+        for id in 0..4 {
+            decoder.receive_source_symbol_borrowed(source_symbol_metadata_from_u64(id), &[id as u8; 16], Instant::now()).unwrap();
+        }
+        assert!(window_slid(&recorder.take()).is_empty(), "{:?}", codec);
+
+        decoder.remove_up_to(source_symbol_metadata_from_u64(2), None);
+        let slid = window_slid(&recorder.take());
+        assert_eq!(slid.len(), 1, "{:?}", codec);
+        assert!(matches!(slid[0], FecEvent::WindowSlid { role: Role::Decoder, .. }), "{:?}", codec);
+
+        decoder.remove_up_to(source_symbol_metadata_from_u64(2), None);
+        assert!(recorder.take().is_empty(), "{:?}", codec);
+    }
+}
@@ -0,0 +1,142 @@
+#![cfg(unix)]
+
+use std::ptr;
+
+use networkcoding::ffi;
+use networkcoding::DecoderError;
+
+const SYMBOL_SIZE: usize = 16;
+const BUFFER_TOO_SMALL: isize = -2;
+const INVALID_TIMESTAMP: isize = -9;
+
+fn timespec(sec: i64, nsec: i64) -> libc::timespec {
+    let mut ts: libc::timespec = unsafe { std::mem::zeroed() };
+    ts.tv_sec = sec as libc::time_t;
+    ts.tv_nsec = nsec as libc::c_long;
+    ts
+}
+
+fn now() -> libc::timespec {
+    let mut ts = timespec(0, 0);
+    assert_eq!(unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) }, 0);
+    ts
+}
+
+/// Serialized VLC repair symbol with the given header and a zero payload
+fn repair_symbol(first_id: u64, n_protected_symbols: u32) -> Vec<u8> {
+    let mut data = first_id.to_be_bytes().to_vec();
+    data.extend_from_slice(&n_protected_symbols.to_be_bytes());
+    data.extend_from_slice(&0u64.to_be_bytes());
+    data.resize(data.len() + SYMBOL_SIZE, 0);
+    data
+}
+
+#[test]
+fn null_buffers_are_rejected() {
+    let decoder = ffi::new_vlc_decoder(SYMBOL_SIZE, 8);
+    let dec = unsafe { &mut *decoder };
+    let mut md = 0;
+    assert_eq!(ffi::decoder_read_source_symbol_metadata(dec, ptr::null_mut(), 8, &mut md), BUFFER_TOO_SMALL);
+    assert_eq!(ffi::decoder_get_repair_symbol_payload_length(dec, ptr::null_mut(), 8 + 4 + 8 + SYMBOL_SIZE), BUFFER_TOO_SMALL);
+    assert!(ffi::decoder_receive_and_deserialize_repair_symbol(dec, ptr::null_mut(), 8 + 4 + 8 + SYMBOL_SIZE, ptr::null_mut()).is_null());
+    assert!(ffi::decoder_receive_source_symbol(dec, 0, ptr::null_mut(), SYMBOL_SIZE, now()).is_null());
+    // NULL output pointers are skipped
+    let mut data = 3u64.to_be_bytes();
+    assert_eq!(ffi::decoder_read_source_symbol_metadata(dec, data.as_mut_ptr(), data.len(), ptr::null_mut()), 8);
+    let stats = ffi::decoder_stats(dec);
+    assert_eq!((stats.received_source_symbols, stats.received_repair_symbols), (0, 0));
+    ffi::destroy_decoder(decoder);
+}
+
+#[test]
+fn empty_buffers_may_be_null() {
+    let decoder = ffi::new_vlc_decoder(SYMBOL_SIZE, 8);
+    let dec = unsafe { &mut *decoder };
+    let mut md = 0;
+    assert_eq!(ffi::decoder_read_source_symbol_metadata(dec, ptr::null_mut(), 0, &mut md), BUFFER_TOO_SMALL);
+    assert_eq!(ffi::decoder_get_repair_symbol_payload_length(dec, ptr::null_mut(), 0), BUFFER_TOO_SMALL);
+    assert!(ffi::decoder_receive_and_deserialize_repair_symbol(dec, ptr::null_mut(), 0, ptr::null_mut()).is_null());
+    ffi::destroy_decoder(decoder);
+}
+
+#[test]
+fn invalid_repair_symbol_headers_are_rejected() {
+    let decoder = ffi::new_vlc_decoder(SYMBOL_SIZE, 8);
+    let dec = unsafe { &mut *decoder };
+    // an empty range, and a range wrapping around the u64 ids
+    for (first_id, n_protected_symbols) in [(0, 0), (u64::MAX, 2)] {
+        let mut data = repair_symbol(first_id, n_protected_symbols);
+        let mut consumed = 0;
+        assert!(ffi::decoder_receive_and_deserialize_repair_symbol(dec, data.as_mut_ptr(), data.len(), &mut consumed).is_null());
+        assert_eq!(consumed, 0);
+    }
+    assert_eq!(ffi::decoder_stats(dec).rejected_symbols, 2);
+    // a truncated payload
+    let mut data = repair_symbol(0, 1);
+    assert_eq!(ffi::decoder_get_repair_symbol_payload_length(dec, data.as_mut_ptr(), data.len() - 1), BUFFER_TOO_SMALL);
+    assert!(ffi::decoder_receive_and_deserialize_repair_symbol(dec, data.as_mut_ptr(), data.len() - 1, ptr::null_mut()).is_null());
+    ffi::destroy_decoder(decoder);
+}
+
+#[test]
+fn source_symbols_of_the_wrong_size_are_rejected() {
+    let decoder = ffi::new_vlc_decoder(SYMBOL_SIZE, 8);
+    let dec = unsafe { &mut *decoder };
+    for len in [0, SYMBOL_SIZE - 1, SYMBOL_SIZE + 1] {
+        let mut data = vec![0; len];
+        assert!(ffi::decoder_receive_source_symbol(dec, 0, data.as_mut_ptr(), data.len(), now()).is_null(), "{}", len);
+    }
+    assert_eq!(ffi::decoder_stats(dec).received_source_symbols, 0);
+    ffi::destroy_decoder(decoder);
+}
+
+#[test]
+fn invalid_timestamps_are_reported() {
+    let decoder = ffi::new_vlc_decoder(SYMBOL_SIZE, 8);
+    let dec = unsafe { &mut *decoder };
+    let mut data = vec![1; SYMBOL_SIZE];
+    for ts in [timespec(-1, 0), timespec(0, -1), timespec(0, 1_000_000_000)] {
+        assert!(ffi::decoder_receive_source_symbol(dec, 0, data.as_mut_ptr(), data.len(), ts).is_null());
+        let mut removed = 42;
+        assert_eq!(ffi::decoder_remove_up_to(dec, 0, ts, &mut removed), INVALID_TIMESTAMP);
+        assert_eq!(removed, 42);
+    }
+    assert_eq!(DecoderError::InvalidTimestamp.to_u64(), 8);
+
+    let buffer = ffi::decoder_receive_source_symbol(dec, 0, data.as_mut_ptr(), data.len(), now());
+    assert!(!buffer.is_null());
+    ffi::destroy_source_symbols_buffer(buffer);
+    let mut removed = u64::MAX;
+    assert_eq!(ffi::decoder_remove_up_to(dec, 1, now(), &mut removed), 0);
+    assert_ne!(removed, u64::MAX);
+    assert_eq!(ffi::decoder_remove_up_to(dec, 1, timespec(0, 0), ptr::null_mut()), 0);
+    ffi::destroy_decoder(decoder);
+}
+
+#[test]
+fn failed_codecs_leave_the_output_pointers_untouched() {
+    let mut config = ffi::FfiCodecConfig {
+        scheme: 0,
+        field_bits: 8,
+        density_threshold: 15,
+        fixed_seed: false,
+        seed: 0,
+        symbol_size: SYMBOL_SIZE,
+        window_size: 8,
+        repair_interval: 4,
+        repair_count: 1,
+    };
+    let (mut encoder, mut decoder) = (ptr::null_mut(), ptr::null_mut());
+    // an unknown scheme, then an empty symbol
+    for (scheme, symbol_size) in [(2, SYMBOL_SIZE), (0, 0)] {
+        config.scheme = scheme;
+        config.symbol_size = symbol_size;
+        assert!(ffi::new_codec(&config, &mut encoder, &mut decoder) < 0);
+        assert!(encoder.is_null() && decoder.is_null());
+    }
+    config.symbol_size = SYMBOL_SIZE;
+    assert_eq!(ffi::new_codec(&config, &mut encoder, &mut decoder), 0);
+    assert!(!encoder.is_null() && !decoder.is_null());
+    ffi::destroy_encoder(encoder);
+    ffi::destroy_decoder(decoder);
+}
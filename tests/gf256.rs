@@ -0,0 +1,75 @@
+use proptest::prelude::*;
+
+use networkcoding::gf256::{self, Backend};
+
+fn lengths() -> impl Strategy<Value = usize> {
+    // covers the tails after every SIMD width as well as full symbols
+    prop_oneof![0usize..=200, Just(1200), Just(9000)]
+}
+
+#[test]
+fn inverses() {
+    assert_eq!(gf256::inv(0), None);
+    for a in 1..=255u8 {
+        assert_eq!(gf256::mul(a, gf256::inv(a).unwrap()), 1, "inverse of {}", a);
+    }
+}
+
+#[test]
+fn multiplication_by_x_reduces_with_the_polynomial() {
+    assert_eq!(gf256::mul(0x80, 2), 0x1d);
+    assert_eq!(gf256::mul(0x53, 0xca), gf256::mul(0xca, 0x53));
+}
+
+proptest! {
+    #[test]
+    fn backends_agree_on_mul_add(len in lengths(), c in any::<u8>(), seed in any::<u64>()) {
+        let mut state = seed | 1;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        };
+        let src: Vec<u8> = (0..len).map(|_| next()).collect();
+        let dst: Vec<u8> = (0..len).map(|_| next()).collect();
+        let expected: Vec<u8> = dst.iter().zip(&src).map(|(d, s)| d ^ gf256::mul(c, *s)).collect();
+        for backend in Backend::supported() {
+            let mut out = dst.clone();
+            gf256::region_mul_add_with(backend, &mut out, &src, c);
+            prop_assert_eq!(&out, &expected, "backend {}", backend.name());
+        }
+    }
+
+    #[test]
+    fn backends_agree_on_mul(data in prop::collection::vec(any::<u8>(), 0..300), c in any::<u8>()) {
+        let expected: Vec<u8> = data.iter().map(|x| gf256::mul(c, *x)).collect();
+        for backend in Backend::supported() {
+            let mut out = data.clone();
+            gf256::region_mul_with(backend, &mut out, c);
+            prop_assert_eq!(&out, &expected, "backend {}", backend.name());
+        }
+    }
+
+    #[test]
+    fn eliminate_matches_row_by_row(n_rows in 0usize..64, len in lengths(), seed in any::<u64>()) {
+        let mut state = seed | 1;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        };
+        let pivot: Vec<u8> = (0..len).map(|_| next()).collect();
+        let coefficients: Vec<u8> = (0..n_rows).map(|_| next()).collect();
+        let rows: Vec<Vec<u8>> = (0..n_rows).map(|_| (0..len).map(|_| next()).collect()).collect();
+        let mut expected = rows.clone();
+        for (row, c) in expected.iter_mut().zip(&coefficients) {
+            gf256::region_mul_add_with(Backend::Portable, row, &pivot, *c);
+        }
+
+        let mut eliminated = rows;
+        gf256::eliminate(&mut eliminated.iter_mut().map(|row| row.as_mut_slice()).collect::<Vec<_>>(), &pivot, &coefficients);
+        prop_assert_eq!(&eliminated, &expected);
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Instant;
+
+use networkcoding::limits::DecoderLimits;
+use networkcoding::simulator::Codec;
+use networkcoding::snapshot::DecoderSnapshot;
+use networkcoding::{Decoder, DecoderError, source_symbol_metadata_from_u64};
+
+const SYMBOL_SIZE: usize = 16;
+const WINDOW_SIZE: usize = 8;
+
+fn codecs() -> Vec<Codec> {
+    vec![
+        Codec::VLC,
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { seed: 42 },
+    ]
+}
+
+/// Serialized repair symbol protecting [first_id, first_id + n_protected_symbols - 1] with a zero payload
+fn repair_symbol(codec: Codec, first_id: u64, n_protected_symbols: u32) -> Vec<u8> {
+    let mut data = first_id.to_be_bytes().to_vec();
+    match codec {
+        Codec::VLC => {
+            data.extend_from_slice(&n_protected_symbols.to_be_bytes());
+            data.extend_from_slice(&1u64.to_be_bytes());
+        }
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { .. } => {
+            data.extend_from_slice(&(n_protected_symbols as u64).to_be_bytes());
+            data.extend_from_slice(&1u32.to_be_bytes());
+        }
+    }
+    data.resize(data.len() + SYMBOL_SIZE, 0);
+    data
+}
+
+fn receive_source_symbol(decoder: &mut Decoder, id: u64) -> Result<(), DecoderError> {
+    decoder.receive_source_symbol_borrowed(source_symbol_metadata_from_u64(id), &[1; SYMBOL_SIZE], Instant::now()).map(|_| ())
+}
+
+fn receive_repair_symbol(decoder: &mut Decoder, codec: Codec, first_id: u64, n_protected_symbols: u32) -> Result<(), DecoderError> {
+    decoder.receive_repair_symbol_borrowed(&repair_symbol(codec, first_id, n_protected_symbols)).map(|_| ())
+}
+
+fn rejected(result: Result<(), DecoderError>) -> bool {
+    matches!(result, Err(DecoderError::InvalidSymbol))
+}
+
+/// Limits that only enforce the given one
+fn unbounded() -> DecoderLimits {
+    DecoderLimits {
+        max_protected_symbols: u64::MAX,
+        max_future_symbols: u64::MAX,
+        max_window_span: u64::MAX,
+        max_buffered_repair_symbols: u64::MAX,
+    }
+}
+
+#[test]
+fn limits_can_be_replaced() {
+    for codec in codecs() {
+        let mut decoder = codec.new_decoder(SYMBOL_SIZE, WINDOW_SIZE);
+        assert_eq!(decoder.limits(), DecoderLimits::new(WINDOW_SIZE), "{:?}", codec);
+        let limits = DecoderLimits { max_future_symbols: 1, ..DecoderLimits::new(WINDOW_SIZE) };
+        decoder.set_limits(limits);
+        assert_eq!(decoder.limits(), limits, "{:?}", codec);
+        assert!(rejected(receive_source_symbol(&mut decoder, 1)), "{:?}", codec);
+        assert_eq!(decoder.stats().rejected_symbols, 1, "{:?}", codec);
+    }
+}
+
+#[test]
+fn empty_windows_reject_far_future_symbols() {
+    for codec in codecs() {
+        let mut decoder = codec.new_decoder(SYMBOL_SIZE, WINDOW_SIZE);
+        // a new decoder expects the symbols to start at 0
+        assert!(rejected(receive_source_symbol(&mut decoder, WINDOW_SIZE as u64)), "{:?}", codec);
+        assert!(rejected(receive_source_symbol(&mut decoder, u64::MAX)), "{:?}", codec);
+        assert!(rejected(receive_repair_symbol(&mut decoder, codec, 1_000_000, 1)), "{:?}", codec);
+        assert_eq!(decoder.stats().rejected_symbols, 3, "{:?}", codec);
+        assert!(receive_source_symbol(&mut decoder, WINDOW_SIZE as u64 - 1).is_ok(), "{:?}", codec);
+
+        // once emptied, the window expects the symbols that follow the removed ones
+        decoder.remove_up_to(source_symbol_metadata_from_u64(100), None);
+        assert_eq!(decoder.bounds(), None, "{:?}", codec);
+        assert!(rejected(receive_source_symbol(&mut decoder, 100 + WINDOW_SIZE as u64)), "{:?}", codec);
+        assert!(receive_source_symbol(&mut decoder, 100 + WINDOW_SIZE as u64 - 1).is_ok(), "{:?}", codec);
+    }
+}
+
+#[test]
+fn symbols_far_ahead_of_the_window_are_rejected() {
+    for codec in codecs() {
+        let mut decoder = codec.new_decoder(SYMBOL_SIZE, WINDOW_SIZE);
+        decoder.set_limits(DecoderLimits { max_future_symbols: 2, ..unbounded() });
+        assert!(receive_source_symbol(&mut decoder, 0).is_ok(), "{:?}", codec);
+        assert!(rejected(receive_source_symbol(&mut decoder, 3)), "{:?}", codec);
+        assert!(rejected(receive_repair_symbol(&mut decoder, codec, 1, 3)), "{:?}", codec);
+        assert!(receive_source_symbol(&mut decoder, 2).is_ok(), "{:?}", codec);
+        assert!(receive_source_symbol(&mut decoder, 4).is_ok(), "{:?}", codec);
+    }
+}
+
+#[test]
+fn window_span_is_bounded() {
+    for codec in codecs() {
+        let mut decoder = codec.new_decoder(SYMBOL_SIZE, WINDOW_SIZE);
+        decoder.set_limits(DecoderLimits { max_window_span: 4, ..unbounded() });
+        assert!(receive_source_symbol(&mut decoder, 0).is_ok(), "{:?}", codec);
+        assert!(rejected(receive_source_symbol(&mut decoder, 4)), "{:?}", codec);
+        assert!(rejected(receive_repair_symbol(&mut decoder, codec, 2, 3)), "{:?}", codec);
+        assert!(receive_source_symbol(&mut decoder, 3).is_ok(), "{:?}", codec);
+        decoder.remove_up_to(source_symbol_metadata_from_u64(1), None);
+        assert!(receive_source_symbol(&mut decoder, 4).is_ok(), "{:?}", codec);
+    }
+}
+
+#[test]
+fn repair_symbols_protecting_too_many_symbols_are_rejected() {
+    for codec in codecs() {
+        let mut decoder = codec.new_decoder(SYMBOL_SIZE, WINDOW_SIZE);
+        decoder.set_limits(DecoderLimits { max_protected_symbols: 3, ..unbounded() });
+        assert!(rejected(receive_repair_symbol(&mut decoder, codec, 0, 4)), "{:?}", codec);
+        assert!(!rejected(receive_repair_symbol(&mut decoder, codec, 0, 3)), "{:?}", codec);
+        assert_eq!(decoder.stats().rejected_symbols, 1, "{:?}", codec);
+    }
+}
+
+#[test]
+fn buffered_repair_symbols_are_bounded() {
+    for codec in codecs() {
+        let mut decoder = codec.new_decoder(SYMBOL_SIZE, WINDOW_SIZE);
+        decoder.set_limits(DecoderLimits { max_buffered_repair_symbols: 2, ..unbounded() });
+        for id in 0..5 {
+            assert!(receive_source_symbol(&mut decoder, id).is_ok(), "{:?}", codec);
+        }
+        assert!(!rejected(receive_repair_symbol(&mut decoder, codec, 0, 2)), "{:?}", codec);
+        assert!(!rejected(receive_repair_symbol(&mut decoder, codec, 0, 3)), "{:?}", codec);
+        assert!(rejected(receive_repair_symbol(&mut decoder, codec, 0, 3)), "{:?}", codec);
+        // the repair symbols only protecting removed symbols no longer count
+        decoder.remove_up_to(source_symbol_metadata_from_u64(2), None);
+        assert!(!rejected(receive_repair_symbol(&mut decoder, codec, 2, 2)), "{:?}", codec);
+        assert!(rejected(receive_repair_symbol(&mut decoder, codec, 2, 2)), "{:?}", codec);
+    }
+}
+
+#[test]
+fn snapshots_keep_the_start_of_empty_windows() {
+    for codec in codecs() {
+        let mut decoder = codec.new_decoder(SYMBOL_SIZE, WINDOW_SIZE);
+        decoder.set_snapshots(true);
+        decoder.remove_up_to(source_symbol_metadata_from_u64(100), None);
+        let mut data = Vec::new();
+        decoder.snapshot().unwrap().write(&mut data).unwrap();
+        let snapshot = DecoderSnapshot::read(&mut data.as_slice()).unwrap();
+        assert_eq!(snapshot.window_start, 100, "{:?}", codec);
+        let mut restored = Decoder::restore(&snapshot, Instant::now()).unwrap();
+        assert!(rejected(receive_source_symbol(&mut restored, 100 + WINDOW_SIZE as u64)), "{:?}", codec);
+        assert!(receive_source_symbol(&mut restored, 100).is_ok(), "{:?}", codec);
+    }
+}
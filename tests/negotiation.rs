@@ -0,0 +1,89 @@
+use networkcoding::config::{Field, MAX_SYMBOL_SIZE, MAX_WINDOW_SIZE, Scheme};
+use networkcoding::negotiation::{FecCapabilities, FecScheme, NegotiationError, Role};
+
+fn scheme(scheme: Scheme, field: Field, max_symbol_size: usize, max_window_size: usize) -> FecScheme {
+    FecScheme { scheme, field, max_symbol_size, max_window_size }
+}
+
+#[test]
+fn capabilities_roundtrip_through_sdp_and_bytes() {
+    let capabilities = FecCapabilities {
+        schemes: vec![
+            scheme(Scheme::Rlc, Field::Gf256, 1200, 64),
+            scheme(Scheme::Rlc, Field::Gf2, 9000, 20000),
+            scheme(Scheme::Vlc, Field::Gf256, 1400, 32),
+        ],
+    };
+    let sdp = capabilities.to_sdp();
+    assert!(sdp.starts_with("a=fec-repair-flow: encoding-id=9; preference-lvl=0; ss-fssi=E:1200,W:64\r\n"));
+    assert_eq!(FecCapabilities::from_sdp(&sdp).unwrap(), capabilities);
+    assert_eq!(FecCapabilities::from_bytes(&capabilities.to_bytes()).unwrap(), capabilities);
+}
+
+#[test]
+fn sdp_parsing_follows_the_preference_levels() {
+    let sdp = "v=0\r\n\
+               m=application 30000 RTP/AVP 110\r\n\
+               a=fec-repair-flow: encoding-id=250; preference-lvl=1; ss-fssi=W:16,E:1000\r\n\
+               a=fec-repair-flow: encoding-id=6; preference-lvl=0; ss-fssi=E:1000,S:0,m:16\r\n\
+               a=fec-repair-flow: encoding-id=9; preference-lvl=0; ss-fssi=E:1200,W:64\r\n";
+    let capabilities = FecCapabilities::from_sdp(sdp).unwrap();
+    assert_eq!(capabilities.schemes, vec![scheme(Scheme::Rlc, Field::Gf256, 1200, 64), scheme(Scheme::Vlc, Field::Gf256, 1000, 16)]);
+
+    let missing_size = "a=fec-repair-flow: encoding-id=9; ss-fssi=E:1200";
+    assert!(matches!(FecCapabilities::from_sdp(missing_size), Err(NegotiationError::BadSdp(_))));
+}
+
+#[test]
+fn truncated_or_unknown_blobs_are_rejected() {
+    let bytes = FecCapabilities::local(1200, 64).to_bytes();
+    for len in 0..bytes.len() {
+        assert_eq!(FecCapabilities::from_bytes(&bytes[..len]), Err(NegotiationError::Truncated));
+    }
+    let mut future = bytes.clone();
+    future[0] = 2;
+    assert_eq!(FecCapabilities::from_bytes(&future), Err(NegotiationError::UnsupportedVersion(2)));
+}
+
+#[test]
+fn negotiation_picks_the_common_scheme_and_the_smallest_sizes() {
+    let local = FecCapabilities::local(1400, 64);
+    let remote = FecCapabilities {
+        schemes: vec![scheme(Scheme::Rlc, Field::Gf2, 1200, 128), scheme(Scheme::Vlc, Field::Gf256, 1200, 128)],
+    };
+    let config = local.negotiate(&remote, Role::Offerer).unwrap();
+    assert_eq!((config.scheme, config.field, config.symbol_size, config.window_size), (Scheme::Vlc, Field::Gf256, 1200, 64));
+
+    let config = FecCapabilities::local(usize::MAX, usize::MAX).negotiate(&FecCapabilities::local(usize::MAX, usize::MAX), Role::Offerer).unwrap();
+    assert_eq!((config.symbol_size, config.window_size), (MAX_SYMBOL_SIZE, MAX_WINDOW_SIZE));
+
+    let remote = FecCapabilities { schemes: vec![scheme(Scheme::Rlc, Field::Gf2, 1200, 128)] };
+    assert_eq!(local.negotiate(&remote, Role::Offerer), Err(NegotiationError::NoCommonScheme));
+
+    #[cfg(feature = "enable-rlc")]
+    {
+        let remote = FecCapabilities::local(9000, 16);
+        let config = local.negotiate(&remote, Role::Offerer).unwrap();
+        assert_eq!((config.scheme, config.symbol_size, config.window_size), (Scheme::Rlc, 1400, 16));
+    }
+}
+
+#[test]
+fn both_peers_agree_on_the_offerer_preference() {
+    let a = FecCapabilities {
+        schemes: vec![scheme(Scheme::Vlc, Field::Gf256, 1400, 64), scheme(Scheme::Rlc, Field::Gf256, 1200, 32)],
+    };
+    let b = FecCapabilities {
+        schemes: vec![scheme(Scheme::Rlc, Field::Gf256, 9000, 16), scheme(Scheme::Vlc, Field::Gf256, 1000, 128)],
+    };
+    for (offer, answer) in [(&a, &b), (&b, &a)] {
+        assert_eq!(offer.negotiate(answer, Role::Offerer), answer.negotiate(offer, Role::Answerer));
+    }
+    let config = a.negotiate(&b, Role::Offerer).unwrap();
+    assert_eq!((config.scheme, config.symbol_size, config.window_size), (Scheme::Vlc, 1000, 64));
+    #[cfg(feature = "enable-rlc")]
+    {
+        let config = a.negotiate(&b, Role::Answerer).unwrap();
+        assert_eq!((config.scheme, config.symbol_size, config.window_size), (Scheme::Rlc, 1200, 16));
+    }
+}
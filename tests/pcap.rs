@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use networkcoding::pcap::{self, ExtractionRule, PayloadKind};
+use networkcoding::simulator::Codec;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+const SOURCE_PORT: u16 = 4433;
+
+/// (timestamp, destination port, last byte of the source symbol id) of each captured UDP datagram
+fn datagrams(packets: &[pcap::CapturedPacket]) -> Vec<(Duration, u16, u8)> {
+    packets.iter()
+        .filter_map(|packet| pcap::udp_datagram(packet.link_type, &packet.data).map(|datagram| (packet.timestamp, datagram.dst_port, datagram.payload[7])))
+        .collect()
+}
+
+#[test]
+fn reads_classic_pcap() {
+    let packets = pcap::read_file(format!("{}/udp.pcap", FIXTURES)).unwrap();
+    assert_eq!(packets.len(), 4);
+    assert!(packets.iter().all(|packet| packet.link_type == 1));
+    let epoch = Duration::from_secs(1_700_000_000);
+    assert_eq!(datagrams(&packets), vec![
+        (epoch + Duration::from_micros(300), SOURCE_PORT, 2),
+        (epoch + Duration::from_micros(100), SOURCE_PORT, 0),
+        (epoch + Duration::from_micros(200), SOURCE_PORT, 1),
+    ]);
+    // the last frame is ARP
+    assert_eq!(packets[3].timestamp, epoch + Duration::from_micros(400));
+    assert!(pcap::udp_datagram(packets[3].link_type, &packets[3].data).is_none());
+}
+
+#[test]
+fn reads_pcapng_with_fine_timestamp_resolutions() {
+    let packets = pcap::read_file(format!("{}/udp.pcapng", FIXTURES)).unwrap();
+    assert_eq!(packets.iter().map(|packet| packet.link_type).collect::<Vec<_>>(), vec![1, 101, 101]);
+    // the first interface has a nanosecond resolution, the second one a picosecond resolution
+    assert_eq!(packets.iter().map(|packet| packet.timestamp).collect::<Vec<_>>(), vec![
+        Duration::from_millis(1500),
+        Duration::from_secs(1),
+        Duration::from_millis(2250),
+    ]);
+    let datagrams = datagrams(&packets);
+    assert_eq!(datagrams.iter().map(|(_, port, _)| *port).collect::<Vec<_>>(), vec![SOURCE_PORT, SOURCE_PORT, 4434]);
+}
+
+#[test]
+fn replay_follows_the_capture_timestamps() {
+    let rules = [
+        ExtractionRule { port: SOURCE_PORT, offset: 0, kind: PayloadKind::Source },
+        ExtractionRule { port: 4434, offset: 0, kind: PayloadKind::Repair },
+    ];
+    for capture in ["udp.pcap", "udp.pcapng"] {
+        let packets = pcap::read_file(format!("{}/{}", FIXTURES, capture)).unwrap();
+        let mut sorted = packets.clone();
+        sorted.sort_by_key(|packet| packet.timestamp);
+
+        // a window of one symbol: replaying the packets in capture order would receive expired symbols
+        let reports: Vec<pcap::ReplayReport> = [&packets, &sorted].iter()
+            .map(|packets| pcap::replay(&mut Codec::VLC.new_decoder(16, 1), packets, &rules, 1))
+            .collect();
+        for report in &reports {
+            assert_eq!(report.packets, packets.len(), "{}", capture);
+            assert!(report.missing.is_empty(), "{}: {:?}", capture, report.missing);
+        }
+        assert_eq!(reports[0].source_symbols, reports[1].source_symbols, "{}", capture);
+        assert_eq!(reports[0].invalid_packets, reports[1].invalid_packets, "{}", capture);
+        assert_eq!(reports[0].recovered, reports[1].recovered, "{}", capture);
+    }
+}
+
+/// Raw IPv4 frame of a UDP datagram to SOURCE_PORT carrying a source symbol
+fn source_packet(timestamp_us: u64, id: u64, len: usize) -> pcap::CapturedPacket {
+    let mut payload = id.to_be_bytes().to_vec();
+    payload.resize(8 + len, id as u8);
+    let mut data = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2];
+    data.extend_from_slice(&1234u16.to_be_bytes());
+    data.extend_from_slice(&SOURCE_PORT.to_be_bytes());
+    data.extend_from_slice(&(8 + payload.len() as u16).to_be_bytes());
+    data.extend_from_slice(&[0, 0]);
+    data.extend_from_slice(&payload);
+    let total_len = data.len() as u16;
+    data[2..4].copy_from_slice(&total_len.to_be_bytes());
+    pcap::CapturedPacket { timestamp: Duration::from_micros(timestamp_us), link_type: 101, data }
+}
+
+#[test]
+fn replay_rejects_stray_and_misshapen_source_symbols() {
+    let rules = [ExtractionRule { port: SOURCE_PORT, offset: 0, kind: PayloadKind::Source }];
+    let packets = [
+        source_packet(1, 0, 16),
+        // too far ahead of the window for the decoder limits
+        source_packet(2, 1 << 63, 16),
+        source_packet(3, 1, 16),
+        source_packet(4, 3, 16),
+        // truncated and oversized payloads are not padded or cut to the symbol size
+        source_packet(5, 4, 15),
+        source_packet(6, 5, 17),
+    ];
+    let report = pcap::replay(&mut Codec::VLC.new_decoder(16, 16), &packets, &rules, 16);
+    assert_eq!((report.packets, report.source_symbols, report.invalid_packets), (6, 6, 3));
+    assert_eq!(report.missing, vec![2]);
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "python")]
+
+use pyo3::ffi::c_str;
+use pyo3::prelude::*;
+
+use networkcoding::python::networkcoding as module;
+
+#[test]
+fn python_roundtrip() {
+    pyo3::append_to_inittab!(module);
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        py.run(c_str!(r#"
+import networkcoding
+enc = networkcoding.Encoder.vlc(16, 8)
+dec = networkcoding.Decoder.vlc(16, 8)
+md = enc.protect_data(bytes(range(16)))
+assert networkcoding.metadata_to_int(md) == 0
+repair = enc.generate_repair_symbol()
+consumed, recovered = dec.receive_repair_symbol(repair.data)
+assert consumed == len(repair.data)
+assert [(s.metadata, s.data) for s in recovered] == [(md, bytes(range(16)))]
+try:
+    dec.remove_up_to(b"short")
+    raise AssertionError("bad metadata accepted")
+except networkcoding.DecoderError:
+    pass
+try:
+    enc.remove_up_to(b"short")
+    raise AssertionError("bad metadata accepted")
+except networkcoding.EncoderError:
+    pass
+for size, window in [(0, 8), (16, 0), (65536, 8), (16, 65537)]:
+    try:
+        networkcoding.Encoder.vlc(size, window)
+        raise AssertionError("invalid sizes accepted")
+    except ValueError:
+        pass
+    try:
+        networkcoding.Decoder.vlc(size, window)
+        raise AssertionError("invalid sizes accepted")
+    except ValueError:
+        pass
+"#), None, None).unwrap();
+    });
+}
@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use proptest::prelude::*;
+
+use networkcoding::config::CodecConfig;
+use networkcoding::simulator::Codec;
+use networkcoding::snapshot::{DecoderSnapshot, EncoderSnapshot, SnapshotError};
+use networkcoding::tag::{RepairSymbolTag, TAG_LEN};
+use networkcoding::{Decoder, DecoderError, Encoder, EncoderError, ReceivedPacket, RepairSymbol, SourceSymbol, source_symbol_metadata_from_u64, source_symbol_metadata_to_u64};
+
+/// Every backend of the crate, new backends must be added here
+fn codecs() -> Vec<Codec> {
+    vec![
+        Codec::VLC,
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { seed: 42 },
+    ]
+}
+
+/// Number of repair symbols needed on top of the lost ones for the recovery to be certain.
+/// VLC repair symbols are always independent, RLC ones are independent with a high probability
+fn repair_margin(codec: Codec) -> usize {
+    match codec {
+        Codec::VLC => 0,
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { .. } => 2,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Scenario {
+    symbol_size: usize,
+    window_size: usize,
+    data: Vec<Vec<u8>>,
+    /// for each source symbol, whether it is lost
+    source_losses: Vec<bool>,
+    /// for each repair symbol, whether it is lost
+    repair_losses: Vec<bool>,
+}
+
+fn scenario() -> impl Strategy<Value = Scenario> {
+    (1usize..=64, 1usize..=32)
+        .prop_flat_map(|(symbol_size, window_size)| {
+            (1..=window_size).prop_flat_map(move |n| {
+                (
+                    prop::collection::vec(prop::collection::vec(any::<u8>(), symbol_size), n),
+                    prop::collection::vec(prop::bool::weighted(0.3), n),
+                    prop::collection::vec(prop::bool::weighted(0.3), 0..=n + 3),
+                ).prop_map(move |(data, source_losses, repair_losses)| Scenario {
+                    symbol_size,
+                    window_size,
+                    data,
+                    source_losses,
+                    repair_losses,
+                })
+            })
+        })
+}
+
+enum Packet {
+    Source(SourceSymbol),
+    Repair(RepairSymbol),
+}
+
+/// Protects the data of the scenario and returns the packets that are not lost, source symbols first
+fn encode(codec: Codec, scenario: &Scenario) -> (Encoder, Vec<Packet>, HashMap<u64, Vec<u8>>) {
+    encode_with(codec.new_encoder(scenario.symbol_size, scenario.window_size), scenario)
+}
+
+fn encode_with(mut encoder: Encoder, scenario: &Scenario) -> (Encoder, Vec<Packet>, HashMap<u64, Vec<u8>>) {
+    let mut packets = Vec::new();
+    let mut lost = HashMap::new();
+    for (data, lost_symbol) in scenario.data.iter().zip(&scenario.source_losses) {
+        let mut md = source_symbol_metadata_from_u64(0);
+        encoder.protect_data(data.clone(), &mut md).unwrap();
+        if *lost_symbol {
+            lost.insert(source_symbol_metadata_to_u64(md), data.clone());
+        } else {
+            packets.push(Packet::Source(SourceSymbol::new(md, data.clone())));
+        }
+    }
+    for lost_symbol in &scenario.repair_losses {
+        let repair_symbol = encoder.generate_and_serialize_repair_symbol().unwrap();
+        if !lost_symbol {
+            packets.push(Packet::Repair(repair_symbol));
+        }
+    }
+    (encoder, packets, lost)
+}
+
+/// Feeds the packets to the decoder and returns the recovered symbols
+fn decode(decoder: &mut Decoder, packets: Vec<Packet>) -> Vec<SourceSymbol> {
+    let now = Instant::now();
+    let mut recovered = Vec::new();
+    for packet in packets {
+        let res = match packet {
+            Packet::Source(symbol) => decoder.receive_source_symbol(symbol, now),
+            Packet::Repair(repair_symbol) => {
+                let (_, repair_symbol) = decoder.read_repair_symbol(repair_symbol.get()).unwrap();
+                decoder.receive_and_deserialize_repair_symbol(repair_symbol).map(|(_, r)| r)
+            }
+        };
+        match res {
+            Ok(symbols) => recovered.extend(symbols),
+            Err(DecoderError::UnusedSourceSymbol) | Err(DecoderError::UnusedRepairSymbol) => (),
+            Err(err) => panic!("unexpected decoder error: {:?}", err),
+        }
+    }
+    recovered
+}
+
+/// Same as decode with the borrowing API, feeding the serialized repair symbols directly
+fn decode_borrowed(decoder: &mut Decoder, packets: &[Packet]) -> Vec<(u64, Vec<u8>)> {
+    let now = Instant::now();
+    let mut recovered = Vec::new();
+    for packet in packets {
+        let res = match packet {
+            Packet::Source(symbol) => decoder.receive_source_symbol_borrowed(symbol.metadata(), symbol.get(), now),
+            Packet::Repair(repair_symbol) => decoder.receive_repair_symbol_borrowed(repair_symbol.get()).map(|(_, r)| r),
+        };
+        match res {
+            Ok(symbols) => recovered.extend(symbols.map(|(md, data)| (source_symbol_metadata_to_u64(md), data.to_vec()))),
+            Err(DecoderError::UnusedSourceSymbol) | Err(DecoderError::UnusedRepairSymbol) => (),
+            Err(err) => panic!("unexpected decoder error: {:?}", err),
+        }
+    }
+    recovered
+}
+
+proptest! {
+    #[test]
+    fn recovers_exactly_the_lost_symbols(scenario in scenario()) {
+        for codec in codecs() {
+            let (_, packets, lost) = encode(codec, &scenario);
+            let n_repair = scenario.repair_losses.iter().filter(|lost| !**lost).count();
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            let recovered = decode(&mut decoder, packets);
+
+            let mut recovered_ids = HashMap::new();
+            for symbol in recovered {
+                let id = source_symbol_metadata_to_u64(symbol.metadata());
+                prop_assert!(lost.contains_key(&id), "{:?}: symbol {} recovered but not lost", codec, id);
+                prop_assert_eq!(&lost[&id], symbol.get(), "{:?}: symbol {} badly recovered", codec, id);
+                prop_assert!(recovered_ids.insert(id, ()).is_none(), "{:?}: symbol {} recovered twice", codec, id);
+            }
+            if n_repair >= lost.len() + repair_margin(codec) {
+                prop_assert_eq!(recovered_ids.len(), lost.len(), "{:?}: {} repair symbols for {} lost symbols", codec, n_repair, lost.len());
+            }
+        }
+    }
+
+    #[test]
+    fn recovers_reordered_packets(scenario in scenario(), order_seed in any::<u64>()) {
+        for codec in codecs() {
+            let (_, mut packets, lost) = encode(codec, &scenario);
+            let n_repair = scenario.repair_losses.iter().filter(|lost| !**lost).count();
+            // deterministic Fisher-Yates shuffle driven by the generated seed
+            let mut state = order_seed | 1;
+            for i in (1..packets.len()).rev() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                packets.swap(i, (state % (i as u64 + 1)) as usize);
+            }
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            let recovered = decode(&mut decoder, packets);
+
+            let mut recovered_lost = 0;
+            for symbol in recovered {
+                let id = source_symbol_metadata_to_u64(symbol.metadata());
+                let index = (id - first_id(codec, &scenario)) as usize;
+                prop_assert_eq!(&scenario.data[index], symbol.get(), "{:?}: symbol {} badly recovered", codec, id);
+                if lost.contains_key(&id) {
+                    recovered_lost += 1;
+                }
+            }
+            if n_repair >= lost.len() + repair_margin(codec) {
+                prop_assert_eq!(recovered_lost, lost.len());
+            }
+        }
+    }
+
+    #[test]
+    fn borrowing_api_recovers_the_same_symbols(scenario in scenario()) {
+        for codec in codecs() {
+            let (_, packets, _) = encode(codec, &scenario);
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            let borrowed = decode_borrowed(&mut decoder, &packets);
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            let owned: Vec<(u64, Vec<u8>)> = decode(&mut decoder, packets).into_iter()
+                .map(|symbol| (source_symbol_metadata_to_u64(symbol.metadata()), symbol.take()))
+                .collect();
+            prop_assert_eq!(borrowed, owned, "{:?}", codec);
+        }
+    }
+
+    #[test]
+    fn batch_api_recovers_the_same_symbols(scenario in scenario()) {
+        for codec in codecs() {
+            let (_, packets, _) = encode(codec, &scenario);
+            let batch: Vec<ReceivedPacket> = packets.iter().map(|packet| match packet {
+                Packet::Source(symbol) => ReceivedPacket::Source(symbol.metadata(), symbol.get()),
+                Packet::Repair(repair_symbol) => ReceivedPacket::Repair(repair_symbol.get()),
+            }).collect();
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            let (recovered, errors) = decoder.receive_batch(&batch, Instant::now());
+            let batched: Vec<(u64, Vec<u8>)> = recovered.map(|(md, data)| (source_symbol_metadata_to_u64(md), data.to_vec())).collect();
+            for (_, err) in errors {
+                prop_assert!(matches!(err, DecoderError::UnusedSourceSymbol | DecoderError::UnusedRepairSymbol), "{:?}: {:?}", codec, err);
+            }
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            prop_assert_eq!(batched, decode_borrowed(&mut decoder, &packets), "{:?}", codec);
+        }
+    }
+
+    #[test]
+    fn repair_batch_in_place_matches_repair_batch(scenario in scenario(), count in 1usize..=4, slack in 0usize..=8) {
+        for codec in codecs() {
+            let (mut encoder, _, _) = encode(codec, &scenario);
+            let (mut in_place, _, _) = encode(codec, &scenario);
+            let batch = encoder.generate_repair_batch(count).unwrap();
+            let len = batch[0].get().len();
+            prop_assert!(matches!(in_place.generate_repair_batch_in_place(&mut [0; 64], len - 1), Err(EncoderError::BufferTooSmall)), "{:?}", codec);
+
+            // the trailing bytes that do not fill a segment are left untouched
+            let segment_size = len + slack;
+            let mut out = vec![0xaa; count * segment_size + segment_size - 1];
+            prop_assert_eq!(in_place.generate_repair_batch_in_place(&mut out, segment_size).unwrap(), count, "{:?}", codec);
+            for (segment, repair_symbol) in out.chunks_exact(segment_size).zip(&batch) {
+                prop_assert_eq!(&segment[..len], repair_symbol.get(), "{:?}", codec);
+            }
+            prop_assert!(out[count * segment_size..].iter().all(|b| *b == 0xaa), "{:?}", codec);
+        }
+    }
+
+    #[test]
+    fn work_budget_defers_but_recovers_the_same_symbols(scenario in scenario(), budget in 1u64..=64) {
+        for codec in codecs() {
+            let (_, packets, _) = encode(codec, &scenario);
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            let mut expected = decode_borrowed(&mut decoder, &packets);
+
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            decoder.set_work_budget(Some(budget));
+            let mut recovered = decode_borrowed(&mut decoder, &packets);
+            while decoder.pending_repair_symbols() > 0 {
+                let pending = decoder.pending_repair_symbols();
+                recovered.extend(decoder.poll_recovery().into_iter().map(|symbol| (source_symbol_metadata_to_u64(symbol.metadata()), symbol.take())));
+                prop_assert!(decoder.pending_repair_symbols() < pending, "{:?}: poll_recovery did not progress", codec);
+            }
+            expected.sort();
+            recovered.sort();
+            prop_assert_eq!(recovered, expected, "{:?}", codec);
+        }
+    }
+
+    #[test]
+    fn restored_encoder_continues_the_session(scenario in scenario(), split in any::<prop::sample::Index>()) {
+        for codec in codecs() {
+            let split = split.index(scenario.data.len() + 1);
+            let mut reference = codec.new_encoder(scenario.symbol_size, scenario.window_size);
+            let mut encoder = codec.new_encoder(scenario.symbol_size, scenario.window_size);
+            encoder.set_snapshots(true);
+            if codec == Codec::VLC {
+                // a restored VLC encoder would reuse the sequence numbers of the repair symbols already sent
+                prop_assert!(matches!(encoder.snapshot(), Err(SnapshotError::NotRestorable(Codec::VLC))));
+                continue;
+            }
+            for (i, data) in scenario.data.iter().enumerate() {
+                if i == split {
+                    let mut bytes = Vec::new();
+                    encoder.snapshot().unwrap().write(&mut bytes).unwrap();
+                    encoder = Encoder::restore(&EncoderSnapshot::read(&mut bytes.as_slice()).unwrap()).unwrap();
+                }
+                let (mut expected_md, mut md) = (source_symbol_metadata_from_u64(0), source_symbol_metadata_from_u64(0));
+                reference.protect_data(data.clone(), &mut expected_md).unwrap();
+                encoder.protect_data(data.clone(), &mut md).unwrap();
+                prop_assert_eq!(md, expected_md, "{:?}", codec);
+                let expected = reference.generate_and_serialize_repair_symbol().unwrap();
+                let repair_symbol = encoder.generate_and_serialize_repair_symbol().unwrap();
+                prop_assert_eq!(repair_symbol.get(), expected.get(), "{:?}", codec);
+            }
+            prop_assert_eq!(encoder.stats(), reference.stats(), "{:?}", codec);
+        }
+    }
+
+    #[test]
+    fn restored_decoder_recovers_the_same_symbols(scenario in scenario(), split in any::<prop::sample::Index>()) {
+        for codec in codecs() {
+            let (_, packets, _) = encode(codec, &scenario);
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            let mut expected = decode_borrowed(&mut decoder, &packets);
+
+            let split = split.index(packets.len() + 1);
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            decoder.set_snapshots(true);
+            let mut recovered = decode_borrowed(&mut decoder, &packets[..split]);
+            let snapshot = decoder.snapshot().unwrap();
+            let mut bytes = Vec::new();
+            snapshot.write(&mut bytes).unwrap();
+            prop_assert_eq!(&DecoderSnapshot::read(&mut bytes.as_slice()).unwrap(), &snapshot);
+            let mut decoder = Decoder::restore(&snapshot, Instant::now()).unwrap();
+            prop_assert_eq!(decoder.stats(), snapshot.stats, "{:?}", codec);
+            recovered.extend(decode_borrowed(&mut decoder, &packets[split..]));
+            expected.sort();
+            recovered.sort();
+            prop_assert_eq!(recovered, expected, "{:?}", codec);
+        }
+    }
+
+    #[test]
+    fn tagged_repair_symbols_recover_the_same_symbols(scenario in scenario()) {
+        for codec in codecs() {
+            let (_, packets, _) = encode(codec, &scenario);
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            let mut expected = decode_borrowed(&mut decoder, &packets);
+
+            let mut encoder = codec.new_encoder(scenario.symbol_size, scenario.window_size);
+            encoder.set_repair_symbol_tag(true);
+            let (_, packets, _) = encode_with(encoder, &scenario);
+            let tag = RepairSymbolTag::new(CodecConfig::new(codec, scenario.symbol_size, scenario.window_size).scheme);
+            for packet in &packets {
+                if let Packet::Repair(repair_symbol) = packet {
+                    prop_assert_eq!(RepairSymbolTag::parse(repair_symbol.get()).unwrap(), tag);
+                    prop_assert_eq!(repair_symbol.get().len(), TAG_LEN + 8 + 8 + 4 + scenario.symbol_size);
+                    for other in codecs().into_iter().filter(|other| *other != codec) {
+                        let mut other = other.new_decoder(scenario.symbol_size, scenario.window_size);
+                        other.set_repair_symbol_tag(true);
+                        prop_assert!(matches!(other.read_repair_symbol(repair_symbol.get()), Err(DecoderError::MismatchedCodec)));
+                    }
+                }
+            }
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            decoder.set_repair_symbol_tag(true);
+            let mut recovered = decode_borrowed(&mut decoder, &packets);
+            expected.sort();
+            recovered.sort();
+            prop_assert_eq!(recovered, expected, "{:?}", codec);
+        }
+    }
+
+    #[test]
+    fn windows_stay_consistent(scenario in scenario(), removed in any::<prop::sample::Index>()) {
+        for codec in codecs() {
+            let (mut encoder, packets, _) = encode(codec, &scenario);
+            let n = scenario.data.len() as u64;
+            let first = source_symbol_metadata_to_u64(encoder.first_metadata().unwrap());
+            let last = source_symbol_metadata_to_u64(encoder.last_metadata().unwrap());
+            prop_assert_eq!(last + 1 - first, n);
+            prop_assert_eq!(encoder.n_protected_symbols() as u64, n);
+
+            let up_to = first + removed.index(n as usize) as u64;
+            encoder.remove_up_to(source_symbol_metadata_from_u64(up_to));
+            match (encoder.first_metadata(), encoder.last_metadata()) {
+                (Some(new_first), Some(new_last)) => {
+                    prop_assert_eq!(source_symbol_metadata_to_u64(new_first), up_to + 1);
+                    prop_assert_eq!(source_symbol_metadata_to_u64(new_last), last);
+                    prop_assert_eq!(encoder.n_protected_symbols() as u64, last - up_to);
+                }
+                (None, None) => prop_assert_eq!(up_to, last),
+                bounds => prop_assert!(false, "{:?}: inconsistent encoder bounds {:?}", codec, bounds),
+            }
+
+            let mut decoder = codec.new_decoder(scenario.symbol_size, scenario.window_size);
+            let now = Instant::now();
+            let mut received = Vec::new();
+            for packet in packets {
+                if let Packet::Source(symbol) = packet {
+                    received.push(source_symbol_metadata_to_u64(symbol.metadata()));
+                    decoder.receive_source_symbol(symbol, now).unwrap();
+                    let (dec_first, dec_last) = decoder.bounds().unwrap();
+                    let (dec_first, dec_last) = (source_symbol_metadata_to_u64(dec_first), source_symbol_metadata_to_u64(dec_last));
+                    prop_assert!(dec_first <= dec_last);
+                    prop_assert!(received.iter().all(|id| (dec_first..=dec_last).contains(id)));
+                }
+            }
+            if let Some((_, dec_last)) = decoder.bounds() {
+                decoder.remove_up_to(source_symbol_metadata_from_u64(up_to), Some(now));
+                if let Some((dec_first, new_last)) = decoder.bounds() {
+                    prop_assert!(source_symbol_metadata_to_u64(dec_first) > up_to);
+                    prop_assert_eq!(new_last, dec_last);
+                }
+            }
+        }
+    }
+}
+
+/// Id of the first source symbol protected by a fresh encoder
+fn first_id(codec: Codec, scenario: &Scenario) -> u64 {
+    source_symbol_metadata_to_u64(codec.new_encoder(scenario.symbol_size, scenario.window_size).next_metadata().unwrap())
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "serde")]
+
+use serde_json::json;
+
+use networkcoding::config::CodecConfig;
+use networkcoding::simulator::Codec;
+use networkcoding::{DecoderError, EncoderError, SourceSymbol, source_symbol_metadata_from_u64};
+
+#[test]
+fn source_symbols_are_serialized_with_their_id() {
+    let symbol = SourceSymbol::new(source_symbol_metadata_from_u64(42), vec![1, 2, 3]);
+    let value = serde_json::to_value(&symbol).unwrap();
+    assert_eq!(value, json!({"id": 42, "data": [1, 2, 3]}));
+    let deserialized: SourceSymbol = serde_json::from_value(value).unwrap();
+    assert!(deserialized == symbol);
+}
+
+#[test]
+fn errors_have_stable_names() {
+    assert_eq!(serde_json::to_value(EncoderError::NoSymbolToGenerate).unwrap(), json!("no_symbol_to_generate"));
+    assert_eq!(serde_json::to_value(EncoderError::UnImplementedEncoder).unwrap(), json!("unimplemented_encoder"));
+    assert_eq!(serde_json::to_value(DecoderError::InvalidSymbol).unwrap(), json!("invalid_symbol"));
+    assert_eq!(serde_json::to_value(DecoderError::MismatchedCodec).unwrap(), json!("mismatched_codec"));
+    assert_eq!(serde_json::to_value(DecoderError::InvalidTimestamp).unwrap(), json!("invalid_timestamp"));
+    assert_eq!(serde_json::to_value(DecoderError::InternalError("oops".to_string())).unwrap(), json!({"internal_error": "oops"}));
+    let err: DecoderError = serde_json::from_value(json!("unused_repair_symbol")).unwrap();
+    assert!(matches!(err, DecoderError::UnusedRepairSymbol));
+}
+
+#[test]
+fn codec_configs_roundtrip() {
+    let config = CodecConfig::new(Codec::VLC, 1200, 64);
+    let value = serde_json::to_value(config).unwrap();
+    assert_eq!(value, json!({
+        "scheme": "vlc", "field": "gf256", "symbol_size": 1200, "window_size": 64, "density_threshold": 15,
+        "seed": "random", "redundancy": {"interval": 10, "count": 1},
+    }));
+    let minimal: CodecConfig = serde_json::from_value(json!({"scheme": "vlc", "symbol_size": 1200, "window_size": 64})).unwrap();
+    assert_eq!(minimal, config);
+    assert_eq!(serde_json::from_value::<CodecConfig>(value).unwrap(), config);
+
+    #[cfg(feature = "enable-rlc")]
+    {
+        let config = CodecConfig::new(Codec::RLC { seed: 7 }, 1200, 64);
+        let value = serde_json::to_value(config).unwrap();
+        assert_eq!(value["seed"], json!(7));
+        assert_eq!(serde_json::from_value::<CodecConfig>(value).unwrap(), config);
+    }
+}
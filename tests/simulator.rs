@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use networkcoding::simulator::{self, Bernoulli, ChannelConfig, Codec, GilbertElliott, InvalidProbability, LossModel, PeriodicBursts, RepairSchedule, SimulationConfig, SimulationError, TraceLoss};
+
+const INTERVAL: Duration = Duration::from_millis(1);
+const DELAY: Duration = Duration::from_millis(20);
+
+fn codecs() -> Vec<Codec> {
+    vec![
+        Codec::VLC,
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { seed: 42 },
+    ]
+}
+
+fn config(codec: Codec, n_source_symbols: usize, jitter: Duration, loss: Box<dyn LossModel>) -> SimulationConfig {
+    SimulationConfig {
+        codec,
+        symbol_size: 64,
+        window_size: 32,
+        n_source_symbols,
+        source_interval: INTERVAL,
+        repair: RepairSchedule { interval: 10, count: 1 },
+        channel: ChannelConfig { delay: DELAY, jitter },
+        loss,
+        seed: 42,
+        trace: None,
+    }
+}
+
+#[test]
+fn loss_models_reject_invalid_probabilities() {
+    for p in [-0.1, 1.5, f64::NAN, f64::INFINITY] {
+        assert!(Bernoulli::new(p).is_err(), "{}", p);
+        assert!(GilbertElliott::new(p, 0.5, 0.0, 1.0).is_err(), "{}", p);
+        assert!(GilbertElliott::new(0.1, p, 0.0, 1.0).is_err(), "{}", p);
+        assert!(GilbertElliott::new(0.1, 0.5, p, 1.0).is_err(), "{}", p);
+        assert!(GilbertElliott::new(0.1, 0.5, 0.0, p).is_err(), "{}", p);
+    }
+    assert!(matches!(Bernoulli::new(2.0), Err(InvalidProbability(p)) if p == 2.0));
+}
+
+#[test]
+fn loss_models_accept_the_bounds() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut never = Bernoulli::new(0.0).unwrap();
+    let mut always = Bernoulli::new(1.0).unwrap();
+    let mut bad_forever = GilbertElliott::new(1.0, 0.0, 0.0, 1.0).unwrap();
+    assert!(!never.is_lost(&mut rng));
+    assert!(always.is_lost(&mut rng));
+    // switches to the bad state at the first packet and stays there
+    assert!((0..10).all(|_| bad_forever.is_lost(&mut rng)));
+}
+
+#[test]
+fn lossless_channels_leave_nothing_to_recover() {
+    for codec in codecs() {
+        let report = simulator::run(config(codec, 100, Duration::ZERO, Box::new(Bernoulli::new(0.0).unwrap()))).unwrap();
+        assert_eq!((report.source_symbols_sent, report.repair_symbols_sent), (100, 10));
+        assert_eq!((report.source_symbols_lost, report.repair_symbols_lost, report.source_symbols_recovered), (0, 0, 0));
+        assert_eq!(report.channel_loss_rate(), 0.0);
+        assert_eq!(report.residual_loss_rate(), 0.0);
+        assert_eq!(report.overhead(), report.repair_bytes_sent as f64 / (100 * 64) as f64);
+        assert!(report.overhead() > 0.0);
+        assert_eq!(report.mean_recovery_latency(), None);
+    }
+}
+
+#[test]
+fn one_loss_per_repair_interval_is_recovered() {
+    for codec in codecs() {
+        // 10 source symbols and a repair symbol per interval: the first source symbol of each interval is lost
+        let report = simulator::run(config(codec, 100, Duration::ZERO, Box::new(PeriodicBursts::new(11, 1)))).unwrap();
+        assert_eq!((report.source_symbols_lost, report.repair_symbols_lost), (10, 0));
+        assert_eq!(report.source_symbols_recovered, 10);
+        assert_eq!(report.source_symbols_corrupted, 0);
+        assert_eq!(report.residual_loss_rate(), 0.0);
+        // recovered by the repair symbol sent with the last source symbol of the interval
+        let latency = DELAY + INTERVAL * 9;
+        assert_eq!(report.recovery_latencies, vec![latency; 10]);
+        assert_eq!((report.mean_recovery_latency(), report.max_recovery_latency()), (Some(latency), Some(latency)));
+    }
+}
+
+#[test]
+fn reordered_packets_are_recovered() {
+    for codec in codecs() {
+        // the jitter spans several source intervals, so the packets arrive out of order
+        let report = simulator::run(config(codec, 100, INTERVAL * 5, Box::new(PeriodicBursts::new(11, 1)))).unwrap();
+        assert_eq!(report.source_symbols_recovered, report.source_symbols_lost);
+        assert_eq!(report.source_symbols_corrupted, 0);
+        assert!(report.max_recovery_latency().unwrap() < DELAY + INTERVAL * 15);
+    }
+}
+
+#[test]
+fn trace_losses_follow_the_trace() {
+    for codec in codecs() {
+        // loses a source symbol in the even intervals and the repair symbol of the odd ones
+        let trace = "01000000000\n00000000001";
+        let report = simulator::run(config(codec, 100, Duration::ZERO, Box::new(TraceLoss::parse(trace)))).unwrap();
+        assert_eq!((report.source_symbols_lost, report.repair_symbols_lost), (5, 5));
+        assert_eq!(report.source_symbols_recovered, 5);
+        assert_eq!(report.residual_loss_rate(), 0.0);
+    }
+}
+
+#[test]
+fn overflowing_times_are_reported() {
+    let mut overflowing_interval = config(Codec::VLC, 3, Duration::ZERO, Box::new(Bernoulli::new(0.0).unwrap()));
+    overflowing_interval.source_interval = Duration::MAX;
+    assert!(matches!(simulator::run(overflowing_interval), Err(SimulationError::TimeOverflow)));
+
+    let mut overflowing_delay = config(Codec::VLC, 3, Duration::ZERO, Box::new(Bernoulli::new(0.0).unwrap()));
+    overflowing_delay.channel.delay = Duration::MAX;
+    overflowing_delay.channel.jitter = Duration::from_secs(1);
+    assert!(matches!(simulator::run(overflowing_delay), Err(SimulationError::TimeOverflow)));
+}
@@ -0,0 +1,120 @@
+use std::time::Instant;
+
+use networkcoding::config::{MAX_SYMBOL_SIZE, MAX_WINDOW_SIZE};
+use networkcoding::limits::DecoderLimits;
+use networkcoding::simulator::Codec;
+use networkcoding::snapshot::{DecoderSnapshot, EncoderSnapshot, SnapshotError, WindowSymbol};
+use networkcoding::{Decoder, DecoderStats, Encoder, EncoderStats};
+
+const SYMBOL_SIZE: usize = 16;
+const WINDOW_SIZE: usize = 4;
+
+fn decoder_snapshot(source_symbols: Vec<(u64, Vec<u8>)>) -> DecoderSnapshot {
+    DecoderSnapshot {
+        codec: Codec::VLC,
+        symbol_size: SYMBOL_SIZE,
+        window_size: WINDOW_SIZE,
+        stats: DecoderStats::default(),
+        limits: DecoderLimits::new(WINDOW_SIZE),
+        window_start: 0,
+        work_budget: None,
+        source_symbols,
+        repair_symbols: Vec::new(),
+        queued_repair_symbols: Vec::new(),
+    }
+}
+
+fn encoder_snapshot(n_symbols: usize) -> EncoderSnapshot {
+    EncoderSnapshot {
+        codec: Codec::VLC,
+        symbol_size: SYMBOL_SIZE,
+        window_size: WINDOW_SIZE,
+        stats: EncoderStats::default(),
+        next_id: n_symbols as u64,
+        repair_state: 0,
+        window: (0..n_symbols as u64).map(|id| WindowSymbol { id, data: vec![1; SYMBOL_SIZE], acknowledged: false, landed: false }).collect(),
+    }
+}
+
+fn write<F: FnOnce(&mut Vec<u8>) -> std::io::Result<()>>(f: F) -> Vec<u8> {
+    let mut data = Vec::new();
+    f(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn vlc_encoders_cannot_be_snapshotted() {
+    let mut encoder = Codec::VLC.new_encoder(SYMBOL_SIZE, WINDOW_SIZE);
+    encoder.set_snapshots(true);
+    assert!(matches!(encoder.snapshot(), Err(SnapshotError::NotRestorable(Codec::VLC))));
+    assert!(matches!(Encoder::restore(&encoder_snapshot(1)), Err(SnapshotError::NotRestorable(Codec::VLC))));
+}
+
+#[test]
+fn symbols_of_another_size_are_rejected() {
+    let snapshot = decoder_snapshot(vec![(0, vec![1; SYMBOL_SIZE + 1])]);
+    let data = write(|out| snapshot.write(out));
+    assert!(matches!(DecoderSnapshot::read(&mut data.as_slice()), Err(SnapshotError::InvalidSize)));
+    assert!(matches!(Decoder::restore(&snapshot, Instant::now()), Err(SnapshotError::InvalidSize)));
+
+    let mut snapshot = encoder_snapshot(1);
+    snapshot.window[0].data.pop();
+    let data = write(|out| snapshot.write(out));
+    assert!(matches!(EncoderSnapshot::read(&mut data.as_slice()), Err(SnapshotError::InvalidSize)));
+    assert!(matches!(Encoder::restore(&snapshot), Err(SnapshotError::InvalidSize)));
+}
+
+#[test]
+fn lengths_are_checked_before_allocating() {
+    let mut data = write(|out| decoder_snapshot(vec![(0, vec![1; SYMBOL_SIZE])]).write(out));
+    // the length of the only source symbol precedes its data and the two empty repair symbol lists
+    let len_offset = data.len() - 4 - 4 - SYMBOL_SIZE - 4;
+    assert_eq!(&data[len_offset..len_offset + 4], &(SYMBOL_SIZE as u32).to_be_bytes());
+    data[len_offset..len_offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(matches!(DecoderSnapshot::read(&mut data.as_slice()), Err(SnapshotError::InvalidSize)));
+}
+
+#[test]
+fn counts_must_fit_the_window() {
+    let snapshot = encoder_snapshot(WINDOW_SIZE + 1);
+    let data = write(|out| snapshot.write(out));
+    assert!(matches!(EncoderSnapshot::read(&mut data.as_slice()), Err(SnapshotError::InvalidSize)));
+    assert!(matches!(Encoder::restore(&snapshot), Err(SnapshotError::InvalidSize)));
+
+    let span = DecoderLimits::new(WINDOW_SIZE).max_window_span;
+    let snapshot = decoder_snapshot((0..=span).map(|id| (id, vec![1; SYMBOL_SIZE])).collect());
+    let data = write(|out| snapshot.write(out));
+    assert!(matches!(DecoderSnapshot::read(&mut data.as_slice()), Err(SnapshotError::InvalidSize)));
+    assert!(matches!(Decoder::restore(&snapshot, Instant::now()), Err(SnapshotError::InvalidSize)));
+
+    let snapshot = decoder_snapshot((0..span).map(|id| (id, vec![1; SYMBOL_SIZE])).collect());
+    let data = write(|out| snapshot.write(out));
+    assert_eq!(DecoderSnapshot::read(&mut data.as_slice()).unwrap(), snapshot);
+    assert!(Decoder::restore(&snapshot, Instant::now()).is_ok());
+}
+
+#[test]
+fn sizes_must_be_within_the_configuration_bounds() {
+    let mut snapshot = decoder_snapshot(Vec::new());
+    snapshot.symbol_size = MAX_SYMBOL_SIZE + 1;
+    let data = write(|out| snapshot.write(out));
+    assert!(matches!(DecoderSnapshot::read(&mut data.as_slice()), Err(SnapshotError::InvalidSize)));
+    assert!(matches!(Decoder::restore(&snapshot, Instant::now()), Err(SnapshotError::InvalidSize)));
+
+    let mut snapshot = encoder_snapshot(0);
+    snapshot.window_size = MAX_WINDOW_SIZE + 1;
+    let data = write(|out| snapshot.write(out));
+    assert!(matches!(EncoderSnapshot::read(&mut data.as_slice()), Err(SnapshotError::InvalidSize)));
+}
+
+/// The seed of the next repair symbol is derived from repair_state, not replayed from the generator
+#[cfg(feature = "enable-rlc")]
+#[test]
+fn rlc_restore_does_not_replay_the_repair_seeds() {
+    let mut snapshot = encoder_snapshot(1);
+    snapshot.codec = Codec::RLC { seed: 42 };
+    snapshot.repair_state = u64::MAX;
+    let data = write(|out| snapshot.write(out));
+    let encoder = Encoder::restore(&EncoderSnapshot::read(&mut data.as_slice()).unwrap()).unwrap();
+    assert_eq!(encoder.snapshot().unwrap().repair_state, u64::MAX);
+}
@@ -0,0 +1,58 @@
+use std::time::Instant;
+
+use networkcoding::simulator::Codec;
+use networkcoding::{DecoderError, source_symbol_metadata_from_u64};
+
+#[test]
+fn malformed_symbols_are_not_counted_as_received() {
+    let codecs = vec![
+        Codec::VLC,
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { seed: 42 },
+    ];
+    for codec in codecs {
+        let mut decoder = codec.new_decoder(16, 8);
+        assert!(matches!(decoder.receive_repair_symbol_borrowed(&[0; 8 + 8 + 4 + 15]), Err(DecoderError::BufferTooSmall)), "{:?}", codec);
+        assert!(matches!(decoder.receive_source_symbol_borrowed(source_symbol_metadata_from_u64(0), &[0; 15], Instant::now()), Err(DecoderError::BadMetadata)), "{:?}", codec);
+        let stats = decoder.stats();
+        assert_eq!((stats.received_repair_symbols, stats.received_source_symbols), (0, 0), "{:?}", codec);
+    }
+}
+
+/// The errors of the queued repair symbols are not returned, they must still show in the stats
+#[test]
+fn queued_repair_symbols_are_counted_like_direct_ones() {
+    let codecs = vec![
+        Codec::VLC,
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { seed: 42 },
+    ];
+    for codec in codecs {
+        let mut encoder = codec.new_encoder(16, 8);
+        let mut source_symbols = Vec::new();
+        for i in 0..4u8 {
+            let mut md = source_symbol_metadata_from_u64(0);
+            encoder.protect_data(vec![i; 16], &mut md).unwrap();
+            source_symbols.push((md, vec![i; 16]));
+        }
+        let stats: Vec<_> = [None, Some(1)].iter().map(|budget| {
+            let mut decoder = codec.new_decoder(16, 8);
+            decoder.set_work_budget(*budget);
+            for (md, data) in &source_symbols {
+                decoder.receive_source_symbol_borrowed(*md, data, Instant::now()).unwrap();
+            }
+            // every protected symbol was received, the repair symbols are redundant
+            for _ in 0..2 {
+                if let Ok(repair_symbol) = encoder.generate_and_serialize_repair_symbol() {
+                    let res = decoder.receive_repair_symbol_borrowed(repair_symbol.get()).map(|_| ());
+                    assert!(budget.is_none() || res.is_ok(), "{:?}: {:?}", codec, res);
+                }
+            }
+            while decoder.pending_repair_symbols() > 0 {
+                decoder.poll_recovery();
+            }
+            decoder.stats()
+        }).collect();
+        assert_eq!(stats[0], stats[1], "{:?}", codec);
+    }
+}
@@ -0,0 +1,41 @@
+use networkcoding::config::{Field, Scheme};
+use networkcoding::simulator::Codec;
+use networkcoding::tag::{RepairSymbolTag, TAG_LEN, TAG_VERSION};
+use networkcoding::DecoderError;
+
+#[test]
+fn tags_roundtrip_and_reject_unknown_codecs() {
+    let mut bytes = [0; TAG_LEN];
+    RepairSymbolTag::new(Scheme::Rlc).write(&mut bytes);
+    assert_eq!(bytes, [1, TAG_VERSION, 8, 0]);
+    assert_eq!(RepairSymbolTag::parse(&bytes).unwrap(), RepairSymbolTag { scheme: Scheme::Rlc, version: TAG_VERSION, field: Field::Gf256, flags: 0 });
+    assert!(matches!(RepairSymbolTag::parse(&bytes[..3]), Err(DecoderError::BufferTooSmall)));
+    assert!(matches!(RepairSymbolTag::parse(&[2, TAG_VERSION, 8, 0]), Err(DecoderError::InvalidSymbol)));
+    assert!(matches!(RepairSymbolTag::parse(&[0, TAG_VERSION, 4, 0]), Err(DecoderError::InvalidSymbol)));
+}
+
+#[test]
+fn tagged_decoders_reject_mismatched_symbols() {
+    let symbol_size = 16;
+    let mut decoder = Codec::VLC.new_decoder(symbol_size, 8);
+    decoder.set_repair_symbol_tag(true);
+    let mismatched = [
+        RepairSymbolTag::new(Scheme::Rlc),
+        RepairSymbolTag { version: TAG_VERSION + 1, ..RepairSymbolTag::new(Scheme::Vlc) },
+        RepairSymbolTag { field: Field::Gf2, ..RepairSymbolTag::new(Scheme::Vlc) },
+        RepairSymbolTag { flags: 1, ..RepairSymbolTag::new(Scheme::Vlc) },
+    ];
+    for tag in &mismatched {
+        let mut data = vec![0; TAG_LEN + 8 + 8 + 4 + symbol_size];
+        tag.write(&mut data);
+        assert!(matches!(decoder.read_repair_symbol(&data), Err(DecoderError::MismatchedCodec)), "{:?}", tag);
+        assert!(matches!(decoder.read_repair_symbol_header(&data), Err(DecoderError::MismatchedCodec)), "{:?}", tag);
+        assert!(matches!(decoder.receive_repair_symbol_borrowed(&data), Err(DecoderError::MismatchedCodec)), "{:?}", tag);
+    }
+    assert_eq!(decoder.stats().rejected_symbols, mismatched.len() as u64);
+
+    // untagged symbols are still parsed as before
+    let decoder = Codec::VLC.new_decoder(symbol_size, 8);
+    let data = vec![0; 8 + 8 + 4 + symbol_size];
+    assert_eq!(decoder.read_repair_symbol_header(&data).unwrap().header_len, 8 + 8 + 4);
+}
@@ -0,0 +1,147 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use networkcoding::config::{MAX_SYMBOL_SIZE, MAX_WINDOW_SIZE};
+use networkcoding::simulator::{self, Bernoulli, ChannelConfig, Codec, RepairSchedule, SimulationConfig};
+use networkcoding::trace::{self, Outcome, Trace, TraceCall, TraceEntry, TraceError, TraceHeader, TraceRecorder};
+use networkcoding::{EncoderError, SourceSymbol, source_symbol_metadata_from_u64};
+
+const SYMBOL_SIZE: usize = 32;
+const WINDOW_SIZE: usize = 8;
+
+/// A trace output that the test can read back after the simulation consumed it
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn codecs() -> Vec<Codec> {
+    vec![
+        Codec::VLC,
+        #[cfg(feature = "enable-rlc")]
+        Codec::RLC { seed: 42 },
+    ]
+}
+
+fn simulate(codec: Codec, seed: u64) -> Trace {
+    let buffer = SharedBuffer::default();
+    let config = SimulationConfig {
+        codec,
+        symbol_size: SYMBOL_SIZE,
+        window_size: WINDOW_SIZE,
+        n_source_symbols: 200,
+        source_interval: Duration::from_millis(1),
+        repair: RepairSchedule { interval: 4, count: 1 },
+        channel: ChannelConfig { delay: Duration::from_millis(10), jitter: Duration::from_millis(3) },
+        loss: Box::new(Bernoulli::new(0.1).unwrap()),
+        seed,
+        trace: Some(Box::new(buffer.clone())),
+    };
+    simulator::run(config).unwrap();
+    let data = buffer.0.lock().unwrap().clone();
+    Trace::read(&mut data.as_slice()).unwrap()
+}
+
+#[test]
+fn entries_round_trip() {
+    let header = TraceHeader { codec: Codec::VLC, symbol_size: SYMBOL_SIZE, window_size: WINDOW_SIZE };
+    let entry = |call, outcome| TraceEntry { at: Duration::from_micros(1234), call, outcome };
+    let entries = vec![
+        entry(TraceCall::ProtectData { data: vec![1; SYMBOL_SIZE] }, Outcome::Metadata(0)),
+        entry(TraceCall::GenerateRepairSymbol { up_to: None }, Outcome::RepairSymbol { len: 52, digest: 0xdead_beef }),
+        entry(TraceCall::GenerateRepairSymbol { up_to: Some(3) }, Outcome::EncoderError(2)),
+        entry(TraceCall::ReceivedSymbol { metadata: vec![0; 8] }, Outcome::Consumed(8)),
+        entry(TraceCall::EncoderRemoveUpTo { md: 2 }, Outcome::Done),
+        entry(TraceCall::SymbolLanded { md: 3 }, Outcome::Done),
+        entry(TraceCall::RemoveLandedSymbols, Outcome::Done),
+        entry(TraceCall::ReceiveSourceSymbol { md: 4, data: vec![2; SYMBOL_SIZE] }, Outcome::Recovered(vec![1, 3])),
+        entry(TraceCall::ReceiveRepairSymbol { data: vec![3; 52] }, Outcome::DecoderError(4)),
+        entry(TraceCall::DecoderRemoveUpTo { md: 5, expired: true }, Outcome::Metadata(5)),
+    ];
+    let mut data = Vec::new();
+    header.write(&mut data).unwrap();
+    entries.iter().for_each(|entry| entry.write(&mut data).unwrap());
+    assert_eq!(Trace::read(&mut data.as_slice()).unwrap(), Trace { header, entries });
+}
+
+#[test]
+fn headers_with_invalid_sizes_are_rejected() {
+    for (symbol_size, window_size) in [(0, WINDOW_SIZE), (MAX_SYMBOL_SIZE + 1, WINDOW_SIZE), (SYMBOL_SIZE, 0), (SYMBOL_SIZE, MAX_WINDOW_SIZE + 1)] {
+        let mut data = Vec::new();
+        TraceHeader { codec: Codec::VLC, symbol_size, window_size }.write(&mut data).unwrap();
+        assert!(matches!(TraceHeader::read(&mut data.as_slice()), Err(TraceError::InvalidSize)), "{} {}", symbol_size, window_size);
+    }
+}
+
+#[test]
+fn encoder_errors_have_distinct_codes() {
+    let errors = [
+        EncoderError::InternalError("internal".to_string()),
+        EncoderError::BufferTooSmall,
+        EncoderError::NoSymbolToGenerate,
+        EncoderError::BadMetadata,
+        EncoderError::UnImplementedEncoder,
+        EncoderError::NoNextMetadata,
+    ];
+    let mut codes: Vec<u64> = errors.iter().map(|err| err.to_u64()).collect();
+    codes.sort_unstable();
+    codes.dedup();
+    assert_eq!(codes.len(), errors.len());
+}
+
+#[test]
+fn recorded_calls_replay_without_divergence() {
+    for codec in codecs() {
+        let header = TraceHeader { codec, symbol_size: SYMBOL_SIZE, window_size: WINDOW_SIZE };
+        let mut encoder = codec.new_encoder(SYMBOL_SIZE, WINDOW_SIZE);
+        let mut decoder = codec.new_decoder(SYMBOL_SIZE, WINDOW_SIZE);
+        let mut recorder = TraceRecorder::new(Vec::new(), &header).unwrap();
+        let start = Instant::now();
+        for i in 0..12u8 {
+            let mut md = source_symbol_metadata_from_u64(0);
+            let data = vec![i; SYMBOL_SIZE];
+            recorder.protect_data(&mut encoder, data.clone(), &mut md).unwrap();
+            if i % 3 != 0 {
+                let at = start + Duration::from_millis(i as u64);
+                let _ = recorder.receive_source_symbol(&mut decoder, SourceSymbol::new(md, data), at);
+            }
+            if i % 4 == 3 {
+                if let Ok(repair_symbol) = recorder.generate_and_serialize_repair_symbol(&mut encoder) {
+                    let _ = recorder.receive_and_deserialize_repair_symbol(&mut decoder, repair_symbol);
+                }
+            }
+        }
+        recorder.encoder_remove_up_to(&mut encoder, source_symbol_metadata_from_u64(4));
+        recorder.decoder_remove_up_to(&mut decoder, source_symbol_metadata_from_u64(4), Some(start + Duration::from_millis(20)));
+        // malformed calls are recorded with their error
+        let _ = recorder.received_symbol(&mut encoder, &[0; 3]);
+
+        let data = recorder.finish().unwrap();
+        let trace = Trace::read(&mut data.as_slice()).unwrap();
+        assert_eq!(trace.header, header);
+        // plus the repair symbols received, if they could be generated
+        assert!(trace.entries.len() >= 12 + 8 + 3 + 3, "{:?}", codec);
+        assert!(trace.entries.iter().any(|entry| matches!(entry.outcome, Outcome::EncoderError(_))), "{:?}", codec);
+        assert_eq!(trace::replay(&trace), vec![], "{:?}", codec);
+    }
+}
+
+#[test]
+fn simulations_are_deterministic() {
+    for codec in codecs() {
+        let (first, second) = (simulate(codec, 7), simulate(codec, 7));
+        // the call times depend on the machine, not the calls and their outcomes
+        let calls = |trace: &Trace| trace.entries.iter().map(|entry| (entry.call.clone(), entry.outcome.clone())).collect::<Vec<_>>();
+        assert_eq!(calls(&first), calls(&second), "{:?}", codec);
+        assert_eq!(trace::replay(&first), vec![], "{:?}", codec);
+    }
+}
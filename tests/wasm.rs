@@ -0,0 +1,41 @@
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use networkcoding::wasm::{metadata_from_u64, metadata_to_u64, WasmDecoder, WasmEncoder};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const SYMBOL_SIZE: usize = 64;
+
+#[wasm_bindgen_test]
+fn metadata_round_trip() {
+    assert_eq!(metadata_to_u64(&metadata_from_u64(42)).unwrap(), 42);
+}
+
+#[cfg(target_os = "unknown")]
+#[wasm_bindgen_test]
+fn decoders_need_a_clock() {
+    assert!(WasmDecoder::vlc(SYMBOL_SIZE, 16).is_err());
+}
+
+// vandermonde_lc reads std::time::Instant, which panics on wasm32-unknown-unknown
+#[cfg(not(target_os = "unknown"))]
+#[wasm_bindgen_test]
+fn recover_lost_symbol() {
+    let mut encoder = WasmEncoder::vlc(SYMBOL_SIZE, 16);
+    let mut decoder = WasmDecoder::vlc(SYMBOL_SIZE, 16).unwrap();
+
+    let lost = vec![1u8; SYMBOL_SIZE];
+    let received = vec![2u8; SYMBOL_SIZE];
+    encoder.protect_data(&lost).unwrap();
+    let md = encoder.protect_data(&received).unwrap();
+    let repair = encoder.generate_repair_symbol().unwrap();
+    let repair2 = encoder.generate_repair_symbol().unwrap();
+
+    assert!(decoder.receive_source_symbol(&md, &received, 0.0).unwrap().is_empty());
+    let mut recovered = decoder.receive_repair_symbol(&repair).unwrap();
+    if recovered.is_empty() {
+        recovered = decoder.receive_repair_symbol(&repair2).unwrap();
+    }
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].data(), lost);
+    assert_eq!(metadata_to_u64(&recovered[0].metadata()).unwrap(), 0);
+}